@@ -1,35 +1,113 @@
+use std::time::Duration;
+
 use clap::Parser;
 use oxidize_infrastructure::{
-    otel, run_grpc_server, run_http_server, Cli, Commands, Environment, Registry,
+    otel, retention, run_api_key, run_config, run_export, run_grpc_server, run_http_server,
+    run_import, run_migrate, run_multiplexed_server, run_openapi, run_seed, run_server, run_staff,
+    run_tenant, Cli, Commands, DatabaseConfig, Environment, Registry, Shutdown,
 };
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
 
-    let env = Environment::from_env();
-    let _provider = otel::init(&env)?;
-
     let cli = Cli::parse();
+    let env = Environment::load(cli.config.as_deref(), |env| cli.apply(env))?;
+    let telemetry = otel::init(&env)?;
 
     let result = match cli.command {
-        Commands::HttpServer { port } => {
-            let registry = Registry::new(&env.database_url).await?;
-            run_http_server(port, registry).await
+        Commands::HttpServer { .. } => {
+            let registry = Registry::new(&env).await?;
+            let shutdown = Shutdown::install(env.server.shutdown_timeout);
+            retention::spawn_archiver(registry.clone(), shutdown.clone());
+            retention::spawn_purger(registry.clone(), shutdown.clone());
+            let result =
+                run_http_server(env.server.http_port, registry.clone(), shutdown, &env).await;
+            registry.close().await;
+            result
+        }
+        Commands::GrpcServer { .. } => {
+            let registry = Registry::new(&env).await?;
+            let shutdown = Shutdown::install(env.server.shutdown_timeout);
+            retention::spawn_archiver(registry.clone(), shutdown.clone());
+            retention::spawn_purger(registry.clone(), shutdown.clone());
+            let result =
+                run_grpc_server(env.server.grpc_port, registry.clone(), shutdown, &env).await;
+            registry.close().await;
+            result
+        }
+        Commands::Serve { .. } => {
+            // Only checked here: a server on its own may use either port.
+            anyhow::ensure!(
+                env.server.port.is_some() || env.server.http_port != env.server.grpc_port,
+                "server.http_port and server.grpc_port are both {}",
+                env.server.http_port
+            );
+            let registry = Registry::new(&env).await?;
+            let shutdown = Shutdown::install(env.server.shutdown_timeout);
+            retention::spawn_archiver(registry.clone(), shutdown.clone());
+            retention::spawn_purger(registry.clone(), shutdown.clone());
+            let result = match env.server.port {
+                Some(port) => run_multiplexed_server(port, registry.clone(), shutdown, &env).await,
+                None => {
+                    run_server(
+                        env.server.http_port,
+                        env.server.grpc_port,
+                        registry.clone(),
+                        shutdown,
+                        &env,
+                    )
+                    .await
+                }
+            };
+            registry.close().await;
+            result
+        }
+        Commands::Migrate { command, dry_run } => {
+            // Schema changes may legitimately outlast the request statement timeout.
+            let config = DatabaseConfig {
+                statement_timeout: Duration::ZERO,
+                ..env.database.clone()
+            };
+            let pool = oxidize_infrastructure::create_pool(&config).await?;
+            run_migrate(command, dry_run, pool).await
+        }
+        Commands::Seed {
+            tenants,
+            staff_per_tenant,
+            seed,
+        } => {
+            let registry = Registry::new(&env).await?;
+            run_seed(tenants, staff_per_tenant, seed, registry).await
+        }
+        Commands::Tenant { command, output } => {
+            let registry = Registry::new(&env).await?;
+            run_tenant(command, output, registry).await
+        }
+        Commands::Staff { command, output } => {
+            let registry = Registry::new(&env).await?;
+            run_staff(command, output, registry).await
+        }
+        Commands::ApiKey { command, output } => {
+            let registry = Registry::new(&env).await?;
+            run_api_key(command, output, registry).await
         }
-        Commands::GrpcServer { port } => {
-            let registry = Registry::new(&env.database_url).await?;
-            run_grpc_server(port, registry).await
+        Commands::Export { target } => {
+            let registry = Registry::new(&env).await?;
+            run_export(target, registry).await
         }
-        Commands::Migrate => {
-            tracing::info!("Running migrations...");
-            let pool = oxidize_infrastructure::create_pool(&env.database_url).await?;
-            sqlx::migrate!("../../db/migrations").run(&pool).await?;
-            tracing::info!("Migrations completed");
-            Ok(())
+        Commands::Import {
+            path,
+            format,
+            id_mode,
+        } => {
+            let registry = Registry::new(&env).await?;
+            run_import(path, format, id_mode.into(), registry).await
         }
+        Commands::Openapi { output } => run_openapi(output),
+        Commands::Config { command } => run_config(command, &env),
     };
 
-    otel::shutdown(_provider);
+    otel::shutdown(telemetry);
     result
 }
@@ -7,6 +7,7 @@ pub enum ErrorCategory {
     Forbidden,
     NotFound,
     Conflict,
+    TooManyRequests,
     Internal,
 }
 
@@ -31,10 +32,26 @@ impl DomainError {
         Self::new(code, ErrorCategory::BadRequest, message)
     }
 
+    pub fn unauthorized(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::Unauthorized, message)
+    }
+
     pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
         Self::new(code, ErrorCategory::NotFound, message)
     }
 
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::Forbidden, message)
+    }
+
+    pub fn conflict(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::Conflict, message)
+    }
+
+    pub fn too_many_requests(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(code, ErrorCategory::TooManyRequests, message)
+    }
+
     pub fn internal(code: &'static str, message: impl Into<String>) -> Self {
         Self::new(code, ErrorCategory::Internal, message)
     }
@@ -53,13 +70,70 @@ pub mod errors {
         DomainError::bad_request("E100002", "Invalid argument")
     }
 
+    pub fn already_exists() -> DomainError {
+        DomainError::conflict("E100003", "Resource already exists")
+    }
+
+    pub fn rate_limited() -> DomainError {
+        DomainError::too_many_requests("E100004", "Rate limit exceeded")
+    }
+
+    pub fn unauthenticated() -> DomainError {
+        DomainError::unauthorized("E100005", "Missing or invalid credentials")
+    }
+
+    pub fn permission_denied() -> DomainError {
+        DomainError::forbidden("E100006", "Not allowed for this caller")
+    }
+
+    pub fn idempotency_key_reused() -> DomainError {
+        DomainError::bad_request(
+            "E100007",
+            "Idempotency key was already used for a different request",
+        )
+    }
+
+    pub fn idempotency_key_in_use() -> DomainError {
+        DomainError::conflict(
+            "E100008",
+            "A request with this idempotency key is still in progress",
+        )
+    }
+
     pub fn tenant_not_found() -> DomainError {
         DomainError::not_found("E200101", "Tenant not found")
     }
 
+    pub fn tenant_already_exists() -> DomainError {
+        DomainError::conflict("E200102", "Tenant already exists")
+    }
+
+    pub fn tenant_has_staff() -> DomainError {
+        DomainError::conflict(
+            "E200103",
+            "Tenant still has staff; delete them first or force the deletion",
+        )
+    }
+
+    pub fn tenant_closed() -> DomainError {
+        DomainError::forbidden("E200104", "Tenant is closed")
+    }
+
     pub fn staff_not_found() -> DomainError {
         DomainError::not_found("E200201", "Staff not found")
     }
+
+    pub fn staff_already_exists() -> DomainError {
+        DomainError::conflict("E200202", "Staff already exists")
+    }
+
+    pub fn staff_auth_uid_taken() -> DomainError {
+        DomainError::conflict("E200203", "Auth UID is already used by another staff")
+    }
+
+    pub fn api_key_not_found() -> DomainError {
+        DomainError::not_found("E200301", "API key not found")
+    }
 }
 
 #[cfg(test)]
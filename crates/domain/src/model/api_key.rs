@@ -0,0 +1,173 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::TenantId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApiKeyId(String);
+
+impl ApiKeyId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    pub fn from_string(id: String) -> Self {
+        Self(id)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for ApiKeyId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What an API key may do within its tenant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApiKeyScope {
+    #[serde(rename = "tenants:read")]
+    TenantsRead,
+    #[serde(rename = "tenants:write")]
+    TenantsWrite,
+    #[serde(rename = "staffs:read")]
+    StaffsRead,
+    #[serde(rename = "staffs:write")]
+    StaffsWrite,
+}
+
+impl ApiKeyScope {
+    pub const ALL: [ApiKeyScope; 4] = [
+        ApiKeyScope::TenantsRead,
+        ApiKeyScope::TenantsWrite,
+        ApiKeyScope::StaffsRead,
+        ApiKeyScope::StaffsWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::TenantsRead => "tenants:read",
+            ApiKeyScope::TenantsWrite => "tenants:write",
+            ApiKeyScope::StaffsRead => "staffs:read",
+            ApiKeyScope::StaffsWrite => "staffs:write",
+        }
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A key an integration uses to call the API on behalf of one tenant. Only
+/// a hash of the key is kept; the key itself is shown once, on creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub tenant_id: TenantId,
+    pub name: String,
+    /// The first characters of the key, to tell keys apart.
+    pub prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    pub fn new(
+        tenant_id: TenantId,
+        name: String,
+        prefix: String,
+        key_hash: String,
+        scopes: Vec<ApiKeyScope>,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: ApiKeyId::new(),
+            tenant_id,
+            name,
+            prefix,
+            key_hash,
+            scopes,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+            created_at: now,
+        }
+    }
+
+    /// Neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|e| e > now)
+    }
+
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_scope_from_str() {
+        for scope in ApiKeyScope::ALL {
+            assert_eq!(scope.as_str().parse::<ApiKeyScope>(), Ok(scope));
+        }
+        assert!("admin".parse::<ApiKeyScope>().is_err());
+        assert_eq!(
+            serde_json::to_string(&ApiKeyScope::StaffsWrite).unwrap(),
+            "\"staffs:write\""
+        );
+    }
+
+    #[test]
+    fn test_is_active() {
+        let now = Utc::now();
+        let mut key = ApiKey::new(
+            TenantId::new(),
+            "ci".to_string(),
+            "oxk_0123".to_string(),
+            "hash".to_string(),
+            vec![ApiKeyScope::StaffsRead],
+            Some(now + Duration::days(1)),
+            now,
+        );
+        assert!(key.is_active(now));
+        assert!(key.has_scope(ApiKeyScope::StaffsRead));
+        assert!(!key.has_scope(ApiKeyScope::StaffsWrite));
+        assert!(!key.is_active(now + Duration::days(2)));
+
+        key.expires_at = None;
+        key.revoked_at = Some(now);
+        assert!(!key.is_active(now));
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The outcome of a create, kept under the idempotency key of the request
+/// so a retry gets the same answer instead of a duplicate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    /// The operation the key was used for, e.g. `CreateTenant`.
+    pub operation: String,
+    /// Who sent the key; keys of different callers never collide.
+    pub caller: String,
+    pub key: String,
+    /// A hash of the request, to tell a retry from a reused key.
+    pub fingerprint: String,
+    /// Identifies the request holding the key, so one whose lease was taken
+    /// over can no longer complete or release it.
+    pub token: String,
+    /// The serialized result; `None` while the first request is running.
+    pub response: Option<String>,
+    /// Until when the first request holds the key; a retry may take it over
+    /// afterwards should it never complete. `None` once completed.
+    pub locked_until: Option<DateTime<Utc>>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IdempotencyRecord {
+    pub fn new(
+        operation: String,
+        caller: String,
+        key: String,
+        fingerprint: String,
+        ttl: Duration,
+        lease: Duration,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            operation,
+            caller,
+            key,
+            fingerprint,
+            token: Uuid::new_v4().to_string(),
+            response: None,
+            locked_until: Some(now + lease),
+            expires_at: now + ttl,
+            created_at: now,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_record() {
+        let now = Utc::now();
+        let record = IdempotencyRecord::new(
+            "CreateTenant".to_string(),
+            "user:alice".to_string(),
+            "k1".to_string(),
+            "f1".to_string(),
+            Duration::hours(24),
+            Duration::minutes(1),
+            now,
+        );
+
+        assert_eq!(record.response, None);
+        assert_eq!(record.locked_until, Some(now + Duration::minutes(1)));
+        assert!(!record.is_expired(now));
+        assert!(record.is_expired(now + Duration::hours(24)));
+    }
+}
@@ -1,8 +1,12 @@
+mod api_key;
+mod idempotency;
 mod staff;
 mod staff_role;
 mod tenant;
 mod tenant_tag_type;
 
+pub use api_key::*;
+pub use idempotency::*;
 pub use staff::*;
 pub use staff_role::*;
 pub use tenant::*;
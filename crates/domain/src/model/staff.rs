@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::{StaffRole, Tenant, TenantId};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct StaffId(String);
 
 impl StaffId {
@@ -26,7 +28,7 @@ impl Default for StaffId {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Staff {
     pub id: StaffId,
     pub tenant_id: TenantId,
@@ -38,8 +40,10 @@ pub struct Staff {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     // Computed field (not stored in DB)
+    #[serde(skip)]
     pub image_url: Option<String>,
     // Readonly reference (loaded separately)
+    #[serde(skip)]
     pub tenant: Option<Tenant>,
 }
 
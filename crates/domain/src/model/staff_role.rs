@@ -1,7 +1,10 @@
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum StaffRole {
     #[default]
     Unknown,
@@ -75,4 +78,16 @@ mod tests {
         assert_eq!(StaffRole::Normal.to_string(), "normal");
         assert_eq!(StaffRole::Admin.to_string(), "admin");
     }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(
+            serde_json::to_string(&StaffRole::Admin).unwrap(),
+            "\"admin\""
+        );
+        assert_eq!(
+            serde_json::from_str::<StaffRole>("\"normal\"").unwrap(),
+            StaffRole::Normal
+        );
+    }
 }
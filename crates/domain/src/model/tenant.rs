@@ -1,9 +1,11 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::TenantTagType;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct TenantId(String);
 
 impl TenantId {
@@ -26,7 +28,8 @@ impl Default for TenantId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct TenantTagId(String);
 
 impl TenantTagId {
@@ -49,7 +52,7 @@ impl Default for TenantTagId {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TenantTag {
     pub id: TenantTagId,
     pub tag_type: TenantTagType,
@@ -68,11 +71,30 @@ impl TenantTag {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where the archive of a closed tenant was written, and what it hashed to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantArchiveRef {
+    pub location: String,
+    /// SHA-256 of the archive, hex-encoded.
+    pub checksum: String,
+    pub archived_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tenant {
     pub id: TenantId,
     pub name: String,
+    #[serde(default)]
     pub tags: Vec<TenantTag>,
+    /// Set while the tenant is closed: its staff can no longer be accessed.
+    #[serde(default)]
+    pub closed_at: Option<DateTime<Utc>>,
+    /// When a closed tenant and its staff are deleted for good.
+    #[serde(default)]
+    pub purge_after: Option<DateTime<Utc>>,
+    /// The archive of a closed tenant, once written; purging waits for it.
+    #[serde(default)]
+    pub archive: Option<TenantArchiveRef>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -83,11 +105,32 @@ impl Tenant {
             id: TenantId::new(),
             name,
             tags: Vec::new(),
+            closed_at: None,
+            purge_after: None,
+            archive: None,
             created_at: now,
             updated_at: now,
         }
     }
 
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some()
+    }
+
+    pub fn close(&mut self, retention: Duration, now: DateTime<Utc>) {
+        self.closed_at = Some(now);
+        self.purge_after = Some(now + retention);
+        self.archive = None;
+        self.updated_at = now;
+    }
+
+    pub fn reopen(&mut self, now: DateTime<Utc>) {
+        self.closed_at = None;
+        self.purge_after = None;
+        self.archive = None;
+        self.updated_at = now;
+    }
+
     pub fn update(&mut self, name: Option<String>, now: DateTime<Utc>) {
         if let Some(n) = name {
             self.name = n;
@@ -124,4 +167,25 @@ mod tests {
         assert_eq!(tenant.name, "Updated");
         assert!(tenant.updated_at >= tenant.created_at);
     }
+
+    #[test]
+    fn test_close_and_reopen_tenant() {
+        let now = Utc::now();
+        let mut tenant = Tenant::new("Closing".to_string(), now);
+        assert!(!tenant.is_closed());
+
+        tenant.close(Duration::days(30), now);
+        assert!(tenant.is_closed());
+        assert_eq!(tenant.purge_after, Some(now + Duration::days(30)));
+
+        tenant.archive = Some(TenantArchiveRef {
+            location: "archives/t.json".to_string(),
+            checksum: "0".repeat(64),
+            archived_at: now,
+        });
+        tenant.reopen(now);
+        assert!(!tenant.is_closed());
+        assert_eq!(tenant.purge_after, None);
+        assert_eq!(tenant.archive, None);
+    }
 }
@@ -1,7 +1,10 @@
 use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TenantTagType {
     #[default]
     Unknown,
@@ -77,4 +80,16 @@ mod tests {
         assert!(TenantTagType::Entertainment.is_valid());
         assert!(TenantTagType::Education.is_valid());
     }
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(
+            serde_json::to_string(&TenantTagType::Business).unwrap(),
+            "\"business\""
+        );
+        assert_eq!(
+            serde_json::from_str::<TenantTagType>("\"other\"").unwrap(),
+            TenantTagType::Other
+        );
+    }
 }
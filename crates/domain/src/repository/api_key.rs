@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::error::Result;
+use crate::model::{ApiKey, ApiKeyId, TenantId};
+
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    /// The key whose hash is `key_hash`, revoked or expired ones included,
+    /// unless its tenant is closed.
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>>;
+    /// The keys of `tenant_id`, newest first.
+    async fn list(&self, tenant_id: &TenantId) -> Result<Vec<ApiKey>>;
+    async fn create(&self, api_key: &ApiKey) -> Result<()>;
+    /// Revokes key `id` of `tenant_id` at `now`, unless it already was, and
+    /// returns it; `None` if the tenant has no such key.
+    async fn revoke(
+        &self,
+        tenant_id: &TenantId,
+        id: &ApiKeyId,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ApiKey>>;
+    /// Records that key `id` was used at `now`.
+    async fn touch(&self, id: &ApiKeyId, now: DateTime<Utc>) -> Result<()>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::model::IdempotencyRecord;
+
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    /// Takes `record`'s key for a new request, replacing an expired record
+    /// or one whose request let its lease lapse. Returns `None` once taken,
+    /// or the live record that already holds it.
+    async fn claim(&self, record: &IdempotencyRecord) -> Result<Option<IdempotencyRecord>>;
+    /// Stores the `response` of the request that claimed `record`'s key; a
+    /// no-op once another request took the key over.
+    async fn complete(&self, record: &IdempotencyRecord, response: &str) -> Result<()>;
+    /// Gives up the key of a request that failed, so it can be retried; a
+    /// no-op once another request took the key over.
+    async fn release(&self, record: &IdempotencyRecord) -> Result<()>;
+}
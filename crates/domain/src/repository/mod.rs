@@ -1,5 +1,9 @@
+mod api_key;
+mod idempotency;
 mod staff;
 mod tenant;
 
+pub use api_key::*;
+pub use idempotency::*;
 pub use staff::*;
 pub use tenant::*;
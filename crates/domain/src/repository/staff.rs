@@ -8,6 +8,9 @@ pub struct GetStaffQuery {
     pub id: Option<StaffId>,
     pub auth_uid: Option<String>,
     pub with_tenant: bool,
+    /// Must see writes that were just committed, e.g. before modifying the
+    /// staff; otherwise the read may be served by a lagging replica.
+    pub consistent: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -15,6 +18,10 @@ pub struct ListStaffQuery {
     pub tenant_id: Option<TenantId>,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
+    /// Leaves out the staff of closed tenants.
+    pub open_tenants_only: bool,
+    /// Must see writes that were just committed; see `GetStaffQuery`.
+    pub consistent: bool,
 }
 
 #[async_trait]
@@ -23,6 +30,8 @@ pub trait StaffRepository: Send + Sync {
     async fn list(&self, query: ListStaffQuery) -> Result<Vec<Staff>>;
     async fn count(&self, query: ListStaffQuery) -> Result<u64>;
     async fn create(&self, staff: &Staff) -> Result<()>;
-    async fn update(&self, staff: &Staff) -> Result<()>;
-    async fn delete(&self, id: &StaffId) -> Result<()>;
+    /// Returns whether a row matched `staff.id`.
+    async fn update(&self, staff: &Staff) -> Result<bool>;
+    /// Returns whether a row matched `id`.
+    async fn delete(&self, id: &StaffId) -> Result<bool>;
 }
@@ -1,11 +1,15 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use crate::error::Result;
-use crate::model::{Tenant, TenantId};
+use crate::model::{Staff, Tenant, TenantId};
 
 #[derive(Debug, Default)]
 pub struct GetTenantQuery {
     pub id: Option<TenantId>,
+    /// Must see writes that were just committed, e.g. before modifying the
+    /// tenant; otherwise the read may be served by a lagging replica.
+    pub consistent: bool,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -20,6 +24,23 @@ pub trait TenantRepository: Send + Sync {
     async fn list(&self, query: ListTenantQuery) -> Result<Vec<Tenant>>;
     async fn count(&self, query: ListTenantQuery) -> Result<u64>;
     async fn create(&self, tenant: &Tenant) -> Result<()>;
-    async fn update(&self, tenant: &Tenant) -> Result<()>;
-    async fn delete(&self, id: &TenantId) -> Result<()>;
+    /// Creates `tenant` with its `staff`, all or nothing, e.g. to restore an
+    /// archive.
+    async fn import(&self, tenant: &Tenant, staff: &[Staff]) -> Result<()>;
+    /// Returns whether a row matched `tenant.id`.
+    async fn update(&self, tenant: &Tenant) -> Result<bool>;
+    /// Returns whether a row matched `id`. Staff of the tenant are deleted
+    /// with it if `force` is set; otherwise a tenant with staff is refused,
+    /// checked atomically with the delete.
+    async fn delete(&self, id: &TenantId, force: bool) -> Result<bool>;
+    /// Closed tenants whose archive is yet to be written.
+    async fn list_unarchived(&self) -> Result<Vec<Tenant>>;
+    /// Records `tenant.archive`, unless the tenant was reopened or already
+    /// archived since; returns whether it was recorded.
+    async fn record_archive(&self, tenant: &Tenant) -> Result<bool>;
+    /// Archived closed tenants whose `purge_after` is at or before `now`.
+    async fn list_purgeable(&self, now: DateTime<Utc>) -> Result<Vec<Tenant>>;
+    /// Deletes `tenant` with its staff if it is still due by `now` under the
+    /// same archive; returns whether it was.
+    async fn purge(&self, tenant: &Tenant, now: DateTime<Utc>) -> Result<bool>;
 }
@@ -1,5 +1,28 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Compiles `<name>/<name>.proto` and writes its file descriptor set to
+/// `$OUT_DIR/<name>_descriptor.bin` for gRPC server reflection and the
+/// HTTP/JSON gateway. Vendored imports such as `google/api` live in
+/// `third_party`.
+fn compile(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let proto_dir = format!("../../schema/proto/{}", name);
+
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join(format!("{}_descriptor.bin", name)))
+        .compile_protos(
+            &[format!("{}/{}.proto", proto_dir, name)],
+            &[proto_dir, "../../schema/proto/third_party".to_string()],
+        )?;
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("../../schema/proto/tenant/tenant.proto")?;
-    tonic_build::compile_protos("../../schema/proto/staff/staff.proto")?;
+    // Migrations are embedded by `sqlx::migrate!` in database/migration.rs.
+    println!("cargo:rerun-if-changed=../../db/migrations");
+    compile("tenant")?;
+    compile("staff")?;
+    compile("api_key")?;
     Ok(())
 }
@@ -0,0 +1,187 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use oxidize_domain::{Staff, Tenant};
+
+/// Bumped whenever the archive layout changes incompatibly.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ArchiveFormat {
+    /// A single JSON document
+    #[default]
+    Json,
+    /// One JSON record per line: a header, the tenant, then each staff
+    Ndjson,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TenantArchive {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub tenant: Tenant,
+    pub staffs: Vec<Staff>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    Header {
+        version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Tenant(Tenant),
+    Staff(Staff),
+}
+
+impl TenantArchive {
+    pub fn new(tenant: Tenant, staffs: Vec<Staff>) -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            tenant,
+            staffs,
+        }
+    }
+
+    pub fn write(&self, format: ArchiveFormat, mut writer: impl Write) -> anyhow::Result<()> {
+        match format {
+            ArchiveFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writeln!(writer)?;
+            }
+            ArchiveFormat::Ndjson => {
+                let header = Record::Header {
+                    version: self.version,
+                    exported_at: self.exported_at,
+                };
+                serde_json::to_writer(&mut writer, &header)?;
+                writeln!(writer)?;
+                serde_json::to_writer(&mut writer, &Record::Tenant(self.tenant.clone()))?;
+                writeln!(writer)?;
+                for staff in &self.staffs {
+                    serde_json::to_writer(&mut writer, &Record::Staff(staff.clone()))?;
+                    writeln!(writer)?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read(format: ArchiveFormat, reader: impl BufRead) -> anyhow::Result<Self> {
+        let archive = match format {
+            ArchiveFormat::Json => serde_json::from_reader(reader)?,
+            ArchiveFormat::Ndjson => Self::read_ndjson(reader)?,
+        };
+
+        if archive.version != ARCHIVE_VERSION {
+            bail!(
+                "unsupported archive version {} (expected {})",
+                archive.version,
+                ARCHIVE_VERSION
+            );
+        }
+        Ok(archive)
+    }
+
+    fn read_ndjson(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut header = None;
+        let mut tenant = None;
+        let mut staffs = Vec::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record = serde_json::from_str(&line)
+                .map_err(|e| anyhow!("line {}: invalid record: {}", i + 1, e))?;
+            match record {
+                Record::Header { .. } if header.is_some() || tenant.is_some() => {
+                    bail!("line {}: header must appear once, first", i + 1)
+                }
+                Record::Header {
+                    version,
+                    exported_at,
+                } => header = Some((version, exported_at)),
+                Record::Tenant(_) if tenant.is_some() => {
+                    bail!("line {}: archive contains more than one tenant", i + 1)
+                }
+                Record::Tenant(t) => tenant = Some(t),
+                Record::Staff(s) => staffs.push(s),
+            }
+        }
+
+        let (version, exported_at) = header.ok_or_else(|| anyhow!("archive has no header"))?;
+        let tenant = tenant.ok_or_else(|| anyhow!("archive has no tenant record"))?;
+
+        Ok(Self {
+            version,
+            exported_at,
+            tenant,
+            staffs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxidize_domain::{StaffRole, TenantTag, TenantTagType};
+
+    fn sample() -> TenantArchive {
+        let now = Utc::now();
+        let mut tenant = Tenant::new("Acme".to_string(), now);
+        tenant.add_tag(TenantTag::new(TenantTagType::Business, now));
+        let staff = Staff::new(
+            tenant.id.clone(),
+            StaffRole::Admin,
+            "auth123".to_string(),
+            "John Doe".to_string(),
+            "".to_string(),
+            "john@example.com".to_string(),
+            now,
+        );
+        TenantArchive::new(tenant, vec![staff])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in [ArchiveFormat::Json, ArchiveFormat::Ndjson] {
+            let archive = sample();
+            let mut buf = Vec::new();
+            archive.write(format, &mut buf).unwrap();
+
+            let restored = TenantArchive::read(format, buf.as_slice()).unwrap();
+            assert_eq!(restored.tenant.id, archive.tenant.id);
+            assert_eq!(restored.tenant.tags.len(), 1);
+            assert_eq!(restored.tenant.tags[0].tag_type, TenantTagType::Business);
+            assert_eq!(restored.staffs.len(), 1);
+            assert_eq!(restored.staffs[0].role, StaffRole::Admin);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut archive = sample();
+        archive.version = ARCHIVE_VERSION + 1;
+        let mut buf = Vec::new();
+        archive.write(ArchiveFormat::Ndjson, &mut buf).unwrap();
+
+        assert!(TenantArchive::read(ArchiveFormat::Ndjson, buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_ndjson_requires_tenant() {
+        let input = format!(
+            "{{\"type\":\"header\",\"version\":{},\"exported_at\":\"2024-01-01T00:00:00Z\"}}\n",
+            ARCHIVE_VERSION
+        );
+        assert!(TenantArchive::read(ArchiveFormat::Ndjson, input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use crate::environment::Secret;
+
+/// The claims a bearer token must carry.
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+}
+
+/// Verifies HS256 bearer JWTs signed with `auth.jwt_secret`.
+pub struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtVerifier {
+    pub fn new(secret: &Secret) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        validation.validate_nbf = true;
+        Self {
+            key: DecodingKey::from_secret(secret.expose().as_bytes()),
+            validation,
+        }
+    }
+
+    pub fn verify(&self, token: &str) -> jsonwebtoken::errors::Result<Claims> {
+        jsonwebtoken::decode(token, &self.key, &self.validation).map(|data| data.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn token(claims: serde_json::Value, secret: &str) -> String {
+        jsonwebtoken::encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        let verifier = JwtVerifier::new(&Secret::new(SECRET));
+        let exp = chrono::Utc::now().timestamp() + 60;
+
+        let claims = verifier
+            .verify(&token(json!({"sub": "u1", "exp": exp}), SECRET))
+            .unwrap();
+        assert_eq!(claims.sub, "u1");
+
+        let wrong_key = token(json!({"sub": "u1", "exp": exp}), &SECRET.repeat(2));
+        assert!(verifier.verify(&wrong_key).is_err());
+        let expired = token(json!({"sub": "u1", "exp": exp - 3600}), SECRET);
+        assert!(verifier.verify(&expired).is_err());
+        let no_exp = token(json!({"sub": "u1"}), SECRET);
+        assert!(verifier.verify(&no_exp).is_err());
+        assert!(verifier.verify("not-a-jwt").is_err());
+    }
+}
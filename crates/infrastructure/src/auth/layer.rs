@@ -0,0 +1,100 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::{Request, Response};
+use axum::BoxError;
+use tower::{Layer, Service};
+
+use super::{authenticate, Actor};
+use crate::database::scoped;
+use crate::otel;
+use crate::registry::Registry;
+use crate::serve::refuse;
+
+/// Paths anyone may call, whatever their credentials.
+const PUBLIC_PREFIXES: [&str; 6] = [
+    "/health",
+    "/metrics",
+    "/swagger-ui",
+    "/openapi.json",
+    "/grpc.health.v1.Health/",
+    "/grpc.reflection.",
+];
+
+/// Authenticates every request but the public ones, attaching its `Actor`
+/// and recording it on the request span. Requests of API keys run scoped to
+/// their tenant in the database. Refusals are `UNAUTHENTICATED` for gRPC and
+/// 401 for HTTP.
+#[derive(Clone)]
+pub struct AuthLayer {
+    registry: Arc<Registry>,
+}
+
+impl AuthLayer {
+    pub fn new(registry: Arc<Registry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = Auth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Auth {
+            inner,
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Auth<S> {
+    inner: S,
+    registry: Arc<Registry>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Auth<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        // The clone may not be ready; call the one that is.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let registry = self.registry.clone();
+
+        Box::pin(async move {
+            let path = request.uri().path();
+            let mut tenant = None;
+            if !PUBLIC_PREFIXES.iter().any(|p| path.starts_with(p)) {
+                match authenticate(&registry, request.headers()).await {
+                    Ok(Some(actor)) => {
+                        otel::record_actor(&actor.to_string());
+                        if let Actor::Service { tenant_id, .. } = &actor {
+                            tenant = Some(tenant_id.clone());
+                        }
+                        request.extensions_mut().insert(actor);
+                    }
+                    Ok(None) => {}
+                    Err(err) => return Ok(refuse(&request, err)),
+                }
+            }
+            Ok(scoped(tenant, inner.call(request)).await?.map(Body::new))
+        })
+    }
+}
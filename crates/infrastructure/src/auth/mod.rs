@@ -0,0 +1,182 @@
+//! Authentication of API callers, on both servers: end users by a bearer
+//! JWT signed with `auth.jwt_secret`, integrations by one of their tenant's
+//! API keys, sent as `Authorization: ApiKey <key>`.
+//!
+//! The caller becomes an [`Actor`] in the request extensions. Handlers
+//! check it with [`authorize`]: users may do anything, API keys only what
+//! their scopes allow, within their tenant.
+
+mod jwt;
+mod layer;
+
+use std::fmt;
+
+use axum::http::header::AUTHORIZATION;
+use axum::http::HeaderMap;
+
+use oxidize_domain::{errors, ApiKeyId, ApiKeyScope, Result, TenantId};
+
+use crate::environment::AuthConfig;
+use crate::registry::Registry;
+
+pub use jwt::{Claims, JwtVerifier};
+pub use layer::{Auth, AuthLayer};
+
+/// Who is calling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Actor {
+    /// An end user, by the `sub` of their token.
+    User { subject: String },
+    /// An integration, by its API key.
+    Service {
+        key_id: ApiKeyId,
+        tenant_id: TenantId,
+        scopes: Vec<ApiKeyScope>,
+    },
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User { subject } => write!(f, "user:{}", subject),
+            Self::Service { key_id, .. } => write!(f, "api_key:{}", key_id.as_str()),
+        }
+    }
+}
+
+impl Actor {
+    /// Whether the actor may use `scope` on `tenant`. `None` stands for
+    /// calls across tenants or on the set of tenants itself, which API keys
+    /// may not make.
+    pub fn authorize(&self, scope: ApiKeyScope, tenant: Option<&TenantId>) -> Result<()> {
+        match self {
+            Self::User { .. } => Ok(()),
+            Self::Service {
+                tenant_id, scopes, ..
+            } if scopes.contains(&scope) && tenant == Some(tenant_id) => Ok(()),
+            Self::Service { .. } => Err(errors::permission_denied()),
+        }
+    }
+
+    /// Refuses API keys, e.g. to manage API keys.
+    pub fn require_user(&self) -> Result<()> {
+        match self {
+            Self::User { .. } => Ok(()),
+            Self::Service { .. } => Err(errors::permission_denied()),
+        }
+    }
+}
+
+/// [`Actor::authorize`] for the caller, if any. Anonymous callers only get
+/// this far when `auth.required` is off, and may do anything.
+pub fn authorize(
+    actor: Option<&Actor>,
+    scope: ApiKeyScope,
+    tenant: Option<&TenantId>,
+) -> Result<()> {
+    actor.map_or(Ok(()), |actor| actor.authorize(scope, tenant))
+}
+
+/// [`Actor::require_user`] for the caller, if any.
+pub fn require_user(actor: Option<&Actor>) -> Result<()> {
+    actor.map_or(Ok(()), Actor::require_user)
+}
+
+/// The settings of `auth`, ready for use.
+pub struct Authenticator {
+    jwt: Option<JwtVerifier>,
+    required: bool,
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig) -> Self {
+        Self {
+            jwt: config.jwt_secret.as_ref().map(JwtVerifier::new),
+            required: config.required,
+        }
+    }
+}
+
+/// The actor the `Authorization` header in `headers` stands for, or `None`
+/// without one when `auth.required` is off. Credentials that are present
+/// but invalid are always refused.
+pub async fn authenticate(registry: &Registry, headers: &HeaderMap) -> Result<Option<Actor>> {
+    let Some(value) = headers.get(AUTHORIZATION) else {
+        return if registry.authenticator.required {
+            Err(errors::unauthenticated())
+        } else {
+            Ok(None)
+        };
+    };
+    let (scheme, credentials) = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.split_once(' '))
+        .ok_or_else(errors::unauthenticated)?;
+    let credentials = credentials.trim();
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        let verifier = registry.authenticator.jwt.as_ref().ok_or_else(|| {
+            tracing::debug!("Bearer token refused: auth.jwt_secret is not set");
+            errors::unauthenticated()
+        })?;
+        let claims = verifier.verify(credentials).map_err(|e| {
+            tracing::debug!(error = %e, "Bearer token refused");
+            errors::unauthenticated()
+        })?;
+        Ok(Some(Actor::User {
+            subject: claims.sub,
+        }))
+    } else if scheme.eq_ignore_ascii_case("apikey") {
+        let api_key = registry
+            .api_key_interactor
+            .authenticate(credentials)
+            .await?;
+        Ok(Some(Actor::Service {
+            key_id: api_key.id,
+            tenant_id: api_key.tenant_id,
+            scopes: api_key.scopes,
+        }))
+    } else {
+        Err(errors::unauthenticated())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oxidize_domain::error::ErrorCategory;
+
+    use super::*;
+
+    #[test]
+    fn test_authorize() {
+        let tenant = TenantId::from_string("t1".to_string());
+        let other = TenantId::from_string("t2".to_string());
+        let service = Actor::Service {
+            key_id: ApiKeyId::from_string("k1".to_string()),
+            tenant_id: tenant.clone(),
+            scopes: vec![ApiKeyScope::StaffsRead],
+        };
+        assert_eq!(service.to_string(), "api_key:k1");
+
+        assert!(service
+            .authorize(ApiKeyScope::StaffsRead, Some(&tenant))
+            .is_ok());
+        for (scope, tenant) in [
+            (ApiKeyScope::StaffsWrite, Some(&tenant)),
+            (ApiKeyScope::StaffsRead, Some(&other)),
+            (ApiKeyScope::StaffsRead, None),
+        ] {
+            let err = service.authorize(scope, tenant).unwrap_err();
+            assert_eq!(err.category, ErrorCategory::Forbidden);
+        }
+        assert!(service.require_user().is_err());
+
+        let user = Actor::User {
+            subject: "u1".to_string(),
+        };
+        assert!(user.authorize(ApiKeyScope::TenantsWrite, None).is_ok());
+        assert!(user.require_user().is_ok());
+        assert!(authorize(None, ApiKeyScope::TenantsWrite, None).is_ok());
+    }
+}
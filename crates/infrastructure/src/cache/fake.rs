@@ -0,0 +1,179 @@
+//! An in-memory tenant and staff repository counting its reads, to test the
+//! decorators against.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use oxidize_domain::{
+    errors, GetStaffQuery, GetTenantQuery, ListStaffQuery, ListTenantQuery, Result, Staff, StaffId,
+    StaffRepository, Tenant, TenantId, TenantRepository,
+};
+
+use crate::environment::CacheConfig;
+
+pub fn config() -> CacheConfig {
+    CacheConfig {
+        enabled: true,
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    tenants: Vec<Tenant>,
+    staff: Vec<Staff>,
+}
+
+/// Both repositories over one store, so that deleting a tenant deletes its
+/// staff as the database does. Clones share the store.
+#[derive(Clone, Default)]
+pub struct FakeRepository {
+    state: Arc<Mutex<State>>,
+    reads: Arc<AtomicUsize>,
+}
+
+impl FakeRepository {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// How many `get`s reached the store.
+    pub fn reads(&self) -> usize {
+        self.reads.load(Ordering::SeqCst)
+    }
+
+    /// Deletes `id` behind the decorators' back, as another replica would.
+    pub fn remove_staff(&self, id: &StaffId) {
+        self.state().staff.retain(|s| s.id != *id);
+    }
+
+    fn remove_tenant(&self, id: &TenantId) -> bool {
+        let mut state = self.state();
+        let before = state.tenants.len();
+        state.tenants.retain(|t| t.id != *id);
+        state.staff.retain(|s| s.tenant_id != *id);
+        state.tenants.len() < before
+    }
+}
+
+#[async_trait]
+impl TenantRepository for FakeRepository {
+    async fn get(&self, query: GetTenantQuery) -> Result<Option<Tenant>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        let state = self.state();
+        Ok(state
+            .tenants
+            .iter()
+            .find(|t| query.id.as_ref() == Some(&t.id))
+            .cloned())
+    }
+
+    async fn list(&self, _query: ListTenantQuery) -> Result<Vec<Tenant>> {
+        Ok(self.state().tenants.clone())
+    }
+
+    async fn count(&self, _query: ListTenantQuery) -> Result<u64> {
+        Ok(self.state().tenants.len() as u64)
+    }
+
+    async fn create(&self, tenant: &Tenant) -> Result<()> {
+        self.state().tenants.push(tenant.clone());
+        Ok(())
+    }
+
+    async fn import(&self, tenant: &Tenant, staff: &[Staff]) -> Result<()> {
+        let mut state = self.state();
+        state.tenants.push(tenant.clone());
+        state.staff.extend_from_slice(staff);
+        Ok(())
+    }
+
+    async fn update(&self, tenant: &Tenant) -> Result<bool> {
+        let mut state = self.state();
+        let found = state.tenants.iter_mut().find(|t| t.id == tenant.id);
+        Ok(found.map(|t| *t = tenant.clone()).is_some())
+    }
+
+    async fn delete(&self, id: &TenantId, force: bool) -> Result<bool> {
+        if !force && self.state().staff.iter().any(|s| s.tenant_id == *id) {
+            return Err(errors::tenant_has_staff());
+        }
+        Ok(self.remove_tenant(id))
+    }
+
+    async fn list_unarchived(&self) -> Result<Vec<Tenant>> {
+        let state = self.state();
+        let unarchived = state
+            .tenants
+            .iter()
+            .filter(|t| t.is_closed() && t.archive.is_none());
+        Ok(unarchived.cloned().collect())
+    }
+
+    async fn record_archive(&self, tenant: &Tenant) -> Result<bool> {
+        TenantRepository::update(self, tenant).await
+    }
+
+    async fn list_purgeable(&self, now: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        let state = self.state();
+        let purgeable = state
+            .tenants
+            .iter()
+            .filter(|t| t.archive.is_some() && t.purge_after.is_some_and(|at| at <= now));
+        Ok(purgeable.cloned().collect())
+    }
+
+    async fn purge(&self, tenant: &Tenant, _now: DateTime<Utc>) -> Result<bool> {
+        Ok(self.remove_tenant(&tenant.id))
+    }
+}
+
+#[async_trait]
+impl StaffRepository for FakeRepository {
+    async fn get(&self, query: GetStaffQuery) -> Result<Option<Staff>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        let state = self.state();
+        Ok(state
+            .staff
+            .iter()
+            .find(|s| {
+                query.id.as_ref().is_none_or(|id| s.id == *id)
+                    && query.auth_uid.as_ref().is_none_or(|uid| s.auth_uid == *uid)
+            })
+            .cloned())
+    }
+
+    async fn list(&self, query: ListStaffQuery) -> Result<Vec<Staff>> {
+        let state = self.state();
+        let staff = state
+            .staff
+            .iter()
+            .filter(|s| query.tenant_id.as_ref().is_none_or(|id| s.tenant_id == *id));
+        Ok(staff.cloned().collect())
+    }
+
+    async fn count(&self, query: ListStaffQuery) -> Result<u64> {
+        Ok(StaffRepository::list(self, query).await?.len() as u64)
+    }
+
+    async fn create(&self, staff: &Staff) -> Result<()> {
+        self.state().staff.push(staff.clone());
+        Ok(())
+    }
+
+    async fn update(&self, staff: &Staff) -> Result<bool> {
+        let mut state = self.state();
+        let found = state.staff.iter_mut().find(|s| s.id == staff.id);
+        Ok(found.map(|s| *s = staff.clone()).is_some())
+    }
+
+    async fn delete(&self, id: &StaffId) -> Result<bool> {
+        let mut state = self.state();
+        let before = state.staff.len();
+        state.staff.retain(|s| s.id != *id);
+        Ok(state.staff.len() < before)
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::time::Instant;
+
+struct Entry<V> {
+    value: V,
+    expires_at: Instant,
+    /// When the entry was last used, as a key of `Lru::order`.
+    used: u64,
+}
+
+/// A map holding at most `capacity` entries, dropping the least recently
+/// used one to make room, whose entries expire.
+pub struct Lru<K, V> {
+    capacity: usize,
+    entries: HashMap<K, Entry<V>>,
+    /// Keys by when they were last used, oldest first.
+    order: BTreeMap<u64, K>,
+    clock: u64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Lru<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// The value of `key`, unless it is missing or expired by `now`.
+    pub fn get(&mut self, key: &K, now: Instant) -> Option<V> {
+        let used = self.tick();
+        let entry = self.entries.get_mut(key)?;
+        if entry.expires_at <= now {
+            self.remove(key);
+            return None;
+        }
+        self.order.remove(&entry.used);
+        self.order.insert(used, key.clone());
+        entry.used = used;
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&mut self, key: K, value: V, expires_at: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&key);
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        let used = self.tick();
+        self.order.insert(used, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                expires_at,
+                used,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used);
+        Some(entry.value)
+    }
+
+    /// Removes the entries whose value `f` returns true for.
+    pub fn remove_where(&mut self, mut f: impl FnMut(&V) -> bool) {
+        let order = &mut self.order;
+        self.entries.retain(|_, entry| {
+            let remove = f(&entry.value);
+            if remove {
+                order.remove(&entry.used);
+            }
+            !remove
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1, later);
+        lru.insert("b", 2, later);
+        assert_eq!(lru.get(&"a", now), Some(1));
+
+        lru.insert("c", 3, later);
+        assert_eq!(lru.entries.len(), 2);
+        assert_eq!(lru.get(&"b", now), None);
+        assert_eq!(lru.get(&"a", now), Some(1));
+        assert_eq!(lru.get(&"c", now), Some(3));
+    }
+
+    #[test]
+    fn test_expires_and_removes() {
+        let now = Instant::now();
+        let mut lru = Lru::new(4);
+        lru.insert("a", 1, now + Duration::from_secs(1));
+        lru.insert("b", 2, now + Duration::from_secs(60));
+        lru.insert("c", 3, now + Duration::from_secs(60));
+
+        assert_eq!(lru.get(&"a", now + Duration::from_secs(1)), None);
+        assert_eq!(lru.remove(&"b"), Some(2));
+        lru.remove_where(|v| *v == 3);
+        assert_eq!(lru.entries.len(), 0);
+        assert!(lru.order.is_empty());
+    }
+}
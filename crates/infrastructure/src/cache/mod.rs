@@ -0,0 +1,126 @@
+//! In-process caching of repository reads, the hot one being the lookup of
+//! staff by `auth_uid` on every authenticated call.
+//!
+//! [`CachedTenantRepository`] and [`CachedStaffRepository`] wrap any
+//! implementation of their trait. Misses are cached too, for a shorter TTL.
+//! Writes through a decorator invalidate what they touch, including the staff
+//! a tenant delete takes with it; writes elsewhere, e.g. on another replica,
+//! show once the entry expires.
+//!
+//! Calls within a tenant scope bypass the caches: they must only see what
+//! row-level security lets them, and must not cache that for others.
+
+#[cfg(test)]
+mod fake;
+mod lru;
+mod staff;
+mod tenant;
+
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use opentelemetry::KeyValue;
+
+use crate::environment::CacheConfig;
+use crate::metrics::metrics;
+
+use lru::Lru;
+
+pub use staff::{CachedStaffRepository, StaffCaches};
+pub use tenant::CachedTenantRepository;
+
+/// The outcome of looking a key up in a [`Cache`].
+pub enum Lookup<V> {
+    /// The cached value; `None` for a cached miss.
+    Hit(Option<V>),
+    /// Not cached; pass the generation on to [`Cache::put`].
+    Miss(u64),
+}
+
+struct Inner<K, V> {
+    entries: Lru<K, Option<V>>,
+    /// Bumped by every invalidation, so that a read racing a write does not
+    /// cache what it read before the write.
+    generation: u64,
+}
+
+/// Cached reads of one kind, e.g. tenants by ID.
+pub struct Cache<K, V> {
+    name: &'static str,
+    ttl: Duration,
+    negative_ttl: Duration,
+    inner: Mutex<Inner<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Cache<K, V> {
+    pub fn new(name: &'static str, config: &CacheConfig) -> Self {
+        Self {
+            name,
+            ttl: config.ttl,
+            negative_ttl: config.negative_ttl,
+            inner: Mutex::new(Inner {
+                entries: Lru::new(config.capacity),
+                generation: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner<K, V>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get(&self, key: &K) -> Lookup<V> {
+        let mut inner = self.lock();
+        let (lookup, result) = match inner.entries.get(key, Instant::now()) {
+            Some(value) => (Lookup::Hit(value), "hit"),
+            None => (Lookup::Miss(inner.generation), "miss"),
+        };
+        metrics().cache_requests.add(
+            1,
+            &[
+                KeyValue::new("cache.name", self.name),
+                KeyValue::new("cache.result", result),
+            ],
+        );
+        lookup
+    }
+
+    /// Caches `value` read for `key`, unless something was invalidated since
+    /// `generation` was handed out.
+    pub fn put(&self, key: K, value: Option<V>, generation: u64) {
+        let ttl = if value.is_some() {
+            self.ttl
+        } else {
+            self.negative_ttl
+        };
+        if ttl.is_zero() {
+            return;
+        }
+        let mut inner = self.lock();
+        if inner.generation == generation {
+            inner.entries.insert(key, value, Instant::now() + ttl);
+        }
+    }
+
+    /// The generation to cache a read under that did not look the cache up,
+    /// e.g. a consistent one.
+    pub fn generation(&self) -> u64 {
+        self.lock().generation
+    }
+
+    pub fn invalidate(&self, key: &K) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        inner.entries.remove(key);
+    }
+
+    /// Invalidates the cached values `f` returns true for.
+    pub fn invalidate_where(&self, mut f: impl FnMut(&V) -> bool) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        inner
+            .entries
+            .remove_where(|value| value.as_ref().is_some_and(&mut f));
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use oxidize_domain::{
+    GetStaffQuery, ListStaffQuery, Result, Staff, StaffId, StaffRepository, TenantId,
+};
+
+use super::{Cache, Lookup};
+use crate::database::tenant_scope;
+use crate::environment::CacheConfig;
+
+/// Staff cached by ID and by `auth_uid`, shared with the
+/// `CachedTenantRepository` that deletes staff along with their tenant.
+pub struct StaffCaches {
+    by_id: Cache<String, Staff>,
+    by_auth_uid: Cache<String, Staff>,
+}
+
+impl StaffCaches {
+    /// Invalidates `staff`, including a cached miss under its ID or auth UID.
+    pub(super) fn invalidate(&self, staff: &Staff) {
+        self.by_id.invalidate(&staff.id.as_str().to_string());
+        self.by_auth_uid.invalidate(&staff.auth_uid);
+    }
+
+    /// Invalidates every cached staff of `tenant_id`.
+    pub(super) fn invalidate_tenant(&self, tenant_id: &TenantId) {
+        self.by_id
+            .invalidate_where(|staff| staff.tenant_id == *tenant_id);
+        self.by_auth_uid
+            .invalidate_where(|staff| staff.tenant_id == *tenant_id);
+    }
+}
+
+/// Caches staff by ID and by `auth_uid` in front of `R`. Reads with their
+/// tenant, lists and counts are not cached.
+pub struct CachedStaffRepository<R: StaffRepository> {
+    inner: R,
+    /// `None` unless `cache.enabled`.
+    caches: Option<Arc<StaffCaches>>,
+}
+
+impl<R: StaffRepository> CachedStaffRepository<R> {
+    pub fn new(inner: R, config: &CacheConfig) -> Self {
+        Self {
+            inner,
+            caches: config.enabled.then(|| {
+                Arc::new(StaffCaches {
+                    by_id: Cache::new("staff", config),
+                    by_auth_uid: Cache::new("staff_auth_uid", config),
+                })
+            }),
+        }
+    }
+
+    /// The caches to hand to the `CachedTenantRepository` in front of the
+    /// same database.
+    pub fn caches(&self) -> Option<Arc<StaffCaches>> {
+        self.caches.clone()
+    }
+
+    fn invalidate(&self, staff: &Staff) {
+        if let Some(caches) = &self.caches {
+            caches.invalidate(staff);
+        }
+    }
+}
+
+#[async_trait]
+impl<R: StaffRepository> StaffRepository for CachedStaffRepository<R> {
+    async fn get(&self, query: GetStaffQuery) -> Result<Option<Staff>> {
+        let scoped = tenant_scope().is_some();
+        let Some(caches) = self
+            .caches
+            .as_ref()
+            .filter(|_| !query.with_tenant && !scoped)
+        else {
+            return self.inner.get(query).await;
+        };
+        let (cache, key) = match (&query.id, &query.auth_uid) {
+            (Some(id), None) => (&caches.by_id, id.as_str().to_string()),
+            (None, Some(auth_uid)) => (&caches.by_auth_uid, auth_uid.clone()),
+            _ => return self.inner.get(query).await,
+        };
+        // Consistent reads must not be served from the cache, but may fill it.
+        let generation = if query.consistent {
+            cache.generation()
+        } else {
+            match cache.get(&key) {
+                Lookup::Hit(staff) => return Ok(staff),
+                Lookup::Miss(generation) => generation,
+            }
+        };
+
+        let staff = self.inner.get(query).await?;
+        cache.put(key, staff.clone(), generation);
+        Ok(staff)
+    }
+
+    async fn list(&self, query: ListStaffQuery) -> Result<Vec<Staff>> {
+        self.inner.list(query).await
+    }
+
+    async fn count(&self, query: ListStaffQuery) -> Result<u64> {
+        self.inner.count(query).await
+    }
+
+    async fn create(&self, staff: &Staff) -> Result<()> {
+        let result = self.inner.create(staff).await;
+        self.invalidate(staff);
+        result
+    }
+
+    async fn update(&self, staff: &Staff) -> Result<bool> {
+        let result = self.inner.update(staff).await;
+        self.invalidate(staff);
+        result
+    }
+
+    async fn delete(&self, id: &StaffId) -> Result<bool> {
+        let result = self.inner.delete(id).await;
+        if let Some(caches) = &self.caches {
+            caches.by_id.invalidate(&id.as_str().to_string());
+            // Only the staff knows its auth UID; deletes are rare enough to
+            // look for it.
+            caches.by_auth_uid.invalidate_where(|staff| staff.id == *id);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use oxidize_domain::StaffRole;
+
+    use super::super::fake::{config, FakeRepository};
+    use super::*;
+
+    fn staff(auth_uid: &str) -> Staff {
+        let tenant_id = TenantId::from_string("t1".to_string());
+        let email = format!("{}@example.com", auth_uid);
+        let (name, image) = (auth_uid.to_string(), String::new());
+        Staff::new(
+            tenant_id,
+            StaffRole::Admin,
+            auth_uid.to_string(),
+            name,
+            image,
+            email,
+            Utc::now(),
+        )
+    }
+
+    fn by_auth_uid(auth_uid: &str) -> GetStaffQuery {
+        GetStaffQuery {
+            auth_uid: Some(auth_uid.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_caches_hits_and_misses_until_written() {
+        let fake = FakeRepository::default();
+        let repo = CachedStaffRepository::new(fake.clone(), &config());
+
+        assert!(repo.get(by_auth_uid("alice")).await.unwrap().is_none());
+        assert!(repo.get(by_auth_uid("alice")).await.unwrap().is_none());
+        assert_eq!(fake.reads(), 1);
+
+        // Creating the staff drops the cached miss.
+        let alice = staff("alice");
+        repo.create(&alice).await.unwrap();
+        let found = repo.get(by_auth_uid("alice")).await.unwrap().unwrap();
+        assert_eq!(found.id, alice.id);
+        repo.get(by_auth_uid("alice")).await.unwrap();
+        assert_eq!(fake.reads(), 2);
+
+        // A consistent read goes to the store.
+        let query = GetStaffQuery {
+            consistent: true,
+            ..by_auth_uid("alice")
+        };
+        repo.get(query).await.unwrap();
+        assert_eq!(fake.reads(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_writes_invalidate_by_id_and_auth_uid() {
+        let fake = FakeRepository::default();
+        let repo = CachedStaffRepository::new(fake.clone(), &config());
+        let mut alice = staff("alice");
+        repo.create(&alice).await.unwrap();
+        let by_id = || GetStaffQuery {
+            id: Some(alice.id.clone()),
+            ..Default::default()
+        };
+        repo.get(by_id()).await.unwrap();
+        repo.get(by_auth_uid("alice")).await.unwrap();
+
+        alice.display_name = "Alice".to_string();
+        assert!(repo.update(&alice).await.unwrap());
+        let found = repo.get(by_id()).await.unwrap().unwrap();
+        assert_eq!(found.display_name, "Alice");
+
+        assert!(repo.delete(&alice.id).await.unwrap());
+        assert!(repo.get(by_id()).await.unwrap().is_none());
+        assert!(repo.get(by_auth_uid("alice")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_writes_elsewhere_are_served_until_expired() {
+        let fake = FakeRepository::default();
+        let repo = CachedStaffRepository::new(fake.clone(), &config());
+        let alice = staff("alice");
+        repo.create(&alice).await.unwrap();
+        repo.get(by_auth_uid("alice")).await.unwrap();
+
+        fake.remove_staff(&alice.id);
+        assert!(repo.get(by_auth_uid("alice")).await.unwrap().is_some());
+
+        let uncached = CachedStaffRepository::new(fake, &CacheConfig::default());
+        assert!(uncached.get(by_auth_uid("alice")).await.unwrap().is_none());
+    }
+}
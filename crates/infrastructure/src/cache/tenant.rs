@@ -0,0 +1,295 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use oxidize_domain::{
+    GetTenantQuery, ListTenantQuery, Result, Staff, Tenant, TenantId, TenantRepository,
+};
+
+use super::staff::StaffCaches;
+use super::{Cache, Lookup};
+use crate::database::tenant_scope;
+use crate::environment::CacheConfig;
+
+/// Caches tenants by ID in front of `R`; lists and counts are not cached.
+/// Deleting a tenant also evicts its staff from `staff`, the caches of the
+/// `CachedStaffRepository` in front of the same database.
+pub struct CachedTenantRepository<R: TenantRepository> {
+    inner: R,
+    /// `None` unless `cache.enabled`.
+    by_id: Option<Cache<String, Tenant>>,
+    staff: Option<Arc<StaffCaches>>,
+}
+
+impl<R: TenantRepository> CachedTenantRepository<R> {
+    pub fn new(inner: R, config: &CacheConfig, staff: Option<Arc<StaffCaches>>) -> Self {
+        Self {
+            inner,
+            by_id: config.enabled.then(|| Cache::new("tenant", config)),
+            staff,
+        }
+    }
+
+    fn invalidate(&self, id: &TenantId) {
+        if let Some(cache) = &self.by_id {
+            cache.invalidate(&id.as_str().to_string());
+        }
+    }
+
+    /// Invalidates `id` and its staff, deleted with it.
+    fn invalidate_with_staff(&self, id: &TenantId) {
+        self.invalidate(id);
+        if let Some(staff) = &self.staff {
+            staff.invalidate_tenant(id);
+        }
+    }
+}
+
+#[async_trait]
+impl<R: TenantRepository> TenantRepository for CachedTenantRepository<R> {
+    async fn get(&self, query: GetTenantQuery) -> Result<Option<Tenant>> {
+        let (Some(cache), Some(id), None) = (&self.by_id, &query.id, tenant_scope()) else {
+            return self.inner.get(query).await;
+        };
+        let key = id.as_str().to_string();
+        // Consistent reads must not be served from the cache, but may fill it.
+        let generation = if query.consistent {
+            cache.generation()
+        } else {
+            match cache.get(&key) {
+                Lookup::Hit(tenant) => return Ok(tenant),
+                Lookup::Miss(generation) => generation,
+            }
+        };
+
+        let tenant = self.inner.get(query).await?;
+        cache.put(key, tenant.clone(), generation);
+        Ok(tenant)
+    }
+
+    async fn list(&self, query: ListTenantQuery) -> Result<Vec<Tenant>> {
+        self.inner.list(query).await
+    }
+
+    async fn count(&self, query: ListTenantQuery) -> Result<u64> {
+        self.inner.count(query).await
+    }
+
+    async fn create(&self, tenant: &Tenant) -> Result<()> {
+        let result = self.inner.create(tenant).await;
+        // Drops a cached miss, e.g. for an imported tenant keeping its ID.
+        self.invalidate(&tenant.id);
+        result
+    }
+
+    async fn import(&self, tenant: &Tenant, staff: &[Staff]) -> Result<()> {
+        let result = self.inner.import(tenant, staff).await;
+        self.invalidate(&tenant.id);
+        // Drops the misses cached for the imported staff.
+        if let Some(caches) = &self.staff {
+            staff.iter().for_each(|staff| caches.invalidate(staff));
+        }
+        result
+    }
+
+    async fn update(&self, tenant: &Tenant) -> Result<bool> {
+        let result = self.inner.update(tenant).await;
+        self.invalidate(&tenant.id);
+        result
+    }
+
+    async fn delete(&self, id: &TenantId, force: bool) -> Result<bool> {
+        let result = self.inner.delete(id, force).await;
+        self.invalidate_with_staff(id);
+        result
+    }
+
+    async fn list_unarchived(&self) -> Result<Vec<Tenant>> {
+        self.inner.list_unarchived().await
+    }
+
+    async fn record_archive(&self, tenant: &Tenant) -> Result<bool> {
+        let result = self.inner.record_archive(tenant).await;
+        self.invalidate(&tenant.id);
+        result
+    }
+
+    async fn list_purgeable(&self, now: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        self.inner.list_purgeable(now).await
+    }
+
+    async fn purge(&self, tenant: &Tenant, now: DateTime<Utc>) -> Result<bool> {
+        let result = self.inner.purge(tenant, now).await;
+        self.invalidate_with_staff(&tenant.id);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oxidize_domain::{GetStaffQuery, StaffRepository, StaffRole};
+
+    use super::super::fake::{config, FakeRepository};
+    use super::super::CachedStaffRepository;
+    use super::*;
+
+    struct Repos {
+        fake: FakeRepository,
+        tenants: CachedTenantRepository<FakeRepository>,
+        staff: CachedStaffRepository<FakeRepository>,
+    }
+
+    fn repos() -> Repos {
+        let fake = FakeRepository::default();
+        let staff = CachedStaffRepository::new(fake.clone(), &config());
+        let tenants = CachedTenantRepository::new(fake.clone(), &config(), staff.caches());
+        Repos {
+            fake,
+            tenants,
+            staff,
+        }
+    }
+
+    fn by_id(id: &TenantId) -> GetTenantQuery {
+        GetTenantQuery {
+            id: Some(id.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn by_auth_uid(auth_uid: &str) -> GetStaffQuery {
+        GetStaffQuery {
+            auth_uid: Some(auth_uid.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn staff(tenant: &Tenant, auth_uid: &str) -> Staff {
+        let email = format!("{}@example.com", auth_uid);
+        let (name, image) = (auth_uid.to_string(), String::new());
+        let (id, role) = (tenant.id.clone(), StaffRole::Admin);
+        Staff::new(
+            id,
+            role,
+            auth_uid.to_string(),
+            name,
+            image,
+            email,
+            Utc::now(),
+        )
+    }
+
+    /// A tenant with one staff, `auth_uid`, both cached.
+    async fn cached_tenant(repos: &Repos, auth_uid: &str) -> Tenant {
+        let tenant = Tenant::new(auth_uid.to_string(), Utc::now());
+        repos.tenants.create(&tenant).await.unwrap();
+        repos.staff.create(&staff(&tenant, auth_uid)).await.unwrap();
+        repos.tenants.get(by_id(&tenant.id)).await.unwrap();
+        repos.staff.get(by_auth_uid(auth_uid)).await.unwrap();
+        tenant
+    }
+
+    #[tokio::test]
+    async fn test_caches_misses_until_created() {
+        let repos = repos();
+        let tenant = Tenant::new("Acme".to_string(), Utc::now());
+
+        assert!(repos
+            .tenants
+            .get(by_id(&tenant.id))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repos
+            .tenants
+            .get(by_id(&tenant.id))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(repos.fake.reads(), 1);
+
+        repos.tenants.create(&tenant).await.unwrap();
+        assert!(repos
+            .tenants
+            .get(by_id(&tenant.id))
+            .await
+            .unwrap()
+            .is_some());
+        repos.tenants.get(by_id(&tenant.id)).await.unwrap();
+        assert_eq!(repos.fake.reads(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_update_invalidates() {
+        let repos = repos();
+        let mut tenant = cached_tenant(&repos, "alice").await;
+
+        tenant.update(Some("Renamed".to_string()), Utc::now());
+        assert!(repos.tenants.update(&tenant).await.unwrap());
+        let found = repos.tenants.get(by_id(&tenant.id)).await.unwrap().unwrap();
+        assert_eq!(found.name, "Renamed");
+    }
+
+    #[tokio::test]
+    async fn test_delete_evicts_the_staff_deleted_with_the_tenant() {
+        let repos = repos();
+        let tenant = cached_tenant(&repos, "alice").await;
+        let other = cached_tenant(&repos, "bob").await;
+
+        assert!(repos.tenants.delete(&tenant.id, true).await.unwrap());
+        assert!(repos
+            .tenants
+            .get(by_id(&tenant.id))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repos
+            .staff
+            .get(by_auth_uid("alice"))
+            .await
+            .unwrap()
+            .is_none());
+
+        // Other tenants' staff stay cached.
+        let reads = repos.fake.reads();
+        repos.staff.get(by_auth_uid("bob")).await.unwrap();
+        assert_eq!(repos.fake.reads(), reads);
+        assert!(repos.tenants.get(by_id(&other.id)).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_purge_evicts_the_staff_deleted_with_the_tenant() {
+        let repos = repos();
+        let tenant = cached_tenant(&repos, "alice").await;
+
+        assert!(repos.tenants.purge(&tenant, Utc::now()).await.unwrap());
+        assert!(repos
+            .staff
+            .get(by_auth_uid("alice"))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_drops_misses_cached_for_its_staff() {
+        let repos = repos();
+        let tenant = Tenant::new("Acme".to_string(), Utc::now());
+        let alice = staff(&tenant, "alice");
+        assert!(repos
+            .staff
+            .get(by_auth_uid("alice"))
+            .await
+            .unwrap()
+            .is_none());
+
+        repos.tenants.import(&tenant, &[alice]).await.unwrap();
+        assert!(repos
+            .staff
+            .get(by_auth_uid("alice"))
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
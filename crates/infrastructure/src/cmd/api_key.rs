@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use serde_json::json;
+
+use oxidize_domain::{ApiKey, ApiKeyId, TenantId};
+use oxidize_usecase::{CreateApiKeyInput, ListApiKeyInput, RevokeApiKeyInput};
+
+use super::output::{print_json, OutputFormat, Table};
+use super::root::ApiKeyCommand;
+use crate::registry::Registry;
+
+fn print_api_keys(api_keys: &[ApiKey], output: OutputFormat) -> anyhow::Result<()> {
+    match output {
+        OutputFormat::Json => print_json(&api_keys),
+        OutputFormat::Table => {
+            let mut table = Table::new(vec![
+                "ID",
+                "NAME",
+                "PREFIX",
+                "SCOPES",
+                "EXPIRES_AT",
+                "LAST_USED_AT",
+                "REVOKED_AT",
+            ]);
+            let time = |t: Option<chrono::DateTime<Utc>>| t.map(|t| t.to_rfc3339());
+            for k in api_keys {
+                let scopes: Vec<&str> = k.scopes.iter().map(|s| s.as_str()).collect();
+                table.push(vec![
+                    k.id.as_str().to_string(),
+                    k.name.clone(),
+                    k.prefix.clone(),
+                    scopes.join(","),
+                    time(k.expires_at).unwrap_or_else(|| "never".to_string()),
+                    time(k.last_used_at).unwrap_or_default(),
+                    time(k.revoked_at).unwrap_or_default(),
+                ]);
+            }
+            println!("{}", table.render());
+            Ok(())
+        }
+    }
+}
+
+pub async fn run_api_key(
+    command: ApiKeyCommand,
+    output: OutputFormat,
+    registry: Arc<Registry>,
+) -> anyhow::Result<()> {
+    let interactor = &registry.api_key_interactor;
+
+    match command {
+        ApiKeyCommand::List { tenant_id } => {
+            let input = ListApiKeyInput {
+                tenant_id: TenantId::from_string(tenant_id),
+            };
+            let api_keys = interactor.list(input).await?;
+            print_api_keys(&api_keys, output)?;
+        }
+        ApiKeyCommand::Create {
+            tenant_id,
+            name,
+            scopes,
+            expires_in_days,
+        } => {
+            let input = CreateApiKeyInput {
+                tenant_id: TenantId::from_string(tenant_id),
+                name,
+                scopes,
+                expires_at: expires_in_days.map(|days| Utc::now() + Duration::days(days.into())),
+            };
+            let created = interactor.create(input).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "api_key": created.api_key,
+                    "key": created.key,
+                }))?,
+                OutputFormat::Table => {
+                    print_api_keys(&[created.api_key], output)?;
+                    println!("{}", created.key);
+                    eprintln!("Store this key now; it cannot be shown again.");
+                }
+            }
+        }
+        ApiKeyCommand::Revoke { id, tenant_id } => {
+            let input = RevokeApiKeyInput {
+                tenant_id: TenantId::from_string(tenant_id),
+                id: ApiKeyId::from_string(id),
+            };
+            let api_key = interactor.revoke(input).await?;
+            print_api_keys(&[api_key], output)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use oxidize_domain::TenantId;
+use oxidize_usecase::{ExportTenantInput, ImportIdMode, ImportTenantInput};
+
+use super::root::ExportTarget;
+use crate::archive::{ArchiveFormat, TenantArchive};
+use crate::registry::Registry;
+
+pub async fn run_export(target: ExportTarget, registry: Arc<Registry>) -> anyhow::Result<()> {
+    match target {
+        ExportTarget::Tenant { id, output, format } => {
+            let input = ExportTenantInput {
+                id: TenantId::from_string(id),
+            };
+            let exported = registry.archive_interactor.export_tenant(input).await?;
+            let archive = TenantArchive::new(exported.tenant, exported.staff);
+
+            match output {
+                Some(path) => archive.write(format, BufWriter::new(File::create(path)?))?,
+                None => archive.write(format, io::stdout().lock())?,
+            }
+
+            tracing::info!(
+                "Exported tenant {} with {} staff",
+                archive.tenant.id.as_str(),
+                archive.staffs.len()
+            );
+            Ok(())
+        }
+    }
+}
+
+pub async fn run_import(
+    path: PathBuf,
+    format: ArchiveFormat,
+    id_mode: ImportIdMode,
+    registry: Arc<Registry>,
+) -> anyhow::Result<()> {
+    let archive = if path == Path::new("-") {
+        TenantArchive::read(format, io::stdin().lock())?
+    } else {
+        TenantArchive::read(format, BufReader::new(File::open(&path)?))?
+    };
+
+    let input = ImportTenantInput {
+        tenant: archive.tenant,
+        staff: archive.staffs,
+        id_mode,
+    };
+    let imported = registry.archive_interactor.import_tenant(input).await?;
+
+    tracing::info!(
+        "Imported tenant {} with {} staff",
+        imported.tenant.id.as_str(),
+        imported.staff.len()
+    );
+    println!("{}", imported.tenant.id.as_str());
+    Ok(())
+}
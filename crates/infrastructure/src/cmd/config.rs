@@ -0,0 +1,11 @@
+use std::io::{self, Write};
+
+use super::root::ConfigCommand;
+use crate::environment::Environment;
+
+pub fn run_config(command: ConfigCommand, env: &Environment) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Print => write!(io::stdout().lock(), "{}", toml::to_string(env)?)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use sqlx::PgPool;
+
+use super::output::Table;
+use super::root::MigrateCommand;
+use crate::database::{migration_status, pending_migrations, revertible_migrations, MIGRATOR};
+
+pub async fn run_migrate(
+    command: Option<MigrateCommand>,
+    dry_run: bool,
+    pool: PgPool,
+) -> anyhow::Result<()> {
+    match command {
+        None if dry_run => {
+            let pending = pending_migrations(&pool).await?;
+            if pending.is_empty() {
+                tracing::info!("No pending migrations");
+            }
+            for m in pending {
+                println!("-- {} {}\n{}", m.version, m.description, m.sql.trim_end());
+            }
+        }
+        None => {
+            tracing::info!("Running migrations...");
+            MIGRATOR.run(&pool).await?;
+            tracing::info!("Migrations completed");
+        }
+        Some(MigrateCommand::Status) => {
+            let mut table = Table::new(vec!["VERSION", "DESCRIPTION", "STATUS", "CHECKSUM"]);
+            for s in migration_status(&pool).await? {
+                table.push(vec![
+                    s.version.to_string(),
+                    s.description,
+                    s.state.as_str().to_string(),
+                    s.checksum[..16].to_string(),
+                ]);
+            }
+            println!("{}", table.render());
+        }
+        Some(MigrateCommand::Down { to }) => {
+            let migrations = revertible_migrations(&pool, to).await?;
+            if migrations.is_empty() {
+                tracing::info!("Nothing to revert down to version {}", to);
+                return Ok(());
+            }
+
+            if dry_run {
+                for m in migrations {
+                    println!("-- {} {}\n{}", m.version, m.description, m.sql.trim_end());
+                }
+            } else {
+                tracing::info!(
+                    "Reverting {} migrations down to version {}",
+                    migrations.len(),
+                    to
+                );
+                MIGRATOR.undo(&pool, to).await?;
+                tracing::info!("Migrations reverted");
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,24 @@
+mod api_key;
+mod archive;
+mod config;
+mod migrate;
+mod openapi;
+mod output;
 mod root;
+mod seed;
+mod staff;
+mod tenant;
 
-pub use root::{Cli, Commands};
+pub use api_key::run_api_key;
+pub use archive::{run_export, run_import};
+pub use config::run_config;
+pub use migrate::run_migrate;
+pub use openapi::run_openapi;
+pub use output::OutputFormat;
+pub use root::{
+    ApiKeyCommand, Cli, Commands, ConfigCommand, ExportTarget, IdMode, MigrateCommand,
+    StaffCommand, TenantCommand,
+};
+pub use seed::run_seed;
+pub use staff::run_staff;
+pub use tenant::run_tenant;
@@ -0,0 +1,20 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::http::openapi;
+
+pub fn run_openapi(output: Option<PathBuf>) -> anyhow::Result<()> {
+    let document = openapi().to_pretty_json()?;
+
+    match output {
+        Some(path) => {
+            let mut file = BufWriter::new(File::create(&path)?);
+            writeln!(file, "{}", document)?;
+            file.flush()?;
+            tracing::info!("Wrote OpenAPI document to {}", path.display());
+        }
+        None => writeln!(io::stdout().lock(), "{}", document)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable aligned columns
+    #[default]
+    Table,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// Minimal column-aligned table for admin command output.
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: Vec<&'static str>) -> Self {
+        Self {
+            headers,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let format_row = |cells: Vec<&str>| {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            line.join("  ").trim_end().to_string()
+        };
+
+        let mut lines = vec![format_row(self.headers.clone())];
+        for row in &self.rows {
+            lines.push(format_row(row.iter().map(String::as_str).collect()));
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn print_json(value: &impl Serialize) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let mut table = Table::new(vec!["ID", "NAME"]);
+        table.push(vec!["1".to_string(), "Acme".to_string()]);
+        table.push(vec!["100".to_string(), "B".to_string()]);
+
+        assert_eq!(table.render(), "ID   NAME\n1    Acme\n100  B");
+    }
+}
@@ -1,25 +1,358 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Parser, Subcommand, ValueEnum};
+use oxidize_domain::{ApiKeyScope, StaffRole, TenantTagType};
+use oxidize_usecase::ImportIdMode;
+
+use super::output::OutputFormat;
+use crate::archive::ArchiveFormat;
+use crate::environment::{Environment, LogFormat, Secret};
 
 #[derive(Parser)]
 #[command(name = "oxidize")]
 #[command(about = "Rust API boilerplate with Clean Architecture")]
 pub struct Cli {
+    /// TOML config file [default: $OXIDIZE_CONFIG, else ./oxidize.toml if present]
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Overrides database.url
+    #[arg(long, global = true, value_name = "URL")]
+    pub database_url: Option<String>,
+    /// Overrides telemetry.log_format (full, pretty, compact or json)
+    #[arg(long, global = true, value_name = "FORMAT")]
+    pub log_format: Option<LogFormat>,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+impl Cli {
+    /// The command-line layer of the configuration, applied last.
+    pub fn apply(&self, env: &mut Environment) {
+        if let Some(url) = &self.database_url {
+            env.database.url = Some(Secret::new(url));
+        }
+        if let Some(format) = self.log_format {
+            env.telemetry.log_format = format;
+        }
+        match self.command {
+            Commands::HttpServer { port: Some(port) } => env.server.http_port = port,
+            Commands::GrpcServer { port: Some(port) } => env.server.grpc_port = port,
+            Commands::Serve {
+                http_port,
+                grpc_port,
+                port,
+            } => {
+                // Separate ports on the command line win over a configured
+                // single port.
+                if let Some(http_port) = http_port {
+                    env.server.http_port = http_port;
+                    env.server.port = None;
+                }
+                if let Some(grpc_port) = grpc_port {
+                    env.server.grpc_port = grpc_port;
+                    env.server.port = None;
+                }
+                if port.is_some() {
+                    env.server.port = port;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Run HTTP server
     HttpServer {
-        #[arg(short, long, default_value = "8080")]
-        port: u16,
+        /// Overrides server.http_port [default: 8080]
+        #[arg(short, long)]
+        port: Option<u16>,
     },
     /// Run gRPC server
     GrpcServer {
-        #[arg(short, long, default_value = "50051")]
-        port: u16,
+        /// Overrides server.grpc_port [default: 50051]
+        #[arg(short, long)]
+        port: Option<u16>,
+    },
+    /// Run HTTP and gRPC servers in one process
+    Serve {
+        /// Overrides server.http_port [default: 8080]
+        #[arg(long)]
+        http_port: Option<u16>,
+        /// Overrides server.grpc_port [default: 50051]
+        #[arg(long)]
+        grpc_port: Option<u16>,
+        /// Serve both protocols on this single port, routed by content type
+        /// (overrides server.port)
+        #[arg(short, long, conflicts_with_all = ["http_port", "grpc_port"])]
+        port: Option<u16>,
     },
     /// Run database migrations
-    Migrate,
+    Migrate {
+        #[command(subcommand)]
+        command: Option<MigrateCommand>,
+        /// Print the SQL that would run instead of executing it
+        #[arg(long, global = true)]
+        dry_run: bool,
+    },
+    /// Populate the database with fake tenants, tags and staff
+    Seed {
+        /// Number of tenants to create
+        #[arg(long, default_value = "10")]
+        tenants: u32,
+        /// Number of staff per tenant
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+        staff_per_tenant: u32,
+        /// Seed for the fake data; re-running with the same seed creates nothing new
+        #[arg(long, default_value = "0")]
+        seed: u64,
+    },
+    /// Manage tenants
+    Tenant {
+        #[command(subcommand)]
+        command: TenantCommand,
+        #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// Manage staff
+    Staff {
+        #[command(subcommand)]
+        command: StaffCommand,
+        #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// Manage tenants' API keys
+    ApiKey {
+        #[command(subcommand)]
+        command: ApiKeyCommand,
+        #[arg(short, long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+        output: OutputFormat,
+    },
+    /// Export data as a portable archive
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+    /// Import a tenant archive produced by `export tenant`
+    Import {
+        /// Archive file to read ("-" for stdin)
+        path: PathBuf,
+        #[arg(long, value_enum, default_value_t = ArchiveFormat::Json)]
+        format: ArchiveFormat,
+        /// Keep the archived IDs or assign new ones
+        #[arg(long, value_enum, default_value_t = IdMode::Keep)]
+        id_mode: IdMode,
+    },
+    /// Print the OpenAPI document of the HTTP API
+    Openapi {
+        /// File to write instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration as TOML, with secrets redacted
+    Print,
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// List applied and pending migrations with their checksums
+    Status,
+    /// Revert applied migrations newer than the given version
+    Down {
+        /// Version to revert to (0 reverts everything)
+        #[arg(long)]
+        to: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TenantCommand {
+    /// List tenants
+    List {
+        #[arg(long)]
+        limit: Option<u64>,
+        #[arg(long)]
+        offset: Option<u64>,
+    },
+    /// Show a tenant
+    Get { id: String },
+    /// Create a tenant
+    Create {
+        #[arg(long)]
+        name: String,
+        /// Tag to attach (repeatable)
+        #[arg(long = "tag", value_parser = tag_type_parser())]
+        tags: Vec<TenantTagType>,
+    },
+    /// Update a tenant
+    Update {
+        id: String,
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Delete a tenant, refused while it has staff unless --force is given
+    Delete {
+        id: String,
+        /// Succeed if the tenant does not exist
+        #[arg(long)]
+        allow_missing: bool,
+        /// Delete the tenant's staff along with it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Refuse access to a tenant, archive it and purge it after a retention period
+    Close {
+        id: String,
+        /// Days to keep the tenant [default: tenants.retention_days]
+        #[arg(long)]
+        retention_days: Option<u32>,
+    },
+    /// Cancel the purge of a closed tenant and restore access
+    Reopen { id: String },
+    /// Archive closed tenants, then delete those whose retention period has
+    /// passed and whose archive reads back intact
+    Purge,
+}
+
+#[derive(Subcommand)]
+pub enum StaffCommand {
+    /// List staff
+    List {
+        #[arg(long)]
+        tenant_id: Option<String>,
+        #[arg(long)]
+        limit: Option<u64>,
+        #[arg(long)]
+        offset: Option<u64>,
+    },
+    /// Show a staff by ID or auth UID
+    Get {
+        #[arg(required_unless_present = "auth_uid")]
+        id: Option<String>,
+        #[arg(long, conflicts_with = "id")]
+        auth_uid: Option<String>,
+    },
+    /// Create a staff
+    Create {
+        #[arg(long)]
+        tenant_id: String,
+        #[arg(long, value_parser = role_parser())]
+        role: StaffRole,
+        #[arg(long)]
+        auth_uid: String,
+        #[arg(long)]
+        display_name: String,
+        #[arg(long, default_value = "")]
+        image_path: String,
+        #[arg(long)]
+        email: String,
+    },
+    /// Update a staff
+    Update {
+        id: String,
+        #[arg(long, value_parser = role_parser())]
+        role: Option<StaffRole>,
+        #[arg(long)]
+        display_name: Option<String>,
+        #[arg(long)]
+        image_path: Option<String>,
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// Delete a staff
+    Delete {
+        id: String,
+        /// Succeed if the staff does not exist
+        #[arg(long)]
+        allow_missing: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApiKeyCommand {
+    /// List a tenant's API keys, revoked and expired ones included
+    List {
+        #[arg(long)]
+        tenant_id: String,
+    },
+    /// Create an API key and print it; it cannot be shown again
+    Create {
+        #[arg(long)]
+        tenant_id: String,
+        #[arg(long)]
+        name: String,
+        /// Scope to grant (repeatable)
+        #[arg(long = "scope", required = true, value_parser = scope_parser())]
+        scopes: Vec<ApiKeyScope>,
+        /// Days until the key expires [default: never]
+        #[arg(long)]
+        expires_in_days: Option<u32>,
+    },
+    /// Revoke an API key
+    Revoke {
+        id: String,
+        #[arg(long)]
+        tenant_id: String,
+    },
+}
+
+fn role_parser() -> impl TypedValueParser<Value = StaffRole> {
+    PossibleValuesParser::new([StaffRole::Normal.as_str(), StaffRole::Admin.as_str()])
+        .map(|s| s.parse::<StaffRole>().unwrap_or_default())
+}
+
+fn scope_parser() -> impl TypedValueParser<Value = ApiKeyScope> {
+    PossibleValuesParser::new(ApiKeyScope::ALL.map(|s| s.as_str()))
+        .map(|s| s.parse::<ApiKeyScope>().expect("one of ApiKeyScope::ALL"))
+}
+
+fn tag_type_parser() -> impl TypedValueParser<Value = TenantTagType> {
+    PossibleValuesParser::new([
+        TenantTagType::Entertainment.as_str(),
+        TenantTagType::Education.as_str(),
+        TenantTagType::Business.as_str(),
+        TenantTagType::Other.as_str(),
+    ])
+    .map(|s| s.parse::<TenantTagType>().unwrap_or_default())
+}
+
+#[derive(Subcommand)]
+pub enum ExportTarget {
+    /// Export a tenant with its tags and staff
+    Tenant {
+        id: String,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ArchiveFormat::Json)]
+        format: ArchiveFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum IdMode {
+    /// Keep archived IDs (fails if the tenant already exists)
+    Keep,
+    /// Assign fresh IDs to the tenant, its tags and its staff
+    Remap,
+}
+
+impl From<IdMode> for ImportIdMode {
+    fn from(mode: IdMode) -> Self {
+        match mode {
+            IdMode::Keep => ImportIdMode::Keep,
+            IdMode::Remap => ImportIdMode::Remap,
+        }
+    }
 }
@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use uuid::Builder;
+
+use oxidize_domain::{Staff, StaffRole, Tenant, TenantId, TenantTag, TenantTagType};
+use oxidize_usecase::{
+    CreateStaffInput, GetStaffInput, GetTenantInput, ImportIdMode, ImportTenantInput,
+};
+
+use crate::registry::Registry;
+
+const TAG_TYPES: [TenantTagType; 4] = [
+    TenantTagType::Entertainment,
+    TenantTagType::Education,
+    TenantTagType::Business,
+    TenantTagType::Other,
+];
+
+const COMPANY_PREFIXES: &[&str] = &[
+    "Blue",
+    "Northwind",
+    "Summit",
+    "Evergreen",
+    "Silver",
+    "Harbor",
+    "Maple",
+    "Granite",
+    "Crescent",
+    "Pioneer",
+    "Redwood",
+    "Lakeside",
+];
+
+const COMPANY_SUFFIXES: &[&str] = &[
+    "Studios",
+    "Academy",
+    "Holdings",
+    "Labs",
+    "Media",
+    "Learning",
+    "Partners",
+    "Works",
+    "Collective",
+    "Logistics",
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "Emma", "Liam", "Olivia", "Noah", "Ava", "Mateo", "Sophia", "Lucas", "Mia", "Hiro", "Yuki",
+    "Priya", "Arjun", "Chloe", "Omar", "Lena", "Diego", "Aisha", "Felix", "Nora",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smith",
+    "Garcia",
+    "Tanaka",
+    "Kim",
+    "Nguyen",
+    "Müller",
+    "Rossi",
+    "Silva",
+    "Patel",
+    "Cohen",
+    "Johansson",
+    "Dubois",
+    "Kowalski",
+    "O'Brien",
+    "Sato",
+    "Haddad",
+];
+
+struct FakeTenant {
+    id: TenantId,
+    name: String,
+    domain: String,
+    tags: Vec<TenantTagType>,
+    staff: Vec<FakeStaff>,
+}
+
+struct FakeStaff {
+    auth_uid: String,
+    role: StaffRole,
+    display_name: String,
+    email: String,
+}
+
+/// Builds the fixtures for tenant `index`. Each tenant draws from its own
+/// ChaCha stream so the output does not depend on which tenants already exist.
+fn fake_tenant(seed: u64, index: u32, staff_per_tenant: u32) -> FakeTenant {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(index as u64);
+
+    // Drawn first, so it does not depend on `staff_per_tenant`.
+    let id = Builder::from_random_bytes(rng.gen()).into_uuid();
+    let name = format!(
+        "{} {}",
+        COMPANY_PREFIXES.choose(&mut rng).unwrap(),
+        COMPANY_SUFFIXES.choose(&mut rng).unwrap()
+    );
+    let domain = format!(
+        "{}-{}.example.com",
+        name.to_lowercase().replace(' ', "-"),
+        index
+    );
+
+    // Rotate the primary tag so every type is covered once there are enough tenants.
+    let mut tags = vec![TAG_TYPES[index as usize % TAG_TYPES.len()]];
+    if rng.gen_bool(0.5) {
+        let extra = *TAG_TYPES.choose(&mut rng).unwrap();
+        if !tags.contains(&extra) {
+            tags.push(extra);
+        }
+    }
+
+    let staff = (0..staff_per_tenant)
+        .map(|i| {
+            let first = FIRST_NAMES.choose(&mut rng).unwrap();
+            let last = LAST_NAMES.choose(&mut rng).unwrap();
+            // Every tenant starts with an admin and a normal staff, so any tenant
+            // with two or more staff covers each role.
+            let role = match i {
+                0 => StaffRole::Admin,
+                1 => StaffRole::Normal,
+                _ if rng.gen_bool(0.2) => StaffRole::Admin,
+                _ => StaffRole::Normal,
+            };
+            let local_part: String = format!("{}.{}", first, last)
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '.')
+                .collect();
+            FakeStaff {
+                auth_uid: seed_auth_uid(seed, index, i),
+                role,
+                display_name: format!("{} {}", first, last),
+                email: format!("{}{}@{}", local_part, i, domain),
+            }
+        })
+        .collect();
+
+    FakeTenant {
+        id: TenantId::from_string(id.to_string()),
+        name,
+        domain,
+        tags,
+        staff,
+    }
+}
+
+/// Seeded staff are identified by a deterministic `auth_uid`, so that
+/// re-running a seed with more staff per tenant only adds the new ones.
+fn seed_auth_uid(seed: u64, tenant_index: u32, staff_index: u32) -> String {
+    format!("seed-{}-{}-{}", seed, tenant_index, staff_index)
+}
+
+pub async fn run_seed(
+    tenants: u32,
+    staff_per_tenant: u32,
+    seed: u64,
+    registry: Arc<Registry>,
+) -> anyhow::Result<()> {
+    let mut created_tenants = 0;
+    let mut created_staff = 0;
+
+    for index in 0..tenants {
+        let fake = fake_tenant(seed, index, staff_per_tenant);
+
+        // A tenant is created with its staff in one transaction, so one that
+        // exists under its seeded ID was seeded in full.
+        let existing = GetTenantInput {
+            id: fake.id.clone(),
+        };
+        if registry.tenant_interactor.get(existing).await?.is_none() {
+            created_staff += fake.staff.len();
+            let input = import_input(fake);
+            registry.archive_interactor.import_tenant(input).await?;
+            created_tenants += 1;
+            continue;
+        }
+
+        for staff in fake.staff {
+            let existing = GetStaffInput {
+                id: None,
+                auth_uid: Some(staff.auth_uid.clone()),
+                with_tenant: false,
+            };
+            if registry.staff_interactor.get(existing).await?.is_some() {
+                continue;
+            }
+
+            let input = CreateStaffInput {
+                tenant_id: fake.id.clone(),
+                role: staff.role,
+                auth_uid: staff.auth_uid.clone(),
+                display_name: staff.display_name,
+                image_path: format!("/images/{}/{}.png", fake.domain, staff.auth_uid),
+                email: staff.email,
+                idempotency_key: None,
+            };
+            registry.staff_interactor.create(input).await?;
+            created_staff += 1;
+        }
+    }
+
+    tracing::info!(
+        "Seed {} applied: created {} tenants and {} staff",
+        seed,
+        created_tenants,
+        created_staff
+    );
+    Ok(())
+}
+
+/// Creates `fake` under its seeded ID, with its staff.
+fn import_input(fake: FakeTenant) -> ImportTenantInput {
+    let now = Utc::now();
+    let mut tenant = Tenant::new(fake.name, now);
+    tenant.id = fake.id;
+    for tag_type in fake.tags {
+        tenant.add_tag(TenantTag::new(tag_type, now));
+    }
+    let staff = fake
+        .staff
+        .into_iter()
+        .map(|staff| {
+            let image_path = format!("/images/{}/{}.png", fake.domain, staff.auth_uid);
+            Staff::new(
+                tenant.id.clone(),
+                staff.role,
+                staff.auth_uid,
+                staff.display_name,
+                image_path,
+                staff.email,
+                now,
+            )
+        })
+        .collect();
+    ImportTenantInput {
+        tenant,
+        staff,
+        id_mode: ImportIdMode::Keep,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_tenant_is_deterministic() {
+        let a = fake_tenant(42, 3, 4);
+        let b = fake_tenant(42, 3, 4);
+
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.tags, b.tags);
+        let emails = |t: &FakeTenant| t.staff.iter().map(|s| s.email.clone()).collect::<Vec<_>>();
+        assert_eq!(emails(&a), emails(&b));
+    }
+
+    #[test]
+    fn test_tenant_id_does_not_depend_on_staff_count() {
+        assert_eq!(fake_tenant(42, 3, 0).id, fake_tenant(42, 3, 10).id);
+        assert_ne!(fake_tenant(42, 3, 2).id, fake_tenant(42, 4, 2).id);
+        assert_ne!(fake_tenant(42, 3, 2).id, fake_tenant(43, 3, 2).id);
+    }
+
+    #[test]
+    fn test_covers_every_role_and_tag_type() {
+        let tenants: Vec<FakeTenant> = (0..TAG_TYPES.len() as u32)
+            .map(|i| fake_tenant(7, i, 10))
+            .collect();
+
+        for tag_type in TAG_TYPES {
+            assert!(tenants.iter().any(|t| t.tags.contains(&tag_type)));
+        }
+        let roles: Vec<StaffRole> = tenants
+            .iter()
+            .flat_map(|t| t.staff.iter().map(|s| s.role))
+            .collect();
+        assert!(roles.contains(&StaffRole::Admin));
+        assert!(roles.contains(&StaffRole::Normal));
+    }
+}
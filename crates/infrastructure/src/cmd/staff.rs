@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use serde_json::json;
+
+use oxidize_domain::{errors, Staff, StaffId, TenantId};
+use oxidize_usecase::{
+    CreateStaffInput, DeleteStaffInput, GetStaffInput, ListStaffInput, UpdateStaffInput,
+};
+
+use super::output::{print_json, OutputFormat, Table};
+use super::root::StaffCommand;
+use crate::registry::Registry;
+
+fn print_staff(staff: &[Staff], output: OutputFormat) -> anyhow::Result<()> {
+    match output {
+        OutputFormat::Json => print_json(&staff),
+        OutputFormat::Table => {
+            let mut table = Table::new(vec![
+                "ID",
+                "TENANT_ID",
+                "ROLE",
+                "AUTH_UID",
+                "DISPLAY_NAME",
+                "EMAIL",
+                "CREATED_AT",
+            ]);
+            for s in staff {
+                table.push(vec![
+                    s.id.as_str().to_string(),
+                    s.tenant_id.as_str().to_string(),
+                    s.role.to_string(),
+                    s.auth_uid.clone(),
+                    s.display_name.clone(),
+                    s.email.clone(),
+                    s.created_at.to_rfc3339(),
+                ]);
+            }
+            println!("{}", table.render());
+            Ok(())
+        }
+    }
+}
+
+pub async fn run_staff(
+    command: StaffCommand,
+    output: OutputFormat,
+    registry: Arc<Registry>,
+) -> anyhow::Result<()> {
+    let interactor = &registry.staff_interactor;
+
+    match command {
+        StaffCommand::List {
+            tenant_id,
+            limit,
+            offset,
+        } => {
+            let input = ListStaffInput {
+                tenant_id: tenant_id.map(TenantId::from_string),
+                limit,
+                offset,
+            };
+            let result = interactor.list(input).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "staffs": result.staff,
+                    "total_count": result.total_count,
+                }))?,
+                OutputFormat::Table => {
+                    print_staff(&result.staff, output)?;
+                    println!("({} of {})", result.staff.len(), result.total_count);
+                }
+            }
+        }
+        StaffCommand::Get { id, auth_uid } => {
+            let input = GetStaffInput {
+                id: id.map(StaffId::from_string),
+                auth_uid,
+                with_tenant: false,
+            };
+            let staff = interactor
+                .get(input)
+                .await?
+                .ok_or_else(errors::staff_not_found)?;
+            print_staff(&[staff], output)?;
+        }
+        StaffCommand::Create {
+            tenant_id,
+            role,
+            auth_uid,
+            display_name,
+            image_path,
+            email,
+        } => {
+            let input = CreateStaffInput {
+                tenant_id: TenantId::from_string(tenant_id),
+                role,
+                auth_uid,
+                display_name,
+                image_path,
+                email,
+                idempotency_key: None,
+            };
+            let staff = interactor.create(input).await?;
+            print_staff(&[staff], output)?;
+        }
+        StaffCommand::Update {
+            id,
+            role,
+            display_name,
+            image_path,
+            email,
+        } => {
+            let input = UpdateStaffInput {
+                id: StaffId::from_string(id),
+                role,
+                display_name,
+                image_path,
+                email,
+            };
+            let staff = interactor.update(input).await?;
+            print_staff(&[staff], output)?;
+        }
+        StaffCommand::Delete { id, allow_missing } => {
+            let input = DeleteStaffInput {
+                id: StaffId::from_string(id),
+                allow_missing,
+            };
+            let id = input.id.clone();
+            interactor.delete(input).await?;
+            tracing::info!("Deleted staff {}", id.as_str());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use serde_json::json;
+
+use oxidize_domain::{errors, Tenant, TenantId};
+use oxidize_usecase::{
+    CreateTenantInput, DeleteTenantInput, GetTenantInput, ListTenantInput, ReopenTenantInput,
+    UpdateTenantInput,
+};
+
+use super::output::{print_json, OutputFormat, Table};
+use super::root::TenantCommand;
+use crate::registry::Registry;
+use crate::retention::{archive_tenant, archive_tenants, close_tenant, purge_tenants};
+
+fn print_tenants(tenants: &[Tenant], output: OutputFormat) -> anyhow::Result<()> {
+    match output {
+        OutputFormat::Json => print_json(&tenants),
+        OutputFormat::Table => {
+            let mut table = Table::new(vec!["ID", "NAME", "TAGS", "CREATED_AT", "PURGE_AFTER"]);
+            for t in tenants {
+                let tags: Vec<&str> = t.tags.iter().map(|tag| tag.tag_type.as_str()).collect();
+                table.push(vec![
+                    t.id.as_str().to_string(),
+                    t.name.clone(),
+                    tags.join(","),
+                    t.created_at.to_rfc3339(),
+                    t.purge_after.map(|p| p.to_rfc3339()).unwrap_or_default(),
+                ]);
+            }
+            println!("{}", table.render());
+            Ok(())
+        }
+    }
+}
+
+pub async fn run_tenant(
+    command: TenantCommand,
+    output: OutputFormat,
+    registry: Arc<Registry>,
+) -> anyhow::Result<()> {
+    let interactor = &registry.tenant_interactor;
+
+    match command {
+        TenantCommand::List { limit, offset } => {
+            let result = interactor.list(ListTenantInput { limit, offset }).await?;
+            match output {
+                OutputFormat::Json => print_json(&json!({
+                    "tenants": result.tenants,
+                    "total_count": result.total_count,
+                }))?,
+                OutputFormat::Table => {
+                    print_tenants(&result.tenants, output)?;
+                    println!("({} of {})", result.tenants.len(), result.total_count);
+                }
+            }
+        }
+        TenantCommand::Get { id } => {
+            let input = GetTenantInput {
+                id: TenantId::from_string(id),
+            };
+            let tenant = interactor
+                .get(input)
+                .await?
+                .ok_or_else(errors::tenant_not_found)?;
+            print_tenants(&[tenant], output)?;
+        }
+        TenantCommand::Create { name, tags } => {
+            let input = CreateTenantInput {
+                name,
+                tags,
+                idempotency_key: None,
+            };
+            let tenant = interactor.create(input).await?;
+            print_tenants(&[tenant], output)?;
+        }
+        TenantCommand::Update { id, name } => {
+            let input = UpdateTenantInput {
+                id: TenantId::from_string(id),
+                name,
+            };
+            let tenant = interactor.update(input).await?;
+            print_tenants(&[tenant], output)?;
+        }
+        TenantCommand::Delete {
+            id,
+            allow_missing,
+            force,
+        } => {
+            let input = DeleteTenantInput {
+                id: TenantId::from_string(id),
+                allow_missing,
+                force,
+            };
+            let id = input.id.clone();
+            interactor.delete(input).await?;
+            tracing::info!("Deleted tenant {}", id.as_str());
+        }
+        TenantCommand::Close { id, retention_days } => {
+            let tenant = close_tenant(&registry, TenantId::from_string(id), retention_days).await?;
+            print_tenants(std::slice::from_ref(&tenant), output)?;
+            // The servers would otherwise archive it on their next run.
+            if let Some(archive) = archive_tenant(&registry, &tenant.id).await? {
+                eprintln!("Archived to {}", archive.location);
+            }
+        }
+        TenantCommand::Reopen { id } => {
+            let input = ReopenTenantInput {
+                id: TenantId::from_string(id),
+            };
+            let tenant = interactor.reopen(input).await?;
+            print_tenants(&[tenant], output)?;
+        }
+        TenantCommand::Purge => {
+            let archived = archive_tenants(&registry).await?;
+            tracing::info!("Archived {} closed tenants", archived);
+            let purged = purge_tenants(&registry).await?;
+            tracing::info!("Purged {} closed tenants", purged.len());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::Context;
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders};
+
+use crate::environment::CorsConfig;
+
+/// gRPC-Web clients read the call status from these response headers.
+const GRPC_WEB_EXPOSED_HEADERS: [&str; 3] =
+    ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+fn is_any(values: &[String]) -> bool {
+    values.iter().any(|v| v == "*")
+}
+
+/// Builds the CORS layer shared by the HTTP and gRPC servers, or `None` when
+/// no origin is allowed.
+pub fn cors_layer(config: &CorsConfig) -> anyhow::Result<Option<CorsLayer>> {
+    if config.allowed_origins.is_empty() {
+        return Ok(None);
+    }
+
+    let origins = if is_any(&config.allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o).with_context(|| format!("invalid CORS origin {:?}", o))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    let headers = if is_any(&config.allowed_headers) {
+        AllowHeaders::any()
+    } else {
+        let headers = config
+            .allowed_headers
+            .iter()
+            .map(|h| {
+                HeaderName::try_from(h.as_str())
+                    .with_context(|| format!("invalid CORS header {:?}", h))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowHeaders::list(headers)
+    };
+
+    let methods = if is_any(&config.allowed_methods) {
+        AllowMethods::any()
+    } else {
+        let methods = config
+            .allowed_methods
+            .iter()
+            .map(|m| {
+                Method::from_bytes(m.to_uppercase().as_bytes())
+                    .with_context(|| format!("invalid CORS method {:?}", m))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowMethods::list(methods)
+    };
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_headers(headers)
+            .allow_methods(methods)
+            .expose_headers(ExposeHeaders::list(
+                GRPC_WEB_EXPOSED_HEADERS.map(HeaderName::from_static),
+            )),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str], methods: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.iter().map(|v| v.to_string()).collect(),
+            allowed_headers: vec!["content-type".to_string()],
+            allowed_methods: methods.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_cors_layer() {
+        assert!(cors_layer(&config(&[], &["GET"])).unwrap().is_none());
+        assert!(cors_layer(&config(&["*"], &["*"])).unwrap().is_some());
+        assert!(
+            cors_layer(&config(&["https://admin.example.com"], &["get", "post"]))
+                .unwrap()
+                .is_some()
+        );
+        assert!(cors_layer(&config(&["https://admin.example.com"], &["NOT A METHOD"])).is_err());
+    }
+}
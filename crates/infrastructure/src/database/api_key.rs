@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use oxidize_domain::{ApiKey, ApiKeyId, ApiKeyRepository, Result, TenantId};
+
+use super::scope::Connection;
+use super::{with_retry, Pools, Retry};
+
+#[derive(Debug, sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    tenant_id: String,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(row: ApiKeyRow) -> Self {
+        Self {
+            id: ApiKeyId::from_string(row.id),
+            tenant_id: TenantId::from_string(row.tenant_id),
+            name: row.name,
+            prefix: row.prefix,
+            key_hash: row.key_hash,
+            // Scopes this build does not know grant nothing.
+            scopes: row.scopes.iter().filter_map(|s| s.parse().ok()).collect(),
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+pub struct ApiKeyRepositoryImpl {
+    pools: Pools,
+}
+
+impl ApiKeyRepositoryImpl {
+    pub fn new(pools: Pools) -> Self {
+        Self { pools }
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        // A key revoked a moment ago must not keep working on a lagging replica.
+        let row: Option<ApiKeyRow> = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let row = sqlx::query_as(
+                r#"
+                SELECT * FROM api_keys
+                WHERE key_hash = $1
+                  AND tenant_id IN (SELECT id FROM tenants WHERE closed_at IS NULL)
+                "#,
+            )
+            .bind(key_hash)
+            .fetch_optional(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(row)
+        })
+        .await?;
+
+        Ok(row.map(ApiKey::from))
+    }
+
+    async fn list(&self, tenant_id: &TenantId) -> Result<Vec<ApiKey>> {
+        let rows: Vec<ApiKeyRow> = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(self.pools.reader(false)).await?;
+            let rows = sqlx::query_as(
+                "SELECT * FROM api_keys WHERE tenant_id = $1 ORDER BY created_at DESC",
+            )
+            .bind(tenant_id.as_str())
+            .fetch_all(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(rows)
+        })
+        .await?;
+
+        Ok(rows.into_iter().map(ApiKey::from).collect())
+    }
+
+    async fn create(&self, api_key: &ApiKey) -> Result<()> {
+        let scopes: Vec<&str> = api_key.scopes.iter().map(|s| s.as_str()).collect();
+        with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            sqlx::query(
+                r#"
+                INSERT INTO api_keys (id, tenant_id, name, prefix, key_hash, scopes, expires_at, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(api_key.id.as_str())
+            .bind(api_key.tenant_id.as_str())
+            .bind(&api_key.name)
+            .bind(&api_key.prefix)
+            .bind(&api_key.key_hash)
+            .bind(&scopes)
+            .bind(api_key.expires_at)
+            .bind(api_key.created_at)
+            .execute(&mut *conn)
+            .await?;
+            conn.finish().await
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke(
+        &self,
+        tenant_id: &TenantId,
+        id: &ApiKeyId,
+        now: DateTime<Utc>,
+    ) -> Result<Option<ApiKey>> {
+        let row: Option<ApiKeyRow> = with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let row = sqlx::query_as(
+                r#"
+                UPDATE api_keys
+                SET revoked_at = COALESCE(revoked_at, $3)
+                WHERE id = $1 AND tenant_id = $2
+                RETURNING *
+                "#,
+            )
+            .bind(id.as_str())
+            .bind(tenant_id.as_str())
+            .bind(now)
+            .fetch_optional(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(row)
+        })
+        .await?;
+
+        Ok(row.map(ApiKey::from))
+    }
+
+    async fn touch(&self, id: &ApiKeyId, now: DateTime<Utc>) -> Result<()> {
+        with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            sqlx::query(
+                "UPDATE api_keys SET last_used_at = GREATEST(last_used_at, $2) WHERE id = $1",
+            )
+            .bind(id.as_str())
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+            conn.finish().await
+        })
+        .await?;
+
+        Ok(())
+    }
+}
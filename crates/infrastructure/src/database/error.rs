@@ -0,0 +1,200 @@
+use std::future::Future;
+use std::time::Duration;
+
+use rand::Rng;
+
+use oxidize_domain::{errors, DomainError, Result};
+
+/// Attempts per operation, including the first.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each one after it.
+const BASE_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Postgres SQLSTATE codes, see
+/// <https://www.postgresql.org/docs/current/errcodes-appendix.html>.
+mod sqlstate {
+    pub const UNIQUE_VIOLATION: &str = "23505";
+    pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+    pub const NOT_NULL_VIOLATION: &str = "23502";
+    pub const CHECK_VIOLATION: &str = "23514";
+    pub const STRING_DATA_RIGHT_TRUNCATION: &str = "22001";
+    pub const INVALID_TEXT_REPRESENTATION: &str = "22P02";
+    /// Also raised for rows a row-level security policy refuses to write.
+    pub const INSUFFICIENT_PRIVILEGE: &str = "42501";
+    pub const SERIALIZATION_FAILURE: &str = "40001";
+    pub const DEADLOCK_DETECTED: &str = "40P01";
+    pub const TOO_MANY_CONNECTIONS: &str = "53300";
+    pub const ADMIN_SHUTDOWN: &str = "57P01";
+    pub const CANNOT_CONNECT_NOW: &str = "57P03";
+    /// Class 08, connection exceptions.
+    pub const CONNECTION_EXCEPTION_CLASS: &str = "08";
+}
+
+/// The domain error a unique or foreign-key constraint stands for, by the
+/// names Postgres gives the constraints in `db/migrations`.
+fn constraint_error(constraint: &str) -> Option<DomainError> {
+    match constraint {
+        "tenants_pkey" => Some(errors::tenant_already_exists()),
+        "staffs_pkey" => Some(errors::staff_already_exists()),
+        "staffs_auth_uid_key" => Some(errors::staff_auth_uid_taken()),
+        "staffs_tenant_id_fkey" | "tenant_tags_tenant_id_fkey" | "api_keys_tenant_id_fkey" => {
+            Some(errors::tenant_not_found())
+        }
+        _ => None,
+    }
+}
+
+/// Maps a sqlx error to what the caller did wrong, or to an opaque internal
+/// error whose details only go to the log.
+pub fn map_error(error: sqlx::Error) -> DomainError {
+    if let Some(db) = error.as_database_error() {
+        let code = db.code().unwrap_or_default();
+        let mapped = match code.as_ref() {
+            sqlstate::UNIQUE_VIOLATION => Some(
+                db.constraint()
+                    .and_then(constraint_error)
+                    .unwrap_or_else(errors::already_exists),
+            ),
+            sqlstate::FOREIGN_KEY_VIOLATION => Some(
+                db.constraint()
+                    .and_then(constraint_error)
+                    .unwrap_or_else(errors::invalid_argument),
+            ),
+            sqlstate::NOT_NULL_VIOLATION
+            | sqlstate::CHECK_VIOLATION
+            | sqlstate::STRING_DATA_RIGHT_TRUNCATION
+            | sqlstate::INVALID_TEXT_REPRESENTATION => Some(errors::invalid_argument()),
+            sqlstate::INSUFFICIENT_PRIVILEGE => Some(errors::permission_denied()),
+            _ => None,
+        };
+        if let Some(mapped) = mapped {
+            tracing::debug!(error = %error, code = mapped.code, "Constraint violated");
+            return mapped;
+        }
+    }
+
+    tracing::error!(error = %error, "Database error");
+    errors::internal()
+}
+
+/// What may be retried after a failure depends on whether the operation
+/// could have been applied anyway.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// Safe to repeat whatever happened, e.g. a `SELECT`.
+    Read,
+    /// Only repeated when Postgres is known to have rolled it back.
+    Write,
+}
+
+fn is_transient(error: &sqlx::Error, retry: Retry) -> bool {
+    match error {
+        sqlx::Error::Database(db) => {
+            let code = db.code().unwrap_or_default();
+            match code.as_ref() {
+                sqlstate::SERIALIZATION_FAILURE
+                | sqlstate::DEADLOCK_DETECTED
+                | sqlstate::TOO_MANY_CONNECTIONS
+                | sqlstate::CANNOT_CONNECT_NOW => true,
+                sqlstate::ADMIN_SHUTDOWN => retry == Retry::Read,
+                code => {
+                    retry == Retry::Read && code.starts_with(sqlstate::CONNECTION_EXCEPTION_CLASS)
+                }
+            }
+        }
+        // The connection broke, possibly after a write was committed.
+        sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::Protocol(_) => retry == Retry::Read,
+        // Retrying a saturated pool only makes the queue longer.
+        _ => false,
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_BACKOFF * 2u32.pow(attempt - 1);
+    // Up to 50% jitter, so concurrent retries do not collide again.
+    delay + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+/// Runs `operation`, retrying transient failures up to `MAX_ATTEMPTS` times
+/// with exponential backoff, and maps the final error with [`map_error`].
+/// A write must be a whole transaction, so a retry starts it over.
+pub async fn with_retry<T, F, Fut>(retry: Retry, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt < MAX_ATTEMPTS && is_transient(&error, retry) => {
+                let delay = backoff(attempt);
+                tracing::warn!(
+                    error = %error,
+                    attempt,
+                    "Transient database error, retrying in {:?}",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(error) => return Err(map_error(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn io_error() -> sqlx::Error {
+        sqlx::Error::Io(io::Error::from(io::ErrorKind::ConnectionReset))
+    }
+
+    #[test]
+    fn test_constraint_errors() {
+        assert_eq!(
+            constraint_error("staffs_auth_uid_key").unwrap().code,
+            "E200203"
+        );
+        assert_eq!(
+            constraint_error("staffs_tenant_id_fkey").unwrap().code,
+            "E200101"
+        );
+        assert!(constraint_error("unknown_key").is_none());
+    }
+
+    #[test]
+    fn test_only_reads_retry_broken_connections() {
+        assert!(is_transient(&io_error(), Retry::Read));
+        assert!(!is_transient(&io_error(), Retry::Write));
+        assert!(!is_transient(&sqlx::Error::PoolTimedOut, Retry::Read));
+        assert!(!is_transient(&sqlx::Error::RowNotFound, Retry::Read));
+    }
+
+    #[tokio::test]
+    async fn test_with_retry_gives_up() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<()> = with_retry(Retry::Read, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(io_error())
+        })
+        .await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), MAX_ATTEMPTS);
+        assert_eq!(result.unwrap_err().code, "E100001");
+
+        let attempts = AtomicU32::new(0);
+        let result = with_retry(Retry::Read, || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(io_error()),
+                _ => Ok(42),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+    }
+}
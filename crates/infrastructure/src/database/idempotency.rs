@@ -0,0 +1,289 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use oxidize_domain::{errors, IdempotencyRecord, IdempotencyRepository, Result};
+
+use super::{with_retry, Pools, Retry};
+
+/// Expired keys are deleted every this many claims.
+const PRUNE_EVERY: u64 = 1024;
+
+/// Claims made before giving up on a key that keeps expiring, lapsing or
+/// being released under us.
+const MAX_CLAIMS: u32 = 3;
+
+#[derive(Debug, sqlx::FromRow)]
+struct IdempotencyRow {
+    operation: String,
+    caller: String,
+    key: String,
+    fingerprint: String,
+    token: String,
+    response: Option<String>,
+    locked_until: Option<DateTime<Utc>>,
+    expires_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<IdempotencyRow> for IdempotencyRecord {
+    fn from(row: IdempotencyRow) -> Self {
+        Self {
+            operation: row.operation,
+            caller: row.caller,
+            key: row.key,
+            fingerprint: row.fingerprint,
+            token: row.token,
+            response: row.response,
+            locked_until: row.locked_until,
+            expires_at: row.expires_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Keys in `idempotency_keys`, on the primary so that every replica sees a
+/// claim at once.
+pub struct IdempotencyRepositoryImpl {
+    pools: Pools,
+    claims: AtomicU64,
+}
+
+impl IdempotencyRepositoryImpl {
+    pub fn new(pools: Pools) -> Self {
+        Self {
+            pools,
+            claims: AtomicU64::new(0),
+        }
+    }
+
+    fn prune(&self) {
+        let pool = self.pools.primary().clone();
+        tokio::spawn(async move {
+            if let Err(e) = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= now()")
+                .execute(&pool)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to prune idempotency keys");
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for IdempotencyRepositoryImpl {
+    async fn claim(&self, record: &IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+        if self
+            .claims
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(PRUNE_EVERY)
+        {
+            self.prune();
+        }
+
+        for _ in 0..MAX_CLAIMS {
+            // Inserts the key, or takes over an expired one or one whose
+            // request was abandoned; completed keys have no lease.
+            let claimed: Option<(String,)> = with_retry(Retry::Write, || {
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO idempotency_keys (operation, caller, key, fingerprint, token, locked_until, expires_at, created_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    ON CONFLICT (operation, caller, key) DO UPDATE
+                    SET fingerprint = EXCLUDED.fingerprint,
+                        token = EXCLUDED.token,
+                        response = NULL,
+                        locked_until = EXCLUDED.locked_until,
+                        expires_at = EXCLUDED.expires_at,
+                        created_at = EXCLUDED.created_at
+                    WHERE idempotency_keys.expires_at <= EXCLUDED.created_at
+                       OR idempotency_keys.locked_until <= EXCLUDED.created_at
+                    RETURNING key
+                    "#,
+                )
+                .bind(&record.operation)
+                .bind(&record.caller)
+                .bind(&record.key)
+                .bind(&record.fingerprint)
+                .bind(&record.token)
+                .bind(record.locked_until)
+                .bind(record.expires_at)
+                .bind(record.created_at)
+                .fetch_optional(self.pools.primary())
+            })
+            .await?;
+            if claimed.is_some() {
+                return Ok(None);
+            }
+
+            let existing: Option<IdempotencyRow> = with_retry(Retry::Read, || {
+                sqlx::query_as(
+                    "SELECT * FROM idempotency_keys WHERE operation = $1 AND caller = $2 AND key = $3",
+                )
+                .bind(&record.operation)
+                .bind(&record.caller)
+                .bind(&record.key)
+                .fetch_optional(self.pools.primary())
+            })
+            .await?;
+            if let Some(existing) = existing {
+                return Ok(Some(existing.into()));
+            }
+        }
+
+        Err(errors::idempotency_key_in_use())
+    }
+
+    async fn complete(&self, record: &IdempotencyRecord, response: &str) -> Result<()> {
+        with_retry(Retry::Write, || {
+            sqlx::query(
+                r#"
+                UPDATE idempotency_keys
+                SET response = $5, locked_until = NULL
+                WHERE operation = $1 AND caller = $2 AND key = $3
+                  AND token = $4 AND response IS NULL
+                "#,
+            )
+            .bind(&record.operation)
+            .bind(&record.caller)
+            .bind(&record.key)
+            .bind(&record.token)
+            .bind(response)
+            .execute(self.pools.primary())
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn release(&self, record: &IdempotencyRecord) -> Result<()> {
+        with_retry(Retry::Write, || {
+            sqlx::query(
+                r#"
+                DELETE FROM idempotency_keys
+                WHERE operation = $1 AND caller = $2 AND key = $3
+                  AND token = $4 AND response IS NULL
+                "#,
+            )
+            .bind(&record.operation)
+            .bind(&record.caller)
+            .bind(&record.key)
+            .bind(&record.token)
+            .execute(self.pools.primary())
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use oxidize_domain::TenantId;
+
+    use super::*;
+    use crate::database::MIGRATOR;
+    use crate::environment::{DatabaseConfig, Secret};
+
+    /// The repository on the database at `DATABASE_URL`.
+    async fn repository() -> IdempotencyRepositoryImpl {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let config = DatabaseConfig {
+            url: Some(Secret::new(url)),
+            ..Default::default()
+        };
+        let pools = Pools::connect(&config).await.unwrap();
+        MIGRATOR.run(pools.primary()).await.unwrap();
+        IdempotencyRepositoryImpl::new(pools)
+    }
+
+    fn record(key: &str, now: DateTime<Utc>) -> IdempotencyRecord {
+        IdempotencyRecord::new(
+            "CreateTenant".to_string(),
+            "user:idempotency-test".to_string(),
+            key.to_string(),
+            "f1".to_string(),
+            Duration::hours(24),
+            Duration::seconds(60),
+            now,
+        )
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn test_abandoned_claim_is_taken_over_once_its_lease_lapses() {
+        let repository = repository().await;
+        let key = TenantId::new();
+        let start = Utc::now();
+
+        // The first request claims the key and is never heard from again.
+        let first = record(key.as_str(), start);
+        assert_eq!(repository.claim(&first).await.unwrap(), None);
+
+        let early = repository
+            .claim(&record(key.as_str(), start + Duration::seconds(30)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((early.response, early.locked_until.is_some()), (None, true));
+
+        let retry = record(key.as_str(), start + Duration::seconds(61));
+        assert_eq!(repository.claim(&retry).await.unwrap(), None);
+
+        // Completed keys are replayed for the whole TTL.
+        repository.complete(&retry, "{}").await.unwrap();
+        let replayed = repository
+            .claim(&record(key.as_str(), start + Duration::hours(1)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed.response.as_deref(), Some("{}"));
+        assert_eq!(replayed.locked_until, None);
+
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1")
+            .bind(key.as_str())
+            .execute(repository.pools.primary())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn test_taken_over_claim_can_no_longer_complete_or_release() {
+        let repository = repository().await;
+        let key = TenantId::new();
+        let start = Utc::now();
+
+        let stale = record(key.as_str(), start);
+        assert_eq!(repository.claim(&stale).await.unwrap(), None);
+        let retry = record(key.as_str(), start + Duration::seconds(61));
+        assert_eq!(repository.claim(&retry).await.unwrap(), None);
+
+        // The first request finally finishes, after losing its key.
+        repository.complete(&stale, "stale").await.unwrap();
+        repository.release(&stale).await.unwrap();
+        let held = repository
+            .claim(&record(key.as_str(), start + Duration::seconds(62)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(held.token, retry.token);
+        assert_eq!(held.response, None);
+
+        repository.complete(&retry, "{}").await.unwrap();
+        let replayed = repository
+            .claim(&record(key.as_str(), start + Duration::seconds(63)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed.response.as_deref(), Some("{}"));
+
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1")
+            .bind(key.as_str())
+            .execute(repository.pools.primary())
+            .await
+            .unwrap();
+    }
+}
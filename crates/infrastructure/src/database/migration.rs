@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use sqlx::migrate::{Migrate, Migration, Migrator};
+use sqlx::PgPool;
+
+/// Migrations embedded from `db/migrations` at compile time.
+///
+/// `locking` makes `run` and `undo` hold a Postgres advisory lock for their
+/// whole duration, so concurrent `migrate` invocations (e.g. several deploy
+/// pods starting at once) apply each migration exactly once.
+pub static MIGRATOR: Migrator = {
+    let mut migrator = sqlx::migrate!("../../db/migrations");
+    migrator.locking = true;
+    migrator
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the local file no longer matches the recorded checksum.
+    ChecksumMismatch,
+    /// Recorded as started but never finished.
+    Dirty,
+    /// Applied, but there is no local file for it.
+    Missing,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::ChecksumMismatch => "checksum mismatch",
+            MigrationState::Dirty => "dirty",
+            MigrationState::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub checksum: String,
+    pub state: MigrationState,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn up_migrations() -> impl DoubleEndedIterator<Item = &'static Migration> {
+    MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+}
+
+fn down_migration(version: i64) -> Option<&'static Migration> {
+    MIGRATOR
+        .iter()
+        .find(|m| m.version == version && m.migration_type.is_down_migration())
+}
+
+/// Compares the embedded migrations with the `_sqlx_migrations` table.
+/// Read-only: a database that was never migrated reports everything pending.
+pub async fn migration_status(pool: &PgPool) -> anyhow::Result<Vec<MigrationStatus>> {
+    let mut conn = pool.acquire().await?;
+    let initialized: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await?;
+
+    let (dirty, applied): (Option<i64>, HashMap<i64, Vec<u8>>) = if initialized {
+        let dirty = conn.dirty_version().await?;
+        let applied = conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|m| (m.version, m.checksum.into_owned()))
+            .collect();
+        (dirty, applied)
+    } else {
+        (None, HashMap::new())
+    };
+
+    let mut statuses: Vec<MigrationStatus> = up_migrations()
+        .map(|m| {
+            let state = match applied.get(&m.version) {
+                _ if dirty == Some(m.version) => MigrationState::Dirty,
+                Some(checksum) if *checksum != *m.checksum => MigrationState::ChecksumMismatch,
+                Some(_) => MigrationState::Applied,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                checksum: hex(&m.checksum),
+                state,
+            }
+        })
+        .collect();
+
+    for (version, checksum) in &applied {
+        if !MIGRATOR.version_exists(*version) {
+            statuses.push(MigrationStatus {
+                version: *version,
+                description: String::new(),
+                checksum: hex(checksum),
+                state: MigrationState::Missing,
+            });
+        }
+    }
+    statuses.sort_by_key(|s| s.version);
+
+    Ok(statuses)
+}
+
+/// Up migrations that `MIGRATOR.run` would apply, in order.
+pub async fn pending_migrations(pool: &PgPool) -> anyhow::Result<Vec<&'static Migration>> {
+    let statuses = migration_status(pool).await?;
+    Ok(up_migrations()
+        .filter(|m| {
+            statuses
+                .iter()
+                .any(|s| s.version == m.version && s.state == MigrationState::Pending)
+        })
+        .collect())
+}
+
+/// Down migrations that `MIGRATOR.undo(pool, target)` would run, newest first.
+///
+/// Fails if any applied migration newer than `target` has no down script,
+/// since `undo` would otherwise skip it silently.
+pub async fn revertible_migrations(
+    pool: &PgPool,
+    target: i64,
+) -> anyhow::Result<Vec<&'static Migration>> {
+    let statuses = migration_status(pool).await?;
+    let mut migrations = Vec::new();
+
+    for status in statuses.iter().rev() {
+        if status.version <= target || status.state == MigrationState::Pending {
+            continue;
+        }
+        match down_migration(status.version) {
+            Some(m) => migrations.push(m),
+            None => bail!(
+                "migration {} ({}) is not reversible",
+                status.version,
+                status.description
+            ),
+        }
+    }
+
+    Ok(migrations)
+}
@@ -1,7 +1,17 @@
+mod api_key;
+mod error;
+mod idempotency;
+mod migration;
 mod pool;
+mod scope;
 mod staff;
 mod tenant;
 
+pub use api_key::*;
+pub use error::{map_error, with_retry, Retry};
+pub use idempotency::*;
+pub use migration::*;
 pub use pool::*;
+pub use scope::{scoped, tenant_scope, TENANT_ROLE};
 pub use staff::*;
 pub use tenant::*;
@@ -1,9 +1,98 @@
+use std::time::Duration;
+
 use sqlx::postgres::PgPoolOptions;
-use sqlx::PgPool;
+use sqlx::{Executor, PgPool};
+
+use crate::environment::{DatabaseConfig, Secret};
+
+/// `Duration::ZERO` turns a timeout off.
+fn non_zero(timeout: Duration) -> Option<Duration> {
+    (!timeout.is_zero()).then_some(timeout)
+}
 
-pub async fn create_pool(database_url: &str) -> Result<PgPool, sqlx::Error> {
+async fn connect(config: &DatabaseConfig, url: &Secret) -> Result<PgPool, sqlx::Error> {
+    let application_name = config.application_name.clone();
+    let statement_timeout = config.statement_timeout.as_millis().to_string();
     PgPoolOptions::new()
-        .max_connections(10)
-        .connect(database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(non_zero(config.idle_timeout))
+        .max_lifetime(non_zero(config.max_lifetime))
+        .after_connect(move |conn, _meta| {
+            let application_name = application_name.clone();
+            let statement_timeout = statement_timeout.clone();
+            Box::pin(async move {
+                conn.execute(
+                    sqlx::query(
+                        "SELECT set_config('application_name', $1, false), \
+                         set_config('statement_timeout', $2, false)",
+                    )
+                    .bind(application_name)
+                    .bind(statement_timeout),
+                )
+                .await?;
+                Ok(())
+            })
+        })
+        .connect(url.expose())
         .await
 }
+
+pub async fn create_pool(config: &DatabaseConfig) -> Result<PgPool, sqlx::Error> {
+    let url = config.url.as_ref().ok_or_else(|| {
+        sqlx::Error::Configuration(
+            "database.url is not set (DATABASE_URL, DATABASE_URL_FILE or the config file)".into(),
+        )
+    })?;
+    connect(config, url).await
+}
+
+/// The primary pool plus, when `database.read_url` is set, a pool on the
+/// read replica. Writes, and reads that must see them, go to the primary.
+///
+/// Only the reads an interactor makes itself, e.g. before updating, ask to
+/// be consistent. Nothing ties a client's requests together, so a `Get` or
+/// `List` right after the client's own `Create` or `Update` may go to the
+/// replica and miss it until replication catches up. Clients that need to
+/// read their own writes must use what the write returned.
+#[derive(Clone, Debug)]
+pub struct Pools {
+    primary: PgPool,
+    replica: Option<PgPool>,
+}
+
+impl Pools {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let primary = create_pool(config).await?;
+        let replica = match &config.read_url {
+            Some(url) => Some(connect(config, url).await?),
+            None => None,
+        };
+        Ok(Self { primary, replica })
+    }
+
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    pub fn replica(&self) -> Option<&PgPool> {
+        self.replica.as_ref()
+    }
+
+    /// The pool for a read, which may lag behind the primary unless
+    /// `consistent` is set.
+    pub fn reader(&self, consistent: bool) -> &PgPool {
+        match &self.replica {
+            Some(replica) if !consistent => replica,
+            _ => &self.primary,
+        }
+    }
+
+    pub async fn close(&self) {
+        match &self.replica {
+            Some(replica) => tokio::join!(self.primary.close(), replica.close()).0,
+            None => self.primary.close().await,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Tenant isolation by Postgres row-level security.
+//!
+//! Tenant-owned tables only show the rows whose `tenant_id` is the
+//! `app.tenant_id` setting to any role but their owner, or one with
+//! `BYPASSRLS`. The policies are not `FORCE`d, and the servers connect as
+//! the owner, the role running migrations, so nothing is confined unless it
+//! runs [`scoped`] to a tenant: each repository call is then a transaction
+//! that switches to [`TENANT_ROLE`] with `SET LOCAL`, so a query missing its
+//! tenant filter still cannot read or write another tenant's rows.
+//!
+//! Only requests of API keys are scoped. Users with a JWT, anonymous calls,
+//! the CLI, the background archiver and purger, and migrations act for the
+//! whole platform and bypass the policies; for them, the tenant filters in
+//! the queries are all that keeps tenants apart.
+
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+
+use oxidize_domain::TenantId;
+
+/// The role tenant-scoped transactions run as, created by the migrations.
+/// The role the servers connect as must be able to `SET ROLE` to it.
+pub const TENANT_ROLE: &str = "oxidize_tenant";
+
+tokio::task_local! {
+    static TENANT_SCOPE: TenantId;
+}
+
+/// Runs `future` with the queries of the repositories confined to the rows
+/// of `tenant`, or unconfined for `None`.
+pub async fn scoped<F: Future>(tenant: Option<TenantId>, future: F) -> F::Output {
+    match tenant {
+        Some(tenant) => TENANT_SCOPE.scope(tenant, future).await,
+        None => future.await,
+    }
+}
+
+/// The tenant the current request is confined to, if any.
+pub fn tenant_scope() -> Option<TenantId> {
+    TENANT_SCOPE.try_with(TenantId::clone).ok()
+}
+
+/// A transaction on `pool`, confined to the current tenant scope if any.
+pub(super) async fn begin(pool: &PgPool) -> sqlx::Result<Transaction<'static, Postgres>> {
+    let mut tx = pool.begin().await?;
+    if let Some(tenant) = tenant_scope() {
+        sqlx::query("SELECT set_config('role', $1, true), set_config('app.tenant_id', $2, true)")
+            .bind(TENANT_ROLE)
+            .bind(tenant.as_str())
+            .execute(&mut *tx)
+            .await?;
+    }
+    Ok(tx)
+}
+
+/// The connection for the queries of one repository call: a transaction
+/// confined to the tenant within a tenant scope, else a plain connection,
+/// sparing unscoped calls the `BEGIN` and `COMMIT`.
+pub(super) enum Connection {
+    Pooled(PoolConnection<Postgres>),
+    Scoped(Transaction<'static, Postgres>),
+}
+
+impl Connection {
+    pub async fn acquire(pool: &PgPool) -> sqlx::Result<Self> {
+        if tenant_scope().is_some() {
+            Ok(Self::Scoped(begin(pool).await?))
+        } else {
+            Ok(Self::Pooled(pool.acquire().await?))
+        }
+    }
+
+    /// Commits a scoped transaction; dropped instead, it is rolled back.
+    pub async fn finish(self) -> sqlx::Result<()> {
+        match self {
+            Self::Pooled(_) => Ok(()),
+            Self::Scoped(tx) => tx.commit().await,
+        }
+    }
+}
+
+impl Deref for Connection {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Scoped(tx) => tx,
+        }
+    }
+}
+
+impl DerefMut for Connection {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            Self::Pooled(conn) => conn,
+            Self::Scoped(tx) => tx,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use oxidize_domain::{
+        errors, GetStaffQuery, ListStaffQuery, Staff, StaffRepository, StaffRole, Tenant,
+        TenantRepository,
+    };
+
+    use super::*;
+    use crate::database::{Pools, StaffRepositoryImpl, TenantRepositoryImpl, MIGRATOR};
+    use crate::environment::{DatabaseConfig, Secret};
+
+    /// Two tenants with a staff each, on the database at `DATABASE_URL`.
+    async fn fixture() -> (Pools, [Tenant; 2], [Staff; 2]) {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let config = DatabaseConfig {
+            url: Some(Secret::new(url)),
+            ..Default::default()
+        };
+        let pools = Pools::connect(&config).await.unwrap();
+        MIGRATOR.run(pools.primary()).await.unwrap();
+
+        let tenants = TenantRepositoryImpl::new(pools.clone());
+        let staffs = StaffRepositoryImpl::new(pools.clone());
+        let now = Utc::now();
+        let tenant = |name: &str| Tenant::new(name.to_string(), now);
+        let pair = [tenant("rls-a"), tenant("rls-b")];
+        let staff = |tenant: &Tenant| {
+            Staff::new(
+                tenant.id.clone(),
+                StaffRole::Normal,
+                format!("rls-{}", TenantId::new().as_str()),
+                "RLS".to_string(),
+                String::new(),
+                "rls@example.com".to_string(),
+                now,
+            )
+        };
+        let members = [staff(&pair[0]), staff(&pair[1])];
+        for (tenant, staff) in pair.iter().zip(&members) {
+            tenants.create(tenant).await.unwrap();
+            staffs.create(staff).await.unwrap();
+        }
+        (pools, pair, members)
+    }
+
+    async fn cleanup(pools: &Pools, tenants: &[Tenant]) {
+        let repository = TenantRepositoryImpl::new(pools.clone());
+        for tenant in tenants {
+            repository.delete(&tenant.id, true).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn test_scoped_reads_only_see_own_tenant() {
+        let (pools, tenants, staff) = fixture().await;
+        let repository = StaffRepositoryImpl::new(pools.clone());
+        let own = tenants[0].id.clone();
+
+        scoped(Some(own.clone()), async {
+            // A query that forgot its tenant filter.
+            let mut conn = Connection::acquire(pools.primary()).await.unwrap();
+            let tenant_ids: Vec<String> = sqlx::query_scalar("SELECT tenant_id FROM staffs")
+                .fetch_all(&mut *conn)
+                .await
+                .unwrap();
+            conn.finish().await.unwrap();
+            assert!(!tenant_ids.is_empty());
+            assert!(tenant_ids.iter().all(|id| id == own.as_str()));
+
+            let other = GetStaffQuery {
+                id: Some(staff[1].id.clone()),
+                ..Default::default()
+            };
+            assert!(repository.get(other).await.unwrap().is_none());
+            let all = repository.list(ListStaffQuery::default()).await.unwrap();
+            assert!(all.iter().all(|s| s.tenant_id == own));
+            let others = ListStaffQuery {
+                tenant_id: Some(tenants[1].id.clone()),
+                ..Default::default()
+            };
+            assert_eq!(repository.count(others).await.unwrap(), 0);
+        })
+        .await;
+
+        // Unscoped, as for users and the CLI, every tenant is visible.
+        let other = GetStaffQuery {
+            id: Some(staff[1].id.clone()),
+            ..Default::default()
+        };
+        assert!(repository.get(other).await.unwrap().is_some());
+
+        cleanup(&pools, &tenants).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires DATABASE_URL"]
+    async fn test_scoped_writes_only_touch_own_tenant() {
+        let (pools, tenants, staff) = fixture().await;
+        let repository = StaffRepositoryImpl::new(pools.clone());
+
+        scoped(Some(tenants[0].id.clone()), async {
+            let intruder = Staff::new(
+                tenants[1].id.clone(),
+                StaffRole::Admin,
+                format!("rls-{}", TenantId::new().as_str()),
+                "Intruder".to_string(),
+                String::new(),
+                "intruder@example.com".to_string(),
+                Utc::now(),
+            );
+            let err = repository.create(&intruder).await.unwrap_err();
+            assert_eq!(err.code, errors::permission_denied().code);
+            assert!(!repository.update(&staff[1]).await.unwrap());
+            assert!(!repository.delete(&staff[1].id).await.unwrap());
+            assert!(repository.delete(&staff[0].id).await.unwrap());
+        })
+        .await;
+
+        let other = GetStaffQuery {
+            id: Some(staff[1].id.clone()),
+            ..Default::default()
+        };
+        assert!(repository.get(other).await.unwrap().is_some());
+
+        cleanup(&pools, &tenants).await;
+    }
+}
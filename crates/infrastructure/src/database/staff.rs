@@ -1,11 +1,15 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use sqlx::PgConnection;
 
 use oxidize_domain::{
     GetStaffQuery, ListStaffQuery, Result, Staff, StaffId, StaffRepository, TenantId,
 };
 
+use super::scope::Connection;
+use super::{with_retry, Pools, Retry};
+use crate::metrics::metrics;
+
 #[derive(Debug, sqlx::FromRow)]
 struct StaffRow {
     id: String,
@@ -38,18 +42,53 @@ impl From<StaffRow> for Staff {
 }
 
 pub struct StaffRepositoryImpl {
-    pool: PgPool,
+    pools: Pools,
 }
 
 impl StaffRepositoryImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pools: Pools) -> Self {
+        Self { pools }
+    }
+
+    pub(super) async fn insert(conn: &mut PgConnection, staff: &Staff) -> sqlx::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO staffs (id, tenant_id, role, auth_uid, display_name, image_path, email, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(staff.id.as_str())
+        .bind(staff.tenant_id.as_str())
+        .bind(staff.role.as_str())
+        .bind(&staff.auth_uid)
+        .bind(&staff.display_name)
+        .bind(&staff.image_path)
+        .bind(&staff.email)
+        .bind(staff.created_at)
+        .bind(staff.updated_at)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// The `WHERE` condition of `query`, binding the tenant ID as `$1`.
+fn filter(query: &ListStaffQuery) -> String {
+    let mut conditions = vec!["TRUE"];
+    if query.tenant_id.is_some() {
+        conditions.push("tenant_id = $1");
+    }
+    if query.open_tenants_only {
+        conditions.push("tenant_id IN (SELECT id FROM tenants WHERE closed_at IS NULL)");
     }
+    conditions.join(" AND ")
 }
 
 #[async_trait]
 impl StaffRepository for StaffRepositoryImpl {
     async fn get(&self, query: GetStaffQuery) -> Result<Option<Staff>> {
+        let pool = self.pools.reader(query.consistent);
         let mut sql = String::from("SELECT * FROM staffs WHERE 1=1");
 
         if query.id.is_some() {
@@ -59,41 +98,43 @@ impl StaffRepository for StaffRepositoryImpl {
             sql.push_str(" AND auth_uid = $2");
         }
 
-        let row: Option<StaffRow> = match (&query.id, &query.auth_uid) {
-            (Some(id), Some(auth_uid)) => sqlx::query_as(&sql)
-                .bind(id.as_str())
-                .bind(auth_uid)
-                .fetch_optional(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?,
-            (Some(id), None) => {
-                let sql = "SELECT * FROM staffs WHERE id = $1";
-                sqlx::query_as(sql)
-                    .bind(id.as_str())
-                    .fetch_optional(&self.pool)
-                    .await
-                    .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-            }
-            (None, Some(auth_uid)) => {
-                let sql = "SELECT * FROM staffs WHERE auth_uid = $1";
-                sqlx::query_as(sql)
-                    .bind(auth_uid)
-                    .fetch_optional(&self.pool)
-                    .await
-                    .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-            }
-            (None, None) => None,
-        };
+        let row: Option<StaffRow> = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(pool).await?;
+            let row = match (&query.id, &query.auth_uid) {
+                (Some(id), Some(auth_uid)) => {
+                    sqlx::query_as(&sql)
+                        .bind(id.as_str())
+                        .bind(auth_uid)
+                        .fetch_optional(&mut *conn)
+                        .await?
+                }
+                (Some(id), None) => {
+                    let sql = "SELECT * FROM staffs WHERE id = $1";
+                    sqlx::query_as(sql)
+                        .bind(id.as_str())
+                        .fetch_optional(&mut *conn)
+                        .await?
+                }
+                (None, Some(auth_uid)) => {
+                    let sql = "SELECT * FROM staffs WHERE auth_uid = $1";
+                    sqlx::query_as(sql)
+                        .bind(auth_uid)
+                        .fetch_optional(&mut *conn)
+                        .await?
+                }
+                (None, None) => None,
+            };
+            conn.finish().await?;
+            Ok(row)
+        })
+        .await?;
 
         Ok(row.map(Staff::from))
     }
 
     async fn list(&self, query: ListStaffQuery) -> Result<Vec<Staff>> {
-        let mut sql = String::from("SELECT * FROM staffs WHERE 1=1");
-
-        if query.tenant_id.is_some() {
-            sql.push_str(" AND tenant_id = $1");
-        }
+        let pool = self.pools.reader(query.consistent);
+        let mut sql = format!("SELECT * FROM staffs WHERE {}", filter(&query));
 
         sql.push_str(" ORDER BY created_at DESC");
 
@@ -104,101 +145,90 @@ impl StaffRepository for StaffRepositoryImpl {
             sql.push_str(&format!(" OFFSET {}", offset));
         }
 
-        let rows: Vec<StaffRow> = if let Some(tenant_id) = &query.tenant_id {
-            sqlx::query_as(&sql)
-                .bind(tenant_id.as_str())
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-        } else {
-            let sql = format!(
-                "SELECT * FROM staffs ORDER BY created_at DESC{}{}",
-                query
-                    .limit
-                    .map(|l| format!(" LIMIT {}", l))
-                    .unwrap_or_default(),
-                query
-                    .offset
-                    .map(|o| format!(" OFFSET {}", o))
-                    .unwrap_or_default()
-            );
-            sqlx::query_as(&sql)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-        };
+        let rows: Vec<StaffRow> = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(pool).await?;
+            let mut q = sqlx::query_as(&sql);
+            if let Some(tenant_id) = &query.tenant_id {
+                q = q.bind(tenant_id.as_str());
+            }
+            let rows = q.fetch_all(&mut *conn).await?;
+            conn.finish().await?;
+            Ok(rows)
+        })
+        .await?;
 
         Ok(rows.into_iter().map(Staff::from).collect())
     }
 
     async fn count(&self, query: ListStaffQuery) -> Result<u64> {
-        let count: i64 = if let Some(tenant_id) = &query.tenant_id {
-            sqlx::query_scalar("SELECT COUNT(*) FROM staffs WHERE tenant_id = $1")
-                .bind(tenant_id.as_str())
-                .fetch_one(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-        } else {
-            sqlx::query_scalar("SELECT COUNT(*) FROM staffs")
-                .fetch_one(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-        };
+        let pool = self.pools.reader(query.consistent);
+        let sql = format!("SELECT COUNT(*) FROM staffs WHERE {}", filter(&query));
+
+        let count: i64 = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(pool).await?;
+            let mut q = sqlx::query_scalar(&sql);
+            if let Some(tenant_id) = &query.tenant_id {
+                q = q.bind(tenant_id.as_str());
+            }
+            let count = q.fetch_one(&mut *conn).await?;
+            conn.finish().await?;
+            Ok(count)
+        })
+        .await?;
 
         Ok(count as u64)
     }
 
     async fn create(&self, staff: &Staff) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO staffs (id, tenant_id, role, auth_uid, display_name, image_path, email, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-            "#,
-        )
-        .bind(staff.id.as_str())
-        .bind(staff.tenant_id.as_str())
-        .bind(staff.role.as_str())
-        .bind(&staff.auth_uid)
-        .bind(&staff.display_name)
-        .bind(&staff.image_path)
-        .bind(&staff.email)
-        .bind(staff.created_at)
-        .bind(staff.updated_at)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
-
+        with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            Self::insert(&mut conn, staff).await?;
+            conn.finish().await
+        })
+        .await?;
+
+        metrics().staff_created.add(1, &[]);
         Ok(())
     }
 
-    async fn update(&self, staff: &Staff) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE staffs
-            SET role = $2, display_name = $3, image_path = $4, email = $5, updated_at = $6
-            WHERE id = $1
-            "#,
-        )
-        .bind(staff.id.as_str())
-        .bind(staff.role.as_str())
-        .bind(&staff.display_name)
-        .bind(&staff.image_path)
-        .bind(&staff.email)
-        .bind(staff.updated_at)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
-
-        Ok(())
+    async fn update(&self, staff: &Staff) -> Result<bool> {
+        let result = with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let result = sqlx::query(
+                r#"
+                UPDATE staffs
+                SET role = $2, display_name = $3, image_path = $4, email = $5, updated_at = $6
+                WHERE id = $1
+                "#,
+            )
+            .bind(staff.id.as_str())
+            .bind(staff.role.as_str())
+            .bind(&staff.display_name)
+            .bind(&staff.image_path)
+            .bind(&staff.email)
+            .bind(staff.updated_at)
+            .execute(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(result)
+        })
+        .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 
-    async fn delete(&self, id: &StaffId) -> Result<()> {
-        sqlx::query("DELETE FROM staffs WHERE id = $1")
-            .bind(id.as_str())
-            .execute(&self.pool)
-            .await
-            .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
-
-        Ok(())
+    async fn delete(&self, id: &StaffId) -> Result<bool> {
+        let result = with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let result = sqlx::query("DELETE FROM staffs WHERE id = $1")
+                .bind(id.as_str())
+                .execute(&mut *conn)
+                .await?;
+            conn.finish().await?;
+            Ok(result)
+        })
+        .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 }
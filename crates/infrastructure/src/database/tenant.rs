@@ -1,23 +1,66 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use sqlx::{PgConnection, Postgres, Transaction};
 
-use oxidize_domain::{GetTenantQuery, ListTenantQuery, Result, Tenant, TenantId, TenantRepository};
+use oxidize_domain::{
+    errors, GetTenantQuery, ListTenantQuery, Result, Staff, Tenant, TenantArchiveRef, TenantId,
+    TenantRepository, TenantTag, TenantTagId,
+};
+
+use super::scope::{self, Connection};
+use super::{with_retry, Pools, Retry, StaffRepositoryImpl};
+use crate::metrics::metrics;
 
 #[derive(Debug, sqlx::FromRow)]
 struct TenantRow {
     id: String,
     name: String,
+    closed_at: Option<DateTime<Utc>>,
+    purge_after: Option<DateTime<Utc>>,
+    archive_location: Option<String>,
+    archive_checksum: Option<String>,
+    archived_at: Option<DateTime<Utc>>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
 impl From<TenantRow> for Tenant {
     fn from(row: TenantRow) -> Self {
+        let archive = match (row.archive_location, row.archive_checksum, row.archived_at) {
+            (Some(location), Some(checksum), Some(archived_at)) => Some(TenantArchiveRef {
+                location,
+                checksum,
+                archived_at,
+            }),
+            _ => None,
+        };
         Self {
             id: TenantId::from_string(row.id),
             name: row.name,
             tags: Vec::new(),
+            closed_at: row.closed_at,
+            purge_after: row.purge_after,
+            archive,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TenantTagRow {
+    id: String,
+    tenant_id: String,
+    tag_type: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<TenantTagRow> for TenantTag {
+    fn from(row: TenantTagRow) -> Self {
+        Self {
+            id: TenantTagId::from_string(row.id),
+            tag_type: row.tag_type.parse().unwrap_or_default(),
             created_at: row.created_at,
             updated_at: row.updated_at,
         }
@@ -25,29 +68,107 @@ impl From<TenantRow> for Tenant {
 }
 
 pub struct TenantRepositoryImpl {
-    pool: PgPool,
+    pools: Pools,
 }
 
 impl TenantRepositoryImpl {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pools: Pools) -> Self {
+        Self { pools }
+    }
+
+    /// Reads on `conn`, the same one the tenants came from.
+    async fn load_tags(conn: &mut PgConnection, tenants: &mut [Tenant]) -> sqlx::Result<()> {
+        if tenants.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<String> = tenants.iter().map(|t| t.id.as_str().to_string()).collect();
+        let rows: Vec<TenantTagRow> = sqlx::query_as(
+            "SELECT * FROM tenant_tags WHERE tenant_id = ANY($1) ORDER BY created_at, id",
+        )
+        .bind(&ids)
+        .fetch_all(conn)
+        .await?;
+
+        for row in rows {
+            if let Some(tenant) = tenants.iter_mut().find(|t| t.id.as_str() == row.tenant_id) {
+                tenant.add_tag(TenantTag::from(row));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closed tenants matching `condition`, from the primary since the
+    /// retention jobs act on them; `now` is bound as `$1` if given.
+    async fn list_closed(
+        &self,
+        condition: &str,
+        now: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Tenant>> {
+        let sql = format!("SELECT * FROM tenants WHERE {}", condition);
+        with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let mut query = sqlx::query_as(&sql);
+            if let Some(now) = now {
+                query = query.bind(now);
+            }
+            let rows: Vec<TenantRow> = query.fetch_all(&mut *conn).await?;
+
+            let mut tenants: Vec<Tenant> = rows.into_iter().map(Tenant::from).collect();
+            Self::load_tags(&mut conn, &mut tenants).await?;
+            conn.finish().await?;
+            Ok(tenants)
+        })
+        .await
+    }
+
+    async fn insert_tags(tx: &mut Transaction<'_, Postgres>, tenant: &Tenant) -> sqlx::Result<()> {
+        for tag in &tenant.tags {
+            sqlx::query(
+                r#"
+                INSERT INTO tenant_tags (id, tenant_id, tag_type, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(tag.id.as_str())
+            .bind(tenant.id.as_str())
+            .bind(tag.tag_type.as_str())
+            .bind(tag.created_at)
+            .bind(tag.updated_at)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
     }
 }
 
 #[async_trait]
 impl TenantRepository for TenantRepositoryImpl {
     async fn get(&self, query: GetTenantQuery) -> Result<Option<Tenant>> {
-        let row: Option<TenantRow> = if let Some(id) = &query.id {
-            sqlx::query_as("SELECT * FROM tenants WHERE id = $1")
-                .bind(id.as_str())
-                .fetch_optional(&self.pool)
-                .await
-                .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?
-        } else {
-            None
+        let Some(id) = &query.id else {
+            return Ok(None);
         };
+        let pool = self.pools.reader(query.consistent);
 
-        Ok(row.map(Tenant::from))
+        with_retry(Retry::Read, || async move {
+            let mut conn = Connection::acquire(pool).await?;
+            let row: Option<TenantRow> = sqlx::query_as("SELECT * FROM tenants WHERE id = $1")
+                .bind(id.as_str())
+                .fetch_optional(&mut *conn)
+                .await?;
+
+            let Some(row) = row else {
+                return Ok(None);
+            };
+
+            let mut tenant = Tenant::from(row);
+            Self::load_tags(&mut conn, std::slice::from_mut(&mut tenant)).await?;
+            conn.finish().await?;
+            Ok(Some(tenant))
+        })
+        .await
     }
 
     async fn list(&self, query: ListTenantQuery) -> Result<Vec<Tenant>> {
@@ -62,67 +183,213 @@ impl TenantRepository for TenantRepositoryImpl {
                 .map(|o| format!(" OFFSET {}", o))
                 .unwrap_or_default()
         );
+        let pool = self.pools.reader(false);
 
-        let rows: Vec<TenantRow> = sqlx::query_as(&sql)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
+        with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(pool).await?;
+            let rows: Vec<TenantRow> = sqlx::query_as(&sql).fetch_all(&mut *conn).await?;
 
-        Ok(rows.into_iter().map(Tenant::from).collect())
+            let mut tenants: Vec<Tenant> = rows.into_iter().map(Tenant::from).collect();
+            Self::load_tags(&mut conn, &mut tenants).await?;
+            conn.finish().await?;
+            Ok(tenants)
+        })
+        .await
     }
 
     async fn count(&self, _query: ListTenantQuery) -> Result<u64> {
-        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tenants")
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
+        let count: i64 = with_retry(Retry::Read, || async {
+            let mut conn = Connection::acquire(self.pools.reader(false)).await?;
+            let count = sqlx::query_scalar("SELECT COUNT(*) FROM tenants")
+                .fetch_one(&mut *conn)
+                .await?;
+            conn.finish().await?;
+            Ok(count)
+        })
+        .await?;
 
         Ok(count as u64)
     }
 
     async fn create(&self, tenant: &Tenant) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO tenants (id, name, created_at, updated_at)
-            VALUES ($1, $2, $3, $4)
-            "#,
-        )
-        .bind(tenant.id.as_str())
-        .bind(&tenant.name)
-        .bind(tenant.created_at)
-        .bind(tenant.updated_at)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
+        self.import(tenant, &[]).await
+    }
+
+    async fn import(&self, tenant: &Tenant, staff: &[Staff]) -> Result<()> {
+        with_retry(Retry::Write, || async {
+            let mut tx = scope::begin(self.pools.primary()).await?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO tenants (id, name, closed_at, purge_after, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(tenant.id.as_str())
+            .bind(&tenant.name)
+            .bind(tenant.closed_at)
+            .bind(tenant.purge_after)
+            .bind(tenant.created_at)
+            .bind(tenant.updated_at)
+            .execute(&mut *tx)
+            .await?;
 
+            Self::insert_tags(&mut tx, tenant).await?;
+            for s in staff {
+                StaffRepositoryImpl::insert(&mut tx, s).await?;
+            }
+            tx.commit().await
+        })
+        .await?;
+
+        metrics().tenants_created.add(1, &[]);
+        if !staff.is_empty() {
+            metrics().staff_created.add(staff.len() as u64, &[]);
+        }
         Ok(())
     }
 
-    async fn update(&self, tenant: &Tenant) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE tenants
-            SET name = $2, updated_at = $3
-            WHERE id = $1
-            "#,
+    async fn update(&self, tenant: &Tenant) -> Result<bool> {
+        with_retry(Retry::Write, || async {
+            let mut tx = scope::begin(self.pools.primary()).await?;
+
+            let updated = sqlx::query(
+                r#"
+                UPDATE tenants
+                SET name = $2, closed_at = $3, purge_after = $4, updated_at = $5,
+                    archive_location = $6, archive_checksum = $7, archived_at = $8
+                WHERE id = $1
+                "#,
+            )
+            .bind(tenant.id.as_str())
+            .bind(&tenant.name)
+            .bind(tenant.closed_at)
+            .bind(tenant.purge_after)
+            .bind(tenant.updated_at)
+            .bind(tenant.archive.as_ref().map(|a| &a.location))
+            .bind(tenant.archive.as_ref().map(|a| &a.checksum))
+            .bind(tenant.archive.as_ref().map(|a| a.archived_at))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if updated == 0 {
+                return Ok(false);
+            }
+
+            sqlx::query("DELETE FROM tenant_tags WHERE tenant_id = $1")
+                .bind(tenant.id.as_str())
+                .execute(&mut *tx)
+                .await?;
+
+            Self::insert_tags(&mut tx, tenant).await?;
+            tx.commit().await?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn delete(&self, id: &TenantId, force: bool) -> Result<bool> {
+        // `None` while the tenant has staff.
+        let deleted: Option<bool> = with_retry(Retry::Write, || async {
+            let mut tx = scope::begin(self.pools.primary()).await?;
+
+            // Adding staff locks the tenant row too, so none are added
+            // between the check and the delete.
+            let locked: Option<String> =
+                sqlx::query_scalar("SELECT id FROM tenants WHERE id = $1 FOR UPDATE")
+                    .bind(id.as_str())
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if locked.is_none() {
+                return Ok(Some(false));
+            }
+
+            if !force {
+                let has_staff: bool =
+                    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM staffs WHERE tenant_id = $1)")
+                        .bind(id.as_str())
+                        .fetch_one(&mut *tx)
+                        .await?;
+                if has_staff {
+                    return Ok(None);
+                }
+            }
+
+            sqlx::query("DELETE FROM tenants WHERE id = $1")
+                .bind(id.as_str())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(Some(true))
+        })
+        .await?;
+
+        deleted.ok_or_else(errors::tenant_has_staff)
+    }
+
+    async fn list_unarchived(&self) -> Result<Vec<Tenant>> {
+        self.list_closed(
+            "closed_at IS NOT NULL AND archived_at IS NULL ORDER BY closed_at",
+            None,
         )
-        .bind(tenant.id.as_str())
-        .bind(&tenant.name)
-        .bind(tenant.updated_at)
-        .execute(&self.pool)
         .await
-        .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
+    }
 
-        Ok(())
+    async fn record_archive(&self, tenant: &Tenant) -> Result<bool> {
+        let Some(archive) = &tenant.archive else {
+            return Ok(false);
+        };
+        let result = with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let result = sqlx::query(
+                r#"
+                UPDATE tenants
+                SET archive_location = $2, archive_checksum = $3, archived_at = $4
+                WHERE id = $1 AND closed_at = $5 AND archived_at IS NULL
+                "#,
+            )
+            .bind(tenant.id.as_str())
+            .bind(&archive.location)
+            .bind(&archive.checksum)
+            .bind(archive.archived_at)
+            .bind(tenant.closed_at)
+            .execute(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(result)
+        })
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_purgeable(&self, now: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        self.list_closed(
+            "purge_after <= $1 AND archived_at IS NOT NULL ORDER BY purge_after",
+            Some(now),
+        )
+        .await
     }
 
-    async fn delete(&self, id: &TenantId) -> Result<()> {
-        sqlx::query("DELETE FROM tenants WHERE id = $1")
-            .bind(id.as_str())
-            .execute(&self.pool)
-            .await
-            .map_err(|e| oxidize_domain::DomainError::internal("DB_ERROR", e.to_string()))?;
+    async fn purge(&self, tenant: &Tenant, now: DateTime<Utc>) -> Result<bool> {
+        let Some(archive) = &tenant.archive else {
+            return Ok(false);
+        };
+        let result = with_retry(Retry::Write, || async {
+            let mut conn = Connection::acquire(self.pools.primary()).await?;
+            let result = sqlx::query(
+                "DELETE FROM tenants WHERE id = $1 AND purge_after <= $2 AND archive_checksum = $3",
+            )
+            .bind(tenant.id.as_str())
+            .bind(now)
+            .bind(&archive.checksum)
+            .execute(&mut *conn)
+            .await?;
+            conn.finish().await?;
+            Ok(result)
+        })
+        .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 }
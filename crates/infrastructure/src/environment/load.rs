@@ -0,0 +1,575 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+
+use super::{Environment, LimitConfig, SamplerConfig};
+
+/// Read from the working directory when neither `--config` nor
+/// `OXIDIZE_CONFIG` names a file, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "oxidize.toml";
+
+/// Every problem found while loading the configuration, so they can all be
+/// fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Environment {
+    /// Loads the config file at `path` (or `OXIDIZE_CONFIG`, or
+    /// `oxidize.toml`), applies environment variables and then `flags`, and
+    /// validates the result.
+    pub fn load(path: Option<&Path>, flags: impl FnOnce(&mut Self)) -> Result<Self, ConfigError> {
+        let lookup = |name: &str| std::env::var(name).ok();
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match lookup("OXIDIZE_CONFIG") {
+                Some(path) => (PathBuf::from(path), true),
+                None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+            },
+        };
+        let file = match fs::read_to_string(&path) {
+            Ok(text) => Some((path.display().to_string(), text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => None,
+            Err(e) => {
+                return Err(ConfigError(vec![format!("{}: {}", path.display(), e)]));
+            }
+        };
+        Self::from_layers(file, lookup, flags)
+    }
+
+    /// `file` is the config file's name and contents.
+    fn from_layers(
+        file: Option<(String, String)>,
+        lookup: impl Fn(&str) -> Option<String>,
+        flags: impl FnOnce(&mut Self),
+    ) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let mut env = match file {
+            Some((name, text)) => toml::from_str(&text).unwrap_or_else(|e: toml::de::Error| {
+                let line = e
+                    .span()
+                    .map_or(1, |span| text[..span.start].lines().count());
+                errors.push(format!("{}:{}: {}", name, line.max(1), e.message()));
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+
+        Vars {
+            lookup,
+            errors: &mut errors,
+        }
+        .apply(&mut env);
+        flags(&mut env);
+        env.validate(&mut errors);
+
+        if errors.is_empty() {
+            Ok(env)
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        let database = &self.database;
+        for (key, url) in [("url", &database.url), ("read_url", &database.read_url)] {
+            if let Some(url) = url {
+                if !["postgres://", "postgresql://"]
+                    .iter()
+                    .any(|scheme| url.expose().starts_with(scheme))
+                {
+                    errors.push(format!("database.{} must be a postgres:// URL", key));
+                }
+            }
+        }
+        if database.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+        if database.min_connections > database.max_connections {
+            errors.push(format!(
+                "database.min_connections ({}) exceeds database.max_connections ({})",
+                database.min_connections, database.max_connections
+            ));
+        }
+        if database.acquire_timeout.is_zero() {
+            errors.push("database.acquire_timeout_secs must not be 0".to_string());
+        }
+
+        let server = &self.server;
+        for (key, port) in [
+            ("server.http_port", Some(server.http_port)),
+            ("server.grpc_port", Some(server.grpc_port)),
+            ("server.port", server.port),
+        ] {
+            if port == Some(0) {
+                errors.push(format!("{} must not be 0", key));
+            }
+        }
+        let cors = &server.cors;
+        for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+            if HeaderValue::from_str(origin).is_err() {
+                errors.push(format!(
+                    "server.cors.allowed_origins: invalid origin {:?}",
+                    origin
+                ));
+            }
+        }
+        for header in cors.allowed_headers.iter().filter(|h| *h != "*") {
+            if HeaderName::from_str(header).is_err() {
+                errors.push(format!(
+                    "server.cors.allowed_headers: invalid header {:?}",
+                    header
+                ));
+            }
+        }
+        for method in cors.allowed_methods.iter().filter(|m| *m != "*") {
+            if Method::from_str(method).is_err() {
+                errors.push(format!(
+                    "server.cors.allowed_methods: invalid method {:?}",
+                    method
+                ));
+            }
+        }
+
+        if let Some(secret) = &self.auth.jwt_secret {
+            if secret.expose().len() < 32 {
+                errors.push("auth.jwt_secret must be at least 32 bytes".to_string());
+            }
+        }
+
+        let rate_limit = &self.rate_limit;
+        let mut limits = vec![
+            ("rate_limit.per_ip".to_string(), rate_limit.per_ip),
+            ("rate_limit.default".to_string(), rate_limit.default),
+        ];
+        let mut routes: Vec<_> = rate_limit.routes.iter().collect();
+        for (id, tenant) in &rate_limit.tenants {
+            if let Some(default) = tenant.default {
+                limits.push((format!("rate_limit.tenants.{}.default", id), default));
+            }
+            routes.extend(&tenant.routes);
+        }
+        for route in routes {
+            let path = match route.route.split_once(' ') {
+                Some((method, path)) => {
+                    if Method::from_str(method).is_err() {
+                        errors.push(format!(
+                            "rate_limit.routes: invalid method in {:?}",
+                            route.route
+                        ));
+                    }
+                    path
+                }
+                None => &route.route,
+            };
+            if !path.starts_with('/') {
+                errors.push(format!(
+                    "rate_limit.routes: {:?} must be a path starting with /",
+                    route.route
+                ));
+            }
+            let limit = LimitConfig {
+                requests: route.requests,
+                per: route.per,
+            };
+            limits.push((format!("rate_limit.routes {:?}", route.route), limit));
+        }
+        for (name, limit) in limits {
+            if limit.requests > 0 && limit.per.is_zero() {
+                errors.push(format!("{}: per_secs must not be 0", name));
+            }
+        }
+
+        if self.tenants.archive_dir.as_os_str().is_empty() {
+            errors.push("tenants.archive_dir must not be empty".to_string());
+        }
+        if self.idempotency.ttl.is_zero() || self.idempotency.lease.is_zero() {
+            errors.push("idempotency: ttl_secs and lease_secs must not be 0".to_string());
+        }
+        if self.cache.enabled && (self.cache.capacity == 0 || self.cache.ttl.is_zero()) {
+            errors.push("cache: capacity and ttl_secs must not be 0 when enabled".to_string());
+        }
+
+        let telemetry = &self.telemetry;
+        if let Some(endpoint) = &telemetry.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                errors.push(format!(
+                    "telemetry.otlp_endpoint: {:?} is not an http(s) URL",
+                    endpoint
+                ));
+            }
+        }
+        for (name, value) in &telemetry.otlp_headers {
+            if HeaderName::from_str(name).is_err() || HeaderValue::from_str(value.expose()).is_err()
+            {
+                errors.push(format!("telemetry.otlp_headers: invalid header {:?}", name));
+            }
+        }
+        if telemetry.otlp_timeout.is_zero() {
+            errors.push("telemetry.otlp_timeout_ms must not be 0".to_string());
+        }
+    }
+}
+
+/// The environment variable layer. Every variable can instead be read from
+/// the file named by `<NAME>_FILE`, which is how secrets are usually mounted.
+struct Vars<'a, F> {
+    lookup: F,
+    errors: &'a mut Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Vars<'_, F> {
+    fn apply(&mut self, env: &mut Environment) {
+        let database = &mut env.database;
+        self.set("DATABASE_URL", &mut database.url);
+        self.set("DATABASE_READ_URL", &mut database.read_url);
+        self.parse("DATABASE_MAX_CONNECTIONS", &mut database.max_connections);
+        self.parse("DATABASE_MIN_CONNECTIONS", &mut database.min_connections);
+        self.seconds(
+            "DATABASE_ACQUIRE_TIMEOUT_SECS",
+            &mut database.acquire_timeout,
+        );
+        self.seconds("DATABASE_IDLE_TIMEOUT_SECS", &mut database.idle_timeout);
+        self.seconds("DATABASE_MAX_LIFETIME_SECS", &mut database.max_lifetime);
+        self.millis(
+            "DATABASE_STATEMENT_TIMEOUT_MS",
+            &mut database.statement_timeout,
+        );
+        self.parse("DATABASE_APPLICATION_NAME", &mut database.application_name);
+
+        let server = &mut env.server;
+        self.parse("HTTP_PORT", &mut server.http_port);
+        self.parse("GRPC_PORT", &mut server.grpc_port);
+        self.set("PORT", &mut server.port);
+        self.seconds("SHUTDOWN_TIMEOUT_SECS", &mut server.shutdown_timeout);
+        self.list("CORS_ALLOWED_ORIGINS", &mut server.cors.allowed_origins);
+        self.list("CORS_ALLOWED_HEADERS", &mut server.cors.allowed_headers);
+        self.list("CORS_ALLOWED_METHODS", &mut server.cors.allowed_methods);
+
+        let features = &mut env.features;
+        self.flag("GRPC_REFLECTION", &mut features.grpc_reflection);
+        self.flag("SWAGGER_UI", &mut features.swagger_ui);
+        self.flag("HTTP_GATEWAY", &mut features.http_gateway);
+
+        self.set("JWT_SECRET", &mut env.auth.jwt_secret);
+        self.flag("AUTH_REQUIRED", &mut env.auth.required);
+
+        let rate_limit = &mut env.rate_limit;
+        self.flag("RATE_LIMIT_ENABLED", &mut rate_limit.enabled);
+        self.parse("RATE_LIMIT_STORE", &mut rate_limit.store);
+        self.flag(
+            "RATE_LIMIT_TRUST_FORWARDED_FOR",
+            &mut rate_limit.trust_forwarded_for,
+        );
+        self.parse(
+            "RATE_LIMIT_PER_IP_REQUESTS",
+            &mut rate_limit.per_ip.requests,
+        );
+        self.seconds("RATE_LIMIT_PER_IP_PER_SECS", &mut rate_limit.per_ip.per);
+        self.parse("RATE_LIMIT_REQUESTS", &mut rate_limit.default.requests);
+        self.seconds("RATE_LIMIT_PER_SECS", &mut rate_limit.default.per);
+
+        let tenants = &mut env.tenants;
+        self.parse("TENANT_RETENTION_DAYS", &mut tenants.retention_days);
+        self.parse("TENANT_ARCHIVE_DIR", &mut tenants.archive_dir);
+        self.seconds(
+            "TENANT_ARCHIVE_INTERVAL_SECS",
+            &mut tenants.archive_interval,
+        );
+        self.seconds("TENANT_PURGE_INTERVAL_SECS", &mut tenants.purge_interval);
+
+        self.seconds("IDEMPOTENCY_TTL_SECS", &mut env.idempotency.ttl);
+        self.seconds("IDEMPOTENCY_LEASE_SECS", &mut env.idempotency.lease);
+
+        let cache = &mut env.cache;
+        self.flag("CACHE_ENABLED", &mut cache.enabled);
+        self.parse("CACHE_CAPACITY", &mut cache.capacity);
+        self.seconds("CACHE_TTL_SECS", &mut cache.ttl);
+        self.seconds("CACHE_NEGATIVE_TTL_SECS", &mut cache.negative_ttl);
+
+        let telemetry = &mut env.telemetry;
+        self.parse("LOG_FORMAT", &mut telemetry.log_format);
+        self.set("OTEL_EXPORTER_OTLP_ENDPOINT", &mut telemetry.otlp_endpoint);
+        self.set("OTLP_ENDPOINT", &mut telemetry.otlp_endpoint);
+        self.parse("OTEL_EXPORTER_OTLP_PROTOCOL", &mut telemetry.otlp_protocol);
+        self.key_values("OTEL_EXPORTER_OTLP_HEADERS", &mut telemetry.otlp_headers);
+        self.millis("OTEL_EXPORTER_OTLP_TIMEOUT", &mut telemetry.otlp_timeout);
+        self.flag("OTLP_LOGS", &mut telemetry.otlp_logs);
+        if let Some(name) = self.get("OTEL_TRACES_SAMPLER") {
+            let arg = self.get("OTEL_TRACES_SAMPLER_ARG");
+            match SamplerConfig::parse(&name, arg.as_deref()) {
+                Ok(sampler) => telemetry.sampler = sampler,
+                Err(e) => self.errors.push(format!("OTEL_TRACES_SAMPLER: {}", e)),
+            }
+        }
+        self.set("OTEL_SERVICE_NAME", &mut telemetry.service_name);
+        self.set("SERVICE_VERSION", &mut telemetry.service_version);
+        self.set(
+            "DEPLOYMENT_ENVIRONMENT",
+            &mut telemetry.deployment_environment,
+        );
+        self.key_values(
+            "OTEL_RESOURCE_ATTRIBUTES",
+            &mut telemetry.resource_attributes,
+        );
+    }
+
+    fn get(&mut self, name: &str) -> Option<String> {
+        let file_name = format!("{}_FILE", name);
+        match ((self.lookup)(name), (self.lookup)(&file_name)) {
+            (Some(_), Some(_)) => {
+                self.errors
+                    .push(format!("set only one of {} and {}", name, file_name));
+                None
+            }
+            (Some(value), None) => Some(value),
+            (None, Some(path)) => match fs::read_to_string(&path) {
+                Ok(value) => Some(value.trim_end_matches(['\r', '\n']).to_string()),
+                Err(e) => {
+                    self.errors.push(format!("{}: {}: {}", file_name, path, e));
+                    None
+                }
+            },
+            (None, None) => None,
+        }
+    }
+
+    fn value<T>(&mut self, name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.get(name)?;
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                self.errors.push(format!("{}={:?}: {}", name, value, e));
+                None
+            }
+        }
+    }
+
+    fn parse<T>(&mut self, name: &str, target: &mut T)
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.value(name) {
+            *target = value;
+        }
+    }
+
+    fn set<T>(&mut self, name: &str, target: &mut Option<T>)
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.value(name) {
+            *target = Some(value);
+        }
+    }
+
+    fn seconds(&mut self, name: &str, target: &mut Duration) {
+        if let Some(secs) = self.value(name) {
+            *target = Duration::from_secs(secs);
+        }
+    }
+
+    fn millis(&mut self, name: &str, target: &mut Duration) {
+        if let Some(millis) = self.value(name) {
+            *target = Duration::from_millis(millis);
+        }
+    }
+
+    fn flag(&mut self, name: &str, target: &mut bool) {
+        if let Some(value) = self.get(name) {
+            match value.to_lowercase().as_str() {
+                "true" | "1" => *target = true,
+                "false" | "0" => *target = false,
+                _ => self
+                    .errors
+                    .push(format!("{}={:?}: expected true or false", name, value)),
+            }
+        }
+    }
+
+    /// Comma-separated list; replaces the configured one.
+    fn list(&mut self, name: &str, target: &mut Vec<String>) {
+        if let Some(value) = self.get(name) {
+            *target = value
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect();
+        }
+    }
+
+    /// `key=value` pairs separated by commas, as in `OTEL_RESOURCE_ATTRIBUTES`
+    /// and `OTEL_EXPORTER_OTLP_HEADERS`. Values may be percent-encoded. Keys
+    /// from the config file that are not mentioned are kept.
+    fn key_values<V: From<String>>(&mut self, name: &str, target: &mut BTreeMap<String, V>) {
+        let Some(value) = self.get(name) else {
+            return;
+        };
+        for pair in value.split(',').filter(|p| !p.trim().is_empty()) {
+            match pair.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    target.insert(key.trim().to_string(), percent_decode(value.trim()).into());
+                }
+                _ => self
+                    .errors
+                    .push(format!("{}: expected key=value, got {:?}", name, pair)),
+            }
+        }
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::environment::LogFormat;
+
+    fn load(file: &str, vars: &[(&str, &str)]) -> Result<Environment, ConfigError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Environment::from_layers(
+            Some(("oxidize.toml".to_string(), file.to_string())),
+            |name| vars.get(name).cloned(),
+            |_| {},
+        )
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = r#"
+            [database]
+            url = "postgres://app:secret@db/oxidize"
+            max_connections = 20
+
+            [server]
+            http_port = 9000
+
+            [telemetry]
+            log_format = "json"
+            resource_attributes = { team = "platform", region = "eu" }
+        "#;
+        let env = load(
+            file,
+            &[
+                ("HTTP_PORT", "9100"),
+                ("OTEL_RESOURCE_ATTRIBUTES", "region=us"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(env.database.max_connections, 20);
+        assert_eq!(env.server.http_port, 9100);
+        assert_eq!(env.server.grpc_port, 50051);
+        assert_eq!(env.telemetry.log_format, LogFormat::Json);
+        assert_eq!(env.telemetry.resource_attributes["team"], "platform");
+        assert_eq!(env.telemetry.resource_attributes["region"], "us");
+
+        let env = Environment::from_layers(None, |_| None, |env| env.server.http_port = 9200);
+        assert_eq!(env.unwrap().server.http_port, 9200);
+    }
+
+    #[test]
+    fn test_every_invalid_setting_is_reported() {
+        let file = r#"
+            [database]
+            max_connections = 0
+        "#;
+        let ConfigError(errors) = load(
+            file,
+            &[
+                ("GRPC_PORT", "grpc"),
+                ("LOG_FORMAT", "xml"),
+                ("JWT_SECRET", "short"),
+                ("OTLP_LOGS", "maybe"),
+            ],
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors[0].starts_with("GRPC_PORT=\"grpc\""));
+        assert!(errors.iter().any(|e| e.contains("max_connections")));
+
+        let ConfigError(errors) = load("[server]\nhttp_prot = 1\n", &[]).unwrap_err();
+        assert!(errors[0].starts_with("oxidize.toml:1: unknown field `http_prot`"));
+    }
+
+    #[test]
+    fn test_secret_file() {
+        let path = std::env::temp_dir().join(format!("oxidize-secret-{}", std::process::id()));
+        fs::write(&path, "postgres://app:secret@db/oxidize\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let env = load("", &[("DATABASE_URL_FILE", path)]).unwrap();
+        assert_eq!(
+            env.database.url.unwrap().expose(),
+            "postgres://app:secret@db/oxidize"
+        );
+
+        let ConfigError(errors) = load(
+            "",
+            &[("DATABASE_URL_FILE", path), ("DATABASE_URL", "postgres://")],
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            ["set only one of DATABASE_URL and DATABASE_URL_FILE"]
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%20b%2Cc"), "a b,c");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
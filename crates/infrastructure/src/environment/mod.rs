@@ -1,17 +1,614 @@
-use std::env;
+mod load;
+mod secret;
 
-#[derive(Clone)]
-pub struct Environment {
-    pub database_url: String,
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+pub use load::{ConfigError, DEFAULT_CONFIG_FILE};
+pub use secret::{redact_endpoint, redact_url, Secret};
+
+/// Output format of the stderr log.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `tracing_subscriber`'s default single-line format.
+    #[default]
+    Full,
+    /// Multi-line, for reading locally.
+    Pretty,
+    Compact,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "pretty" => Ok(Self::Pretty),
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format {:?}", s)),
+        }
+    }
+}
+
+/// Transport of the OTLP exporters (`OTEL_EXPORTER_OTLP_PROTOCOL`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtlpProtocol {
+    #[default]
+    #[serde(rename = "grpc")]
+    Grpc,
+    #[serde(rename = "http/protobuf")]
+    HttpProtobuf,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grpc" => Ok(Self::Grpc),
+            "http/protobuf" => Ok(Self::HttpProtobuf),
+            _ => Err(format!("unsupported OTLP protocol {:?}", s)),
+        }
+    }
+}
+
+/// Which root spans are sampled (`OTEL_TRACES_SAMPLER`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceSampler {
+    AlwaysOn,
+    AlwaysOff,
+    /// Fraction of traces, by trace id.
+    Ratio(f64),
+    /// At most this many traces per second.
+    RateLimited(f64),
+}
+
+/// Written as `{ name = "parentbased_traceidratio", arg = 0.1 }` in the
+/// config file, with the names of `OTEL_TRACES_SAMPLER`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SamplerSpec", into = "SamplerSpec")]
+pub struct SamplerConfig {
+    pub sampler: TraceSampler,
+    /// Follows the caller's decision when there is a parent span, and only
+    /// applies `sampler` to new traces.
+    pub parent_based: bool,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            sampler: TraceSampler::AlwaysOn,
+            parent_based: true,
+        }
+    }
+}
+
+impl SamplerConfig {
+    /// Parses `OTEL_TRACES_SAMPLER` and `OTEL_TRACES_SAMPLER_ARG`. Besides the
+    /// standard samplers, `ratelimited` and `parentbased_ratelimited` take a
+    /// number of traces per second.
+    pub fn parse(name: &str, arg: Option<&str>) -> Result<Self, String> {
+        let (parent_based, name) = match name.strip_prefix("parentbased_") {
+            Some(name) => (true, name),
+            None => (false, name),
+        };
+        let arg = |default: f64| -> Result<f64, String> {
+            arg.map_or(Ok(default), |a| {
+                a.parse()
+                    .map_err(|_| format!("invalid sampler argument {:?}", a))
+            })
+        };
+        let sampler = match name {
+            "always_on" => TraceSampler::AlwaysOn,
+            "always_off" => TraceSampler::AlwaysOff,
+            "traceidratio" => match arg(1.0)? {
+                ratio if (0.0..=1.0).contains(&ratio) => TraceSampler::Ratio(ratio),
+                ratio => return Err(format!("sampling ratio {} is not between 0 and 1", ratio)),
+            },
+            "ratelimited" => match arg(100.0)? {
+                rate if rate >= 0.0 => TraceSampler::RateLimited(rate),
+                rate => return Err(format!("sampling rate {} is negative", rate)),
+            },
+            _ => return Err(format!("unknown sampler {:?}", name)),
+        };
+        Ok(Self {
+            sampler,
+            parent_based,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SamplerSpec {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    arg: Option<f64>,
+}
+
+impl TryFrom<SamplerSpec> for SamplerConfig {
+    type Error = String;
+
+    fn try_from(spec: SamplerSpec) -> Result<Self, Self::Error> {
+        Self::parse(&spec.name, spec.arg.map(|a| a.to_string()).as_deref())
+    }
+}
+
+impl From<SamplerConfig> for SamplerSpec {
+    fn from(config: SamplerConfig) -> Self {
+        let (name, arg) = match config.sampler {
+            TraceSampler::AlwaysOn => ("always_on", None),
+            TraceSampler::AlwaysOff => ("always_off", None),
+            TraceSampler::Ratio(ratio) => ("traceidratio", Some(ratio)),
+            TraceSampler::RateLimited(rate) => ("ratelimited", Some(rate)),
+        };
+        let prefix = if config.parent_based {
+            "parentbased_"
+        } else {
+            ""
+        };
+        Self {
+            name: format!("{}{}", prefix, name),
+            arg,
+        }
+    }
+}
+
+/// Durations are whole seconds in the config file.
+mod seconds {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+mod millis {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// Logging, tracing and metrics export. Follows the standard `OTEL_*`
+/// variables where one exists.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub log_format: LogFormat,
+    /// Nothing is exported over OTLP while unset. Printed without its
+    /// userinfo.
+    #[serde(
+        serialize_with = "secret::serialize_endpoint",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub otlp_endpoint: Option<String>,
+    pub otlp_protocol: OtlpProtocol,
+    /// Usually carry credentials, so they are treated as secrets.
+    pub otlp_headers: BTreeMap<String, Secret>,
+    #[serde(rename = "otlp_timeout_ms", with = "millis")]
+    pub otlp_timeout: Duration,
+    /// Also exports logs over OTLP when `otlp_endpoint` is set.
+    pub otlp_logs: bool,
+    pub sampler: SamplerConfig,
+    /// Overrides `service.name`, `oxidize` by default.
+    pub service_name: Option<String>,
+    /// Overrides `service.version`, the crate version by default.
+    pub service_version: Option<String>,
+    pub deployment_environment: Option<String>,
+    /// Extra resource attributes; the settings above take precedence.
+    pub resource_attributes: BTreeMap<String, String>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            log_format: LogFormat::default(),
+            otlp_endpoint: None,
+            otlp_protocol: OtlpProtocol::default(),
+            otlp_headers: BTreeMap::new(),
+            otlp_timeout: Duration::from_secs(10),
+            otlp_logs: false,
+            sampler: SamplerConfig::default(),
+            service_name: None,
+            service_version: None,
+            deployment_environment: None,
+            resource_attributes: BTreeMap::new(),
+        }
+    }
+}
+
+/// Browser access to both servers. CORS is disabled while
+/// `allowed_origins` is empty; `*` allows any origin, header or method.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub allowed_methods: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_headers: [
+                "authorization",
+                "content-type",
+                "grpc-timeout",
+                "idempotency-key",
+                "x-grpc-web",
+                "x-user-agent",
+            ]
+            .map(String::from)
+            .to_vec(),
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// Settings shared by the HTTP and gRPC servers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub http_port: u16,
+    pub grpc_port: u16,
+    /// When set, `serve` multiplexes both protocols on this port instead.
+    pub port: Option<u16>,
+    /// How long servers wait for in-flight requests after SIGINT/SIGTERM.
+    #[serde(rename = "shutdown_timeout_secs", with = "seconds")]
+    pub shutdown_timeout: Duration,
+    pub cors: CorsConfig,
 }
 
-impl Environment {
-    pub fn from_env() -> Self {
+impl Default for ServerConfig {
+    fn default() -> Self {
         Self {
-            database_url: env::var("DATABASE_URL")
-                .unwrap_or_else(|_| "postgres://localhost/oxidize".to_string()),
-            otlp_endpoint: env::var("OTLP_ENDPOINT").ok(),
+            http_port: 8080,
+            grpc_port: 50051,
+            port: None,
+            shutdown_timeout: Duration::from_secs(30),
+            cors: CorsConfig::default(),
         }
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// Required by every command that touches the database.
+    #[serde(
+        serialize_with = "secret::serialize_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub url: Option<Secret>,
+    /// Read replica that `list`, `count` and `get` queries are sent to.
+    /// Reads within a request that must see its own writes stay on the
+    /// primary; a later request may not see them until the replica catches
+    /// up.
+    #[serde(
+        serialize_with = "secret::serialize_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub read_url: Option<Secret>,
+    /// Per pool, so the replica gets as many connections again.
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing.
+    #[serde(rename = "acquire_timeout_secs", with = "seconds")]
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this long;
+    /// 0 keeps them.
+    #[serde(rename = "idle_timeout_secs", with = "seconds")]
+    pub idle_timeout: Duration,
+    /// Connections are recycled after this long; 0 keeps them forever.
+    #[serde(rename = "max_lifetime_secs", with = "seconds")]
+    pub max_lifetime: Duration,
+    /// Postgres cancels statements running longer than this; 0 disables it.
+    #[serde(rename = "statement_timeout_ms", with = "millis")]
+    pub statement_timeout: Duration,
+    /// Shown in `pg_stat_activity`.
+    pub application_name: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            read_url: None,
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(600),
+            max_lifetime: Duration::from_secs(1800),
+            statement_timeout: Duration::from_secs(30),
+            application_name: "oxidize".to_string(),
+        }
+    }
+}
+
+/// How API callers are authenticated; see the `auth` module.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// HMAC key for verifying bearer JWTs; at least 32 bytes. Bearer tokens
+    /// are refused without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwt_secret: Option<Secret>,
+    /// Refuse requests without credentials; turned off, they are served
+    /// anonymously and may do anything. Health checks, metrics and API docs
+    /// stay open either way.
+    pub required: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            required: true,
+        }
+    }
+}
+
+/// Optional parts of the servers; all on by default.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    /// gRPC server reflection; turn off in production.
+    pub grpc_reflection: bool,
+    /// `/swagger-ui` and `/openapi.json`.
+    pub swagger_ui: bool,
+    /// The HTTP/JSON routes transcoded to gRPC from `google.api.http`.
+    pub http_gateway: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            grpc_reflection: true,
+            swagger_ui: true,
+            http_gateway: true,
+        }
+    }
+}
+
+/// Closing tenants: closing refuses access to a tenant at once, the servers
+/// archive it in the background, then purge it once the retention period has
+/// passed and its archive reads back intact.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantConfig {
+    /// How long a closed tenant is kept, unless the close request says.
+    pub retention_days: u32,
+    /// Where closed tenants are archived. Every replica must see the same
+    /// directory, e.g. a shared volume: purging checks the archive first.
+    pub archive_dir: PathBuf,
+    /// How often the servers archive closed tenants; 0 leaves it to
+    /// `tenant purge`.
+    #[serde(rename = "archive_interval_secs", with = "seconds")]
+    pub archive_interval: Duration,
+    /// How often the servers purge closed tenants; 0 leaves it to
+    /// `tenant purge`, e.g. from cron.
+    #[serde(rename = "purge_interval_secs", with = "seconds")]
+    pub purge_interval: Duration,
+}
+
+impl Default for TenantConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            archive_dir: PathBuf::from("archives"),
+            archive_interval: Duration::from_secs(60),
+            purge_interval: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// Retry-safe creates: the result of a create sent with an `Idempotency-Key`
+/// header is replayed to retries with the same key for `ttl`. A create that
+/// never completes holds its key for `lease`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    #[serde(rename = "ttl_secs", with = "seconds")]
+    pub ttl: Duration,
+    #[serde(rename = "lease_secs", with = "seconds")]
+    pub lease: Duration,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(24 * 3600),
+            lease: Duration::from_secs(60),
+        }
+    }
+}
+
+/// In-process caching of tenant and staff reads; see `cache`. Each replica
+/// may serve a change made elsewhere up to `ttl` late.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// Entries kept per cache, least recently used dropped first.
+    pub capacity: usize,
+    #[serde(rename = "ttl_secs", with = "seconds")]
+    pub ttl: Duration,
+    /// How long a miss is cached; 0 does not cache misses.
+    #[serde(rename = "negative_ttl_secs", with = "seconds")]
+    pub negative_ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 10_000,
+            ttl: Duration::from_secs(30),
+            negative_ttl: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Where rate limit buckets are kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStore {
+    /// In this process; each replica limits on its own.
+    #[default]
+    Memory,
+    /// Shared by every replica through the primary database.
+    Postgres,
+}
+
+impl FromStr for RateLimitStore {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "memory" => Ok(Self::Memory),
+            "postgres" => Ok(Self::Postgres),
+            other => Err(format!("unknown rate limit store {:?}", other)),
+        }
+    }
+}
+
+/// A token bucket holding `requests` tokens, refilled over `per`.
+/// `requests = 0` means no limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LimitConfig {
+    pub requests: u32,
+    #[serde(rename = "per_secs", with = "seconds")]
+    pub per: Duration,
+}
+
+/// A limit for the requests matching `route`: a gRPC method path or HTTP
+/// path, optionally prefixed with an HTTP method and ending in `*` to match
+/// a prefix, e.g. `/tenant.TenantService/*` or `POST /v1/tenants`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimitConfig {
+    pub route: String,
+    pub requests: u32,
+    #[serde(rename = "per_secs", with = "seconds")]
+    pub per: Duration,
+}
+
+/// Limits replacing the global ones for a single tenant.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantLimitConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<LimitConfig>,
+    pub routes: Vec<RouteLimitConfig>,
+}
+
+/// Per-client rate limiting on both servers. A client is identified by the
+/// tenant of the API key it authenticated with, so a tenant's keys share one
+/// limit, else by the user it authenticated as, else by its IP address. The
+/// first matching rule applies, in order: the API key's tenant's routes,
+/// `routes`, that tenant's default, then `default`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub store: RateLimitStore,
+    /// Take the client IP from `X-Forwarded-For`; only behind a proxy that
+    /// sets it.
+    pub trust_forwarded_for: bool,
+    /// Every request from an IP address, checked before its credentials
+    /// are, so forged ones cannot be tried without limit.
+    pub per_ip: LimitConfig,
+    pub default: LimitConfig,
+    pub routes: Vec<RouteLimitConfig>,
+    /// Keyed by tenant ID.
+    pub tenants: BTreeMap<String, TenantLimitConfig>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            store: RateLimitStore::Memory,
+            trust_forwarded_for: false,
+            per_ip: LimitConfig {
+                requests: 1000,
+                per: Duration::from_secs(60),
+            },
+            default: LimitConfig {
+                requests: 100,
+                per: Duration::from_secs(60),
+            },
+            routes: Vec::new(),
+            tenants: BTreeMap::new(),
+        }
+    }
+}
+
+/// The effective configuration: defaults, overridden by the TOML config
+/// file, then by environment variables, then by command-line flags.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Environment {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub features: FeatureConfig,
+    pub auth: AuthConfig,
+    pub rate_limit: RateLimitConfig,
+    pub tenants: TenantConfig,
+    pub idempotency: IdempotencyConfig,
+    pub cache: CacheConfig,
+    pub telemetry: TelemetryConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sampler() {
+        assert_eq!(
+            SamplerConfig::parse("parentbased_traceidratio", Some("0.25")),
+            Ok(SamplerConfig {
+                sampler: TraceSampler::Ratio(0.25),
+                parent_based: true,
+            })
+        );
+        assert_eq!(
+            SamplerConfig::parse("ratelimited", None),
+            Ok(SamplerConfig {
+                sampler: TraceSampler::RateLimited(100.0),
+                parent_based: false,
+            })
+        );
+        assert!(SamplerConfig::parse("traceidratio", Some("half")).is_err());
+        assert!(SamplerConfig::parse("traceidratio", Some("1.5")).is_err());
+        assert!(SamplerConfig::parse("sometimes", None).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+use std::cmp::Reverse;
+
+use super::error::Error;
+use super::template::PathTemplate;
+use anyhow::{bail, Context};
+use axum::http::Method;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, MethodDescriptor,
+    SerializeOptions,
+};
+use serde_json::{Map, Value};
+
+// Only `HttpRule` is used; the rest of `google.api` is generated alongside it.
+#[allow(dead_code)]
+mod google_api {
+    tonic::include_proto!("google.api");
+}
+
+use google_api::http_rule::Pattern;
+use google_api::HttpRule;
+
+/// One HTTP route of an RPC, from its `google.api.http` option or one of the
+/// option's `additional_bindings`.
+#[derive(Debug)]
+pub struct Binding {
+    /// `None` for a custom `*` pattern, which matches every method.
+    method: Option<Method>,
+    template: PathTemplate,
+    body: Option<String>,
+    response_body: Option<String>,
+    /// gRPC request path, e.g. `/tenant.TenantService/GetTenant`.
+    pub grpc_path: String,
+    /// Path template as annotated, e.g. `/v1/tenants/{id}`.
+    pub path: String,
+    input: MessageDescriptor,
+    output: MessageDescriptor,
+}
+
+/// Finds a field by proto name or JSON name.
+fn find_field(desc: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    desc.get_field_by_name(name)
+        .or_else(|| desc.get_field_by_json_name(name))
+}
+
+/// Resolves a dotted field path such as `parent.id` to its leaf field.
+fn resolve_field(desc: &MessageDescriptor, path: &str) -> anyhow::Result<FieldDescriptor> {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let field = find_field(desc, name)
+        .with_context(|| format!("{} has no field {:?}", desc.full_name(), name))?;
+    match (rest, field.kind()) {
+        (None, _) => Ok(field),
+        (Some(rest), Kind::Message(nested)) if !field.is_list() && !field.is_map() => {
+            resolve_field(&nested, rest)
+        }
+        (Some(_), _) => bail!("{} is not a singular message field", field.full_name()),
+    }
+}
+
+/// Converts a path or query parameter to the JSON value proto3 JSON expects
+/// for `field`. Everything but booleans may be given as a string.
+fn parameter_value(field: &FieldDescriptor, value: String) -> Result<Value, Error> {
+    match field.kind() {
+        Kind::Bool => value.parse().map(Value::Bool).map_err(|_| {
+            Error::invalid_argument(format!("invalid bool {:?} for {}", value, field.name()))
+        }),
+        _ => Ok(Value::String(value)),
+    }
+}
+
+/// Sets the dotted field `path` in a proto3 JSON object, appending to
+/// repeated fields and overwriting singular ones.
+fn insert_parameter(
+    object: &mut Map<String, Value>,
+    desc: &MessageDescriptor,
+    path: &str,
+    value: String,
+) -> Result<(), Error> {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let field = find_field(desc, name).ok_or_else(|| {
+        Error::invalid_argument(format!("{} has no field {:?}", desc.full_name(), name))
+    })?;
+    // Reuse whichever spelling the body already used for this field.
+    let key = if object.contains_key(field.json_name()) {
+        field.json_name().to_string()
+    } else {
+        field.name().to_string()
+    };
+
+    match (rest, field.kind()) {
+        (Some(rest), Kind::Message(nested)) if !field.is_list() && !field.is_map() => {
+            match object
+                .entry(key)
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(inner) => insert_parameter(inner, &nested, rest, value),
+                _ => Err(Error::invalid_argument(format!(
+                    "{} is not an object",
+                    field.name()
+                ))),
+            }
+        }
+        (Some(_), _) => Err(Error::invalid_argument(format!(
+            "{} is not a singular message field",
+            field.name()
+        ))),
+        (None, _) if field.is_map() => Err(Error::invalid_argument(format!(
+            "map field {} cannot be set from a parameter",
+            field.name()
+        ))),
+        (None, _) => {
+            let value = parameter_value(&field, value)?;
+            if field.is_list() {
+                match object
+                    .entry(key)
+                    .or_insert_with(|| Value::Array(Vec::new()))
+                {
+                    Value::Array(values) => values.push(value),
+                    other => *other = Value::Array(vec![value]),
+                }
+            } else {
+                object.insert(key, value);
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Binding {
+    fn new(method: &MethodDescriptor, rule: &HttpRule) -> anyhow::Result<Self> {
+        let (http_method, path) = match &rule.pattern {
+            Some(Pattern::Get(path)) => (Some(Method::GET), path),
+            Some(Pattern::Put(path)) => (Some(Method::PUT), path),
+            Some(Pattern::Post(path)) => (Some(Method::POST), path),
+            Some(Pattern::Delete(path)) => (Some(Method::DELETE), path),
+            Some(Pattern::Patch(path)) => (Some(Method::PATCH), path),
+            Some(Pattern::Custom(custom)) if custom.kind == "*" => (None, &custom.path),
+            Some(Pattern::Custom(custom)) => (
+                Some(Method::from_bytes(custom.kind.as_bytes())?),
+                &custom.path,
+            ),
+            None => bail!("{} has an HttpRule without a path", method.full_name()),
+        };
+
+        let input = method.input();
+        let output = method.output();
+        let template = PathTemplate::parse(path)?;
+        for field in template.fields() {
+            resolve_field(&input, field)?;
+        }
+
+        let body = match rule.body.as_str() {
+            "" => None,
+            "*" => Some("*".to_string()),
+            field => Some(resolve_field(&input, field)?.name().to_string()),
+        };
+        let response_body = match rule.response_body.as_str() {
+            "" => None,
+            field => Some(resolve_field(&output, field)?.json_name().to_string()),
+        };
+
+        Ok(Self {
+            method: http_method,
+            template,
+            body,
+            response_body,
+            grpc_path: format!("/{}/{}", method.parent_service().full_name(), method.name()),
+            path: path.clone(),
+            input,
+            output,
+        })
+    }
+
+    pub fn allows(&self, method: &Method) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method)
+    }
+
+    pub fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        self.template.matches(path)
+    }
+
+    /// Whether a query parameter is already bound by the path or the body.
+    fn is_bound(&self, key: &str) -> bool {
+        let covers = |field: &str| key == field || key.starts_with(&format!("{}.", field));
+        self.template.fields().any(covers) || self.body.as_deref().is_some_and(covers)
+    }
+
+    /// Builds the request message from the body, the query string and the
+    /// path variables, in increasing order of precedence.
+    pub fn request(
+        &self,
+        path_vars: Vec<(String, String)>,
+        query: Vec<(String, String)>,
+        body: &[u8],
+    ) -> Result<DynamicMessage, Error> {
+        let parse_body = || -> Result<Value, Error> {
+            serde_json::from_slice(body)
+                .map_err(|e| Error::invalid_argument(format!("invalid JSON body: {}", e)))
+        };
+
+        let mut object = match self.body.as_deref() {
+            Some("*") if body.is_empty() => Map::new(),
+            Some("*") => match parse_body()? {
+                Value::Object(object) => object,
+                _ => return Err(Error::invalid_argument("body must be a JSON object")),
+            },
+            Some(field) if !body.is_empty() => Map::from_iter([(field.to_string(), parse_body()?)]),
+            _ => Map::new(),
+        };
+
+        if self.body.as_deref() != Some("*") {
+            for (key, value) in query {
+                if !self.is_bound(&key) {
+                    insert_parameter(&mut object, &self.input, &key, value)?;
+                }
+            }
+        }
+        for (field, value) in path_vars {
+            insert_parameter(&mut object, &self.input, &field, value)?;
+        }
+
+        DynamicMessage::deserialize(self.input.clone(), Value::Object(object))
+            .map_err(|e| Error::invalid_argument(e.to_string()))
+    }
+
+    /// Decodes the response message into proto3 JSON, narrowed to
+    /// `response_body` when the rule sets one.
+    pub fn response(&self, bytes: &[u8]) -> Result<Value, Error> {
+        let message = DynamicMessage::decode(self.output.clone(), bytes)
+            .map_err(|e| Error::internal(format!("invalid response message: {}", e)))?;
+        let options = SerializeOptions::new().skip_default_fields(false);
+        let mut json = message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .map_err(|e| Error::internal(e.to_string()))?;
+
+        Ok(match &self.response_body {
+            Some(field) => json.get_mut(field).map(Value::take).unwrap_or(Value::Null),
+            None => json,
+        })
+    }
+}
+
+/// Collects a binding for every `google.api.http` rule in `pool`, most
+/// specific template first.
+pub fn load_bindings(pool: &DescriptorPool) -> anyhow::Result<Vec<Binding>> {
+    let Some(http) = pool.get_extension_by_name("google.api.http") else {
+        return Ok(Vec::new());
+    };
+
+    let mut bindings = Vec::new();
+    for service in pool.services() {
+        for method in service.methods() {
+            let options = method.options();
+            if !options.has_extension(&http) {
+                continue;
+            }
+            if method.is_client_streaming() || method.is_server_streaming() {
+                bail!(
+                    "{}: streaming RPCs cannot be transcoded",
+                    method.full_name()
+                );
+            }
+
+            let rule: HttpRule = options
+                .get_extension(&http)
+                .as_message()
+                .context("google.api.http is not a message")?
+                .transcode_to()?;
+            for rule in std::iter::once(&rule).chain(&rule.additional_bindings) {
+                let binding = Binding::new(&method, rule)
+                    .with_context(|| format!("invalid HTTP rule for {}", method.full_name()))?;
+                bindings.push(binding);
+            }
+        }
+    }
+    bindings.sort_by_key(|b| Reverse(b.template.literal_count()));
+
+    Ok(bindings)
+}
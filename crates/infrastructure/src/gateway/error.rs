@@ -0,0 +1,82 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::json;
+use tonic::{Code, Status};
+
+use crate::grpc::ERROR_CODE_METADATA;
+use crate::metrics::ErrorCode;
+
+/// A failed transcoded call, rendered in grpc-gateway's error shape.
+#[derive(Debug)]
+pub struct Error {
+    code: Code,
+    message: String,
+    /// `DomainError` code reported by the service, if any.
+    error_code: Option<String>,
+}
+
+impl Error {
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            error_code: None,
+        }
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(Code::InvalidArgument, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(Code::Internal, message)
+    }
+}
+
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        let mut err = Self::new(status.code(), status.message());
+        err.error_code = status
+            .metadata()
+            .get(ERROR_CODE_METADATA)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        err
+    }
+}
+
+/// HTTP status for a gRPC code, following grpc-gateway.
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "code": self.code as i32,
+            "message": self.message,
+            "details": [],
+        });
+        let mut response = (http_status(self.code), Json(body)).into_response();
+        if let Some(code) = self.error_code {
+            response.extensions_mut().insert(ErrorCode(code));
+        }
+        response
+    }
+}
@@ -0,0 +1,360 @@
+//! HTTP/JSON transcoding for the gRPC services, driven by the
+//! `google.api.http` annotations in `schema/proto`.
+//!
+//! Requests are matched against the annotated path templates, converted to
+//! protobuf with proto3 JSON mapping and dispatched in-process to the same
+//! tonic services the gRPC server runs, so an annotated RPC is served over
+//! HTTP without any handler code.
+
+mod binding;
+mod error;
+mod template;
+
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::extract::{Query, Request, State};
+use axum::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use http_body_util::BodyExt;
+use prost::Message;
+use prost_reflect::DescriptorPool;
+use tonic::service::Routes;
+use tonic::{Code, Status};
+use tower::Service;
+
+use binding::{load_bindings, Binding};
+pub use error::Error;
+
+use crate::auth::Actor;
+use crate::metrics::RouteLabel;
+use crate::otel;
+
+/// A matched binding and its path variables.
+type Match<'a> = (&'a Binding, Vec<(String, String)>);
+
+/// Same limit tonic applies to decoded gRPC messages.
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+
+/// Request headers that describe the HTTP/1.1 exchange rather than the call,
+/// and so are not forwarded as gRPC metadata.
+const HOP_HEADERS: [HeaderName; 7] = [
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::CONTENT_TYPE,
+    header::HOST,
+    header::TE,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+struct Gateway {
+    bindings: Vec<Binding>,
+    grpc: Router,
+}
+
+/// Returns the status carried by `headers`, unless it is `OK` or absent.
+fn failed_status(headers: &HeaderMap) -> Option<Status> {
+    Status::from_header_map(headers).filter(|s| s.code() != Code::Ok)
+}
+
+impl Gateway {
+    fn find(&self, request: &Request) -> Result<Match<'_>, Error> {
+        let path = request.uri().path();
+        let mut path_matched = false;
+        for binding in &self.bindings {
+            if let Some(vars) = binding.matches(path) {
+                if binding.allows(request.method()) {
+                    return Ok((binding, vars));
+                }
+                path_matched = true;
+            }
+        }
+
+        Err(if path_matched {
+            Error::new(
+                Code::Unimplemented,
+                format!("method {} not allowed", request.method()),
+            )
+        } else {
+            Error::new(Code::NotFound, format!("no route for {}", path))
+        })
+    }
+
+    /// Sends one unary gRPC call through the in-process tonic router and
+    /// returns the encoded response message. The router sits behind the
+    /// HTTP middleware, so the caller's `actor` is passed on as is.
+    async fn call(
+        &self,
+        binding: &Binding,
+        headers: &HeaderMap,
+        actor: Option<Actor>,
+        message: Vec<u8>,
+    ) -> Result<Bytes, Error> {
+        let mut frame = Vec::with_capacity(message.len() + 5);
+        frame.push(0);
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&message);
+
+        let mut request = Request::post(&binding.grpc_path)
+            .body(Body::from(frame))
+            .map_err(|e| Error::internal(e.to_string()))?;
+        for (name, value) in headers {
+            if !HOP_HEADERS.contains(name) {
+                request.headers_mut().append(name, value.clone());
+            }
+        }
+        request.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/grpc"),
+        );
+        request
+            .headers_mut()
+            .insert(header::TE, HeaderValue::from_static("trailers"));
+        if let Some(actor) = actor {
+            request.extensions_mut().insert(actor);
+        }
+        otel::inject(request.headers_mut());
+
+        let response = match self.grpc.clone().call(request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+        let (parts, body) = response.into_parts();
+        // Trailers-only responses carry the status in the headers.
+        if let Some(status) = failed_status(&parts.headers) {
+            return Err(status.into());
+        }
+
+        let collected = body
+            .collect()
+            .await
+            .map_err(|e| Error::internal(e.to_string()))?;
+        if let Some(status) = collected.trailers().and_then(failed_status) {
+            return Err(status.into());
+        }
+
+        let data = collected.to_bytes();
+        match data.get(..5) {
+            Some([0, len @ ..]) => {
+                let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+                data.get(5..5 + len)
+                    .map(|_| data.slice(5..5 + len))
+                    .ok_or_else(|| Error::internal("truncated gRPC response"))
+            }
+            Some(_) => Err(Error::internal("compressed gRPC response")),
+            None => Err(Error::internal("missing gRPC response message")),
+        }
+    }
+
+    async fn transcode(
+        &self,
+        binding: &Binding,
+        path_vars: Vec<(String, String)>,
+        query: Vec<(String, String)>,
+        request: Request,
+    ) -> Result<Response, Error> {
+        let (parts, body) = request.into_parts();
+        let body = axum::body::to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .map_err(|e| Error::invalid_argument(e.to_string()))?;
+
+        let message = binding.request(path_vars, query, &body)?;
+        let actor = parts.extensions.get::<Actor>().cloned();
+        let response = self
+            .call(binding, &parts.headers, actor, message.encode_to_vec())
+            .await?;
+
+        Ok(Json(binding.response(&response)?).into_response())
+    }
+}
+
+async fn handle(
+    State(gateway): State<Arc<Gateway>>,
+    Query(query): Query<Vec<(String, String)>>,
+    request: Request,
+) -> Response {
+    let (binding, path_vars) = match gateway.find(&request) {
+        Ok(found) => found,
+        Err(err) => return err.into_response(),
+    };
+    let mut response = gateway
+        .transcode(binding, path_vars, query, request)
+        .await
+        .unwrap_or_else(IntoResponse::into_response);
+    response
+        .extensions_mut()
+        .insert(RouteLabel(binding.path.clone()));
+    response
+}
+
+/// Builds the descriptor pool for the services in `file_descriptor_sets`.
+fn descriptor_pool(file_descriptor_sets: &[&[u8]]) -> anyhow::Result<DescriptorPool> {
+    let mut pool = DescriptorPool::new();
+    for set in file_descriptor_sets {
+        pool.decode_file_descriptor_set(*set)?;
+    }
+    Ok(pool)
+}
+
+/// A router serving every annotated RPC of `grpc`, meant to be merged into
+/// the HTTP router. Requests matching no rule get a JSON 404.
+pub fn router(file_descriptor_sets: &[&[u8]], grpc: Routes) -> anyhow::Result<Router> {
+    let pool = descriptor_pool(file_descriptor_sets)?;
+    let gateway = Gateway {
+        bindings: load_bindings(&pool)?,
+        grpc: grpc.into_axum_router(),
+    };
+
+    Ok(Router::new().fallback(handle).with_state(Arc::new(gateway)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use prost_reflect::Value;
+
+    use crate::grpc::FILE_DESCRIPTOR_SETS;
+
+    fn gateway() -> Gateway {
+        let pool = descriptor_pool(&FILE_DESCRIPTOR_SETS).unwrap();
+        Gateway {
+            bindings: load_bindings(&pool).unwrap(),
+            grpc: Router::new(),
+        }
+    }
+
+    fn request(method: &str, uri: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_every_rpc_is_bound() {
+        let gateway = gateway();
+        let bound = |grpc_path: &str, path: &str| {
+            gateway
+                .bindings
+                .iter()
+                .any(|b| b.grpc_path == grpc_path && b.path == path)
+        };
+
+        for (grpc_path, path) in [
+            ("/tenant.TenantService/GetTenant", "/v1/tenants/{id}"),
+            ("/tenant.TenantService/ListTenants", "/v1/tenants"),
+            ("/tenant.TenantService/CreateTenant", "/v1/tenants"),
+            ("/tenant.TenantService/UpdateTenant", "/v1/tenants/{id}"),
+            ("/tenant.TenantService/DeleteTenant", "/v1/tenants/{id}"),
+            (
+                "/tenant.TenantService/CloseTenant",
+                "/v1/tenants/{id}:close",
+            ),
+            ("/staff.StaffService/GetStaff", "/v1/staffs/{id}"),
+            (
+                "/staff.StaffService/GetStaff",
+                "/v1/staffs/auth_uid/{auth_uid}",
+            ),
+            ("/staff.StaffService/ListStaffs", "/v1/staffs"),
+            (
+                "/staff.StaffService/ListStaffs",
+                "/v1/tenants/{tenant_id}/staffs",
+            ),
+            ("/staff.StaffService/CreateStaff", "/v1/staffs"),
+            ("/staff.StaffService/UpdateStaff", "/v1/staffs/{id}"),
+            ("/staff.StaffService/DeleteStaff", "/v1/staffs/{id}"),
+            (
+                "/api_key.ApiKeyService/CreateApiKey",
+                "/v1/tenants/{tenant_id}/api_keys",
+            ),
+            (
+                "/api_key.ApiKeyService/ListApiKeys",
+                "/v1/tenants/{tenant_id}/api_keys",
+            ),
+            (
+                "/api_key.ApiKeyService/RevokeApiKey",
+                "/v1/tenants/{tenant_id}/api_keys/{id}:revoke",
+            ),
+        ] {
+            assert!(
+                bound(grpc_path, path),
+                "{} is not bound to {}",
+                grpc_path,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_binding() {
+        let gateway = gateway();
+
+        let (binding, vars) = gateway
+            .find(&request("GET", "/v1/tenants/t1/staffs"))
+            .unwrap();
+        assert_eq!(binding.grpc_path, "/staff.StaffService/ListStaffs");
+        assert_eq!(vars, vec![("tenant_id".to_string(), "t1".to_string())]);
+
+        let (binding, _) = gateway.find(&request("PATCH", "/v1/tenants/t1")).unwrap();
+        assert_eq!(binding.grpc_path, "/tenant.TenantService/UpdateTenant");
+
+        let (binding, vars) = gateway
+            .find(&request("POST", "/v1/tenants/t1:close"))
+            .unwrap();
+        assert_eq!(binding.grpc_path, "/tenant.TenantService/CloseTenant");
+        assert_eq!(vars, vec![("id".to_string(), "t1".to_string())]);
+
+        let (binding, vars) = gateway
+            .find(&request("POST", "/v1/tenants/t1/api_keys/k1:revoke"))
+            .unwrap();
+        assert_eq!(binding.grpc_path, "/api_key.ApiKeyService/RevokeApiKey");
+        assert_eq!(vars.len(), 2);
+
+        let err = gateway.find(&request("PUT", "/v1/tenants/t1")).unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_IMPLEMENTED);
+        let err = gateway.find(&request("GET", "/v1/unknown")).unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_request_binds_path_query_and_body() {
+        let gateway = gateway();
+
+        let (binding, vars) = gateway.find(&request("GET", "/v1/staffs")).unwrap();
+        let query = vec![
+            ("tenantId".to_string(), "t1".to_string()),
+            ("limit".to_string(), "5".to_string()),
+        ];
+        let message = binding.request(vars, query, b"").unwrap();
+        assert_eq!(
+            message.get_field_by_name("tenant_id").unwrap().as_str(),
+            Some("t1")
+        );
+        assert_eq!(
+            message.get_field_by_name("limit").unwrap().as_u64(),
+            Some(5)
+        );
+
+        let (binding, vars) = gateway.find(&request("PATCH", "/v1/tenants/t1")).unwrap();
+        let message = binding
+            .request(vars, Vec::new(), br#"{"id": "ignored", "name": "Acme"}"#)
+            .unwrap();
+        assert_eq!(
+            message.get_field_by_name("id").unwrap().as_ref(),
+            &Value::String("t1".to_string())
+        );
+        assert_eq!(
+            message.get_field_by_name("name").unwrap().as_str(),
+            Some("Acme")
+        );
+
+        let err = binding
+            .request(Vec::new(), Vec::new(), br#"{"unknown": 1}"#)
+            .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+}
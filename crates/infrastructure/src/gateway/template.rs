@@ -0,0 +1,252 @@
+use anyhow::{bail, ensure};
+
+/// One segment of a `google.api.http` path template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// `*`: exactly one segment.
+    Single,
+    /// `**`: zero or more trailing segments.
+    Multi,
+}
+
+/// A `{field=...}` capture, covering `parts[start..end]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Variable {
+    field: String,
+    start: usize,
+    end: usize,
+}
+
+/// A parsed path template such as `/v1/tenants/{tenant_id}/staffs` or
+/// `/v1/{name=files/**}:download`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    parts: Vec<Part>,
+    variables: Vec<Variable>,
+    verb: Option<String>,
+}
+
+/// Splits `s` on `sep`, ignoring separators inside `{...}`.
+fn split_outside_braces(s: &str, sep: char) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut pieces = Vec::new();
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                pieces.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    pieces.push(&s[start..]);
+    pieces
+}
+
+fn parse_part(segment: &str) -> anyhow::Result<Part> {
+    Ok(match segment {
+        "*" => Part::Single,
+        "**" => Part::Multi,
+        "" => bail!("empty path segment"),
+        s if s.contains(['{', '}', '*']) => bail!("invalid path segment {:?}", s),
+        s => Part::Literal(s.to_string()),
+    })
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        let Some(path) = template.strip_prefix('/') else {
+            bail!("path template {:?} must start with '/'", template);
+        };
+
+        let (path, verb) = match split_outside_braces(path, ':').as_slice() {
+            [path] => (*path, None),
+            [path, verb] if !verb.is_empty() && !verb.contains('/') => {
+                (*path, Some(verb.to_string()))
+            }
+            _ => bail!("invalid verb in path template {:?}", template),
+        };
+
+        let mut parts = Vec::new();
+        let mut variables = Vec::new();
+        for segment in split_outside_braces(path, '/') {
+            match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(variable) => {
+                    let (field, pattern) = variable.split_once('=').unwrap_or((variable, "*"));
+                    ensure!(
+                        !field.is_empty() && field.split('.').all(|f| !f.is_empty()),
+                        "invalid variable {:?} in path template {:?}",
+                        variable,
+                        template
+                    );
+                    let start = parts.len();
+                    for s in pattern.split('/') {
+                        parts.push(parse_part(s)?);
+                    }
+                    variables.push(Variable {
+                        field: field.to_string(),
+                        start,
+                        end: parts.len(),
+                    });
+                }
+                None => parts.push(parse_part(segment)?),
+            }
+        }
+
+        ensure!(
+            !parts[..parts.len() - 1].contains(&Part::Multi),
+            "'**' must be the last segment of path template {:?}",
+            template
+        );
+
+        Ok(Self {
+            parts,
+            variables,
+            verb,
+        })
+    }
+
+    /// Field paths bound by this template, e.g. `["tenant_id"]`.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.variables.iter().map(|v| v.field.as_str())
+    }
+
+    /// Number of literal segments; more literal templates take precedence.
+    pub fn literal_count(&self) -> usize {
+        self.parts
+            .iter()
+            .filter(|p| matches!(p, Part::Literal(_)))
+            .count()
+    }
+
+    /// Matches a request path, returning the percent-decoded value of each
+    /// variable. Multi-segment captures keep `%2F` encoded so the original
+    /// segments can still be told apart.
+    pub fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let path = path.strip_prefix('/')?;
+        let path = match &self.verb {
+            Some(verb) => path.strip_suffix(verb.as_str())?.strip_suffix(':')?,
+            None => path,
+        };
+        let segments: Vec<&str> = path.split('/').collect();
+
+        let fixed = match self.parts.last() {
+            Some(Part::Multi) => {
+                if segments.len() < self.parts.len() - 1 {
+                    return None;
+                }
+                self.parts.len() - 1
+            }
+            _ => {
+                if segments.len() != self.parts.len() {
+                    return None;
+                }
+                self.parts.len()
+            }
+        };
+
+        for (part, segment) in self.parts[..fixed].iter().zip(&segments) {
+            match part {
+                Part::Literal(literal) if literal == segment => {}
+                Part::Single if !segment.is_empty() => {}
+                _ => return None,
+            }
+        }
+
+        self.variables
+            .iter()
+            .map(|v| {
+                let end = if v.end == self.parts.len() {
+                    segments.len()
+                } else {
+                    v.end
+                };
+                let single = end - v.start == 1 && self.parts[v.start] == Part::Single;
+                let value = percent_decode(&segments[v.start..end].join("/"), !single)?;
+                Some((v.field.clone(), value))
+            })
+            .collect()
+    }
+}
+
+fn percent_decode(s: &str, keep_slash: bool) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            let byte = u8::from_str_radix(hex, 16).ok()?;
+            if keep_slash && byte == b'/' {
+                decoded.extend_from_slice(&bytes[i..i + 3]);
+            } else {
+                decoded.push(byte);
+            }
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_matches_literals_and_variables() {
+        let template = PathTemplate::parse("/v1/tenants/{tenant_id}/staffs").unwrap();
+        assert_eq!(template.literal_count(), 3);
+        assert_eq!(
+            template.matches("/v1/tenants/abc/staffs"),
+            vars(&[("tenant_id", "abc")])
+        );
+        assert_eq!(template.matches("/v1/tenants/abc"), None);
+        assert_eq!(template.matches("/v1/tenants//staffs"), None);
+        assert_eq!(
+            template.matches("/v1/tenants/a%20b/staffs"),
+            vars(&[("tenant_id", "a b")])
+        );
+    }
+
+    #[test]
+    fn test_matches_nested_patterns_and_verb() {
+        let template = PathTemplate::parse("/v1/{name=shelves/*/books/*}:archive").unwrap();
+        assert_eq!(
+            template.matches("/v1/shelves/1/books/2:archive"),
+            vars(&[("name", "shelves/1/books/2")])
+        );
+        assert_eq!(template.matches("/v1/shelves/1/books/2"), None);
+
+        let template = PathTemplate::parse("/v1/{path=files/**}").unwrap();
+        assert_eq!(
+            template.matches("/v1/files/a/b%2Fc"),
+            vars(&[("path", "files/a/b%2Fc")])
+        );
+        assert_eq!(template.matches("/v1/files"), vars(&[("path", "files")]));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(PathTemplate::parse("v1/tenants").is_err());
+        assert!(PathTemplate::parse("/v1/**/tenants").is_err());
+        assert!(PathTemplate::parse("/v1/{}").is_err());
+        assert!(PathTemplate::parse("/v1//tenants").is_err());
+        assert!(PathTemplate::parse("/v1/tenants:").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tonic::{Request, Response, Status};
+
+use oxidize_domain::error::errors;
+use oxidize_domain::{ApiKeyId, ApiKeyScope, TenantId};
+use oxidize_usecase::{CreateApiKeyInput, ListApiKeyInput, RevokeApiKeyInput};
+
+use super::auth::actor;
+use super::error::to_status;
+use crate::auth::require_user;
+use crate::registry::Registry;
+
+pub mod proto {
+    tonic::include_proto!("api_key");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        tonic::include_file_descriptor_set!("api_key_descriptor");
+}
+
+use proto::api_key_service_server::ApiKeyService;
+use proto::{
+    ApiKey, CreateApiKeyRequest, CreateApiKeyResponse, ListApiKeysRequest, ListApiKeysResponse,
+    RevokeApiKeyRequest, RevokeApiKeyResponse,
+};
+
+pub struct ApiKeyServiceImpl {
+    registry: Arc<Registry>,
+}
+
+impl ApiKeyServiceImpl {
+    pub fn new(registry: Arc<Registry>) -> Self {
+        Self { registry }
+    }
+}
+
+fn to_proto_api_key(k: oxidize_domain::ApiKey) -> ApiKey {
+    ApiKey {
+        id: k.id.as_str().to_string(),
+        tenant_id: k.tenant_id.as_str().to_string(),
+        name: k.name,
+        prefix: k.prefix,
+        scopes: k.scopes.iter().map(ToString::to_string).collect(),
+        expires_at: k.expires_at.map(|e| e.to_rfc3339()),
+        last_used_at: k.last_used_at.map(|l| l.to_rfc3339()),
+        revoked_at: k.revoked_at.map(|r| r.to_rfc3339()),
+        created_at: k.created_at.to_rfc3339(),
+    }
+}
+
+fn parse_scopes(scopes: &[String]) -> oxidize_domain::Result<Vec<ApiKeyScope>> {
+    scopes
+        .iter()
+        .map(|s| s.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|()| errors::invalid_argument())
+}
+
+#[tonic::async_trait]
+impl ApiKeyService for ApiKeyServiceImpl {
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "api_key", tenant_id = %request.get_ref().tenant_id)
+    )]
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        require_user(actor(&request).as_ref()).map_err(to_status)?;
+        let req = request.into_inner();
+        let input = CreateApiKeyInput {
+            tenant_id: TenantId::from_string(req.tenant_id),
+            name: req.name,
+            scopes: parse_scopes(&req.scopes).map_err(to_status)?,
+            expires_at: req
+                .expires_in_days
+                .map(|days| Utc::now() + Duration::days(days.into())),
+        };
+
+        let output = self
+            .registry
+            .api_key_interactor
+            .create(input)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(CreateApiKeyResponse {
+            api_key: Some(to_proto_api_key(output.api_key)),
+            key: output.key,
+        }))
+    }
+
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "api_key", tenant_id = %request.get_ref().tenant_id)
+    )]
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, Status> {
+        require_user(actor(&request).as_ref()).map_err(to_status)?;
+        let req = request.into_inner();
+        let input = ListApiKeyInput {
+            tenant_id: TenantId::from_string(req.tenant_id),
+        };
+
+        let api_keys = self
+            .registry
+            .api_key_interactor
+            .list(input)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(ListApiKeysResponse {
+            api_keys: api_keys.into_iter().map(to_proto_api_key).collect(),
+        }))
+    }
+
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "api_key", tenant_id = %request.get_ref().tenant_id)
+    )]
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        require_user(actor(&request).as_ref()).map_err(to_status)?;
+        let req = request.into_inner();
+        let input = RevokeApiKeyInput {
+            tenant_id: TenantId::from_string(req.tenant_id),
+            id: ApiKeyId::from_string(req.id),
+        };
+
+        let api_key = self
+            .registry
+            .api_key_interactor
+            .revoke(input)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(RevokeApiKeyResponse {
+            api_key: Some(to_proto_api_key(api_key)),
+        }))
+    }
+}
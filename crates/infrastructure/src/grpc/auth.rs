@@ -0,0 +1,9 @@
+use tonic::Request;
+
+use crate::auth::Actor;
+
+/// The caller of `request`, as attached by `AuthLayer`; `None` when
+/// anonymous.
+pub(super) fn actor<T>(request: &Request<T>) -> Option<Actor> {
+    request.extensions().get::<Actor>().cloned()
+}
@@ -0,0 +1,46 @@
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Status};
+
+use oxidize_domain::error::ErrorCategory;
+use oxidize_domain::DomainError;
+
+/// Metadata key carrying the `DomainError` code of a failed call.
+pub const ERROR_CODE_METADATA: &str = "x-error-code";
+
+fn code(category: ErrorCategory) -> Code {
+    match category {
+        ErrorCategory::BadRequest => Code::InvalidArgument,
+        ErrorCategory::Unauthorized => Code::Unauthenticated,
+        ErrorCategory::Forbidden => Code::PermissionDenied,
+        ErrorCategory::NotFound => Code::NotFound,
+        ErrorCategory::Conflict => Code::AlreadyExists,
+        ErrorCategory::TooManyRequests => Code::ResourceExhausted,
+        ErrorCategory::Internal => Code::Internal,
+    }
+}
+
+/// A `DomainError` as a gRPC status, with its code in `x-error-code`.
+pub fn to_status(err: DomainError) -> Status {
+    let mut status = Status::new(code(err.category), err.message);
+    if let Ok(value) = MetadataValue::try_from(err.code) {
+        status.metadata_mut().insert(ERROR_CODE_METADATA, value);
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oxidize_domain::error::errors;
+
+    #[test]
+    fn test_to_status() {
+        let status = to_status(errors::tenant_not_found());
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "Tenant not found");
+        assert_eq!(
+            status.metadata().get(ERROR_CODE_METADATA).unwrap(),
+            "E200101"
+        );
+    }
+}
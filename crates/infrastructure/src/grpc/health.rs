@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tonic::server::NamedService;
+use tonic_health::pb::health_server::{Health, HealthServer};
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+use super::staff_service::proto::staff_service_server::StaffServiceServer;
+use super::staff_service::StaffServiceImpl;
+use super::tenant_service::proto::tenant_service_server::TenantServiceServer;
+use super::tenant_service::TenantServiceImpl;
+use crate::health::check_readiness;
+use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Services reported individually; "" is the overall server status.
+const SERVICES: [&str; 3] = [
+    "",
+    <StaffServiceServer<StaffServiceImpl> as NamedService>::NAME,
+    <TenantServiceServer<TenantServiceImpl> as NamedService>::NAME,
+];
+
+/// `grpc.health.v1.Health`, kept in sync with the readiness checks until
+/// shutdown, when every service flips to `NOT_SERVING`.
+pub fn health_service(registry: Arc<Registry>, shutdown: Shutdown) -> HealthServer<impl Health> {
+    let (reporter, service) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(reporter, registry, shutdown));
+    service
+}
+
+async fn set_status(reporter: &HealthReporter, status: ServingStatus) {
+    for name in SERVICES {
+        reporter.set_service_status(name, status).await;
+    }
+}
+
+async fn report_health(reporter: HealthReporter, registry: Arc<Registry>, shutdown: Shutdown) {
+    let mut current = None;
+
+    loop {
+        let status = if check_readiness(&registry.pools).await.is_ready() {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        // Only publish changes so Watch streams are not flooded with repeats.
+        if current != Some(status) {
+            tracing::info!("gRPC health status: {:?}", status);
+            set_status(&reporter, status).await;
+            current = Some(status);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            _ = shutdown.requested() => break,
+        }
+    }
+
+    set_status(&reporter, ServingStatus::NotServing).await;
+}
@@ -0,0 +1,24 @@
+use tonic::Request;
+
+use oxidize_domain::{errors, Result};
+use oxidize_usecase::IdempotencyKey;
+
+use super::auth::actor;
+
+/// Metadata making a create safe to retry; over the HTTP gateway it is the
+/// `Idempotency-Key` header.
+pub const IDEMPOTENCY_KEY_METADATA: &str = "idempotency-key";
+
+/// The idempotency key of `request`, scoped to its caller, if it has one.
+/// Anonymous callers cannot be told apart, so they may not send one.
+pub(super) fn idempotency_key<T>(request: &Request<T>) -> Result<Option<IdempotencyKey>> {
+    let Some(value) = request.metadata().get(IDEMPOTENCY_KEY_METADATA) else {
+        return Ok(None);
+    };
+    let caller = actor(request).ok_or_else(errors::unauthenticated)?;
+    let key = value.to_str().map_err(|_| errors::invalid_argument())?;
+    Ok(Some(IdempotencyKey {
+        key: key.to_string(),
+        caller: caller.to_string(),
+    }))
+}
@@ -1,5 +1,20 @@
+mod api_key_service;
+mod auth;
+mod error;
+mod health;
+mod idempotency;
+mod reflection;
 mod server;
 mod staff_service;
 mod tenant_service;
 
-pub use server::run_grpc_server;
+pub use error::{to_status, ERROR_CODE_METADATA};
+pub use idempotency::IDEMPOTENCY_KEY_METADATA;
+pub use server::{routes, run_grpc_server, services};
+
+/// Encoded descriptors of the API services, including their imports.
+pub const FILE_DESCRIPTOR_SETS: [&[u8]; 3] = [
+    staff_service::proto::FILE_DESCRIPTOR_SET,
+    tenant_service::proto::FILE_DESCRIPTOR_SET,
+    api_key_service::proto::FILE_DESCRIPTOR_SET,
+];
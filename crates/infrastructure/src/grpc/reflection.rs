@@ -0,0 +1,21 @@
+use tonic::service::Routes;
+use tonic_reflection::server::Builder;
+
+use super::FILE_DESCRIPTOR_SETS;
+
+fn builder() -> Builder<'static> {
+    FILE_DESCRIPTOR_SETS
+        .into_iter()
+        .fold(Builder::configure(), |builder, set| {
+            builder.register_encoded_file_descriptor_set(set)
+        })
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+}
+
+/// Adds `grpc.reflection.v1` and `grpc.reflection.v1alpha`, so clients such as
+/// grpcurl can discover the services without a copy of `schema/proto`.
+pub fn add_reflection(routes: Routes) -> anyhow::Result<Routes> {
+    Ok(routes
+        .add_service(builder().build_v1()?)
+        .add_service(builder().build_v1alpha()?))
+}
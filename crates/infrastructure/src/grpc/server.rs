@@ -1,29 +1,85 @@
 use std::sync::Arc;
 
+use tonic::service::Routes;
 use tonic::transport::Server;
-use tower_http::trace::TraceLayer;
+use tonic_web::GrpcWebLayer;
+use tower::util::option_layer;
 
+use super::api_key_service::proto::api_key_service_server::ApiKeyServiceServer;
+use super::api_key_service::ApiKeyServiceImpl;
+use super::health::health_service;
+use super::reflection::add_reflection;
 use super::staff_service::proto::staff_service_server::StaffServiceServer;
 use super::staff_service::StaffServiceImpl;
 use super::tenant_service::proto::tenant_service_server::TenantServiceServer;
 use super::tenant_service::TenantServiceImpl;
+use crate::auth::AuthLayer;
+use crate::cors::cors_layer;
+use crate::environment::Environment;
+use crate::metrics::GrpcMetricsLayer;
+use crate::otel;
+use crate::ratelimit::RateLimitLayer;
 use crate::registry::Registry;
+use crate::shutdown::Shutdown;
 
-pub async fn run_grpc_server(port: u16, registry: Arc<Registry>) -> anyhow::Result<()> {
+/// The API services described by `FILE_DESCRIPTOR_SETS`.
+pub fn services(registry: Arc<Registry>) -> Routes {
     let staff_service = StaffServiceImpl::new(registry.clone());
-    let tenant_service = TenantServiceImpl::new(registry);
+    let tenant_service = TenantServiceImpl::new(registry.clone());
+    let api_key_service = ApiKeyServiceImpl::new(registry);
 
+    Routes::new(StaffServiceServer::new(staff_service))
+        .add_service(TenantServiceServer::new(tenant_service))
+        .add_service(ApiKeyServiceServer::new(api_key_service))
+}
+
+/// All gRPC services, ready to be served standalone or next to the HTTP router.
+/// Server reflection is only included when `reflection` is set.
+pub fn routes(
+    registry: Arc<Registry>,
+    shutdown: Shutdown,
+    reflection: bool,
+) -> anyhow::Result<Routes> {
+    let routes = services(registry.clone()).add_service(health_service(registry, shutdown));
+
+    if reflection {
+        add_reflection(routes)
+    } else {
+        Ok(routes)
+    }
+}
+
+pub async fn run_grpc_server(
+    port: u16,
+    registry: Arc<Registry>,
+    shutdown: Shutdown,
+    env: &Environment,
+) -> anyhow::Result<()> {
     let addr = format!("0.0.0.0:{}", port).parse()?;
     tracing::info!("Starting gRPC server on {}", addr);
 
-    let trace_layer = TraceLayer::new_for_grpc();
+    let trace_layer = otel::grpc_trace_layer();
+    let cors_layer = option_layer(cors_layer(&env.server.cors)?);
 
-    Server::builder()
+    let signal = shutdown.clone();
+    // HTTP/1.1 is accepted for gRPC-Web; native gRPC clients still use h2.
+    let server = Server::builder()
+        .accept_http1(true)
         .layer(trace_layer)
-        .add_service(StaffServiceServer::new(staff_service))
-        .add_service(TenantServiceServer::new(tenant_service))
-        .serve(addr)
-        .await?;
+        .layer(cors_layer)
+        .layer(GrpcWebLayer::new())
+        .layer(GrpcMetricsLayer)
+        .layer(RateLimitLayer::per_ip(registry.rate_limiter.clone()))
+        .layer(AuthLayer::new(registry.clone()))
+        .layer(RateLimitLayer::new(registry.rate_limiter.clone()))
+        .add_routes(routes(
+            registry,
+            shutdown.clone(),
+            env.features.grpc_reflection,
+        )?)
+        .serve_with_shutdown(addr, async move { signal.requested().await });
+    shutdown.drain(async { Ok(server.await?) }).await?;
 
+    tracing::info!("gRPC server stopped");
     Ok(())
 }
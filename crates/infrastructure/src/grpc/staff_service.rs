@@ -2,15 +2,22 @@ use std::sync::Arc;
 
 use tonic::{Request, Response, Status};
 
-use oxidize_domain::{StaffId, StaffRole, TenantId};
+use oxidize_domain::error::errors;
+use oxidize_domain::{ApiKeyScope, StaffId, StaffRole, TenantId};
 use oxidize_usecase::{
     CreateStaffInput, DeleteStaffInput, GetStaffInput, ListStaffInput, UpdateStaffInput,
 };
 
+use super::auth::actor;
+use super::error::to_status;
+use super::idempotency::idempotency_key;
+use crate::auth::{authorize, Actor};
 use crate::registry::Registry;
 
 pub mod proto {
     tonic::include_proto!("staff");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("staff_descriptor");
 }
 
 use proto::staff_service_server::StaffService;
@@ -28,6 +35,35 @@ impl StaffServiceImpl {
     pub fn new(registry: Arc<Registry>) -> Self {
         Self { registry }
     }
+
+    /// Authorizes `actor` for `scope` on the tenant of staff `id`. Only API
+    /// keys need the staff loaded; a missing staff is left to the call to
+    /// report.
+    async fn authorize_staff(
+        &self,
+        actor: Option<&Actor>,
+        scope: ApiKeyScope,
+        id: &StaffId,
+    ) -> Result<(), Status> {
+        if !matches!(actor, Some(Actor::Service { .. })) {
+            return Ok(());
+        }
+        let input = GetStaffInput {
+            id: Some(id.clone()),
+            auth_uid: None,
+            with_tenant: false,
+        };
+        let staff = self
+            .registry
+            .staff_interactor
+            .get(input)
+            .await
+            .map_err(to_status)?;
+        match staff {
+            Some(staff) => authorize(actor, scope, Some(&staff.tenant_id)).map_err(to_status),
+            None => Ok(()),
+        }
+    }
 }
 
 fn to_proto_staff(s: oxidize_domain::Staff) -> Staff {
@@ -54,11 +90,15 @@ fn parse_role(role: &str) -> StaffRole {
 
 #[tonic::async_trait]
 impl StaffService for StaffServiceImpl {
-    #[tracing::instrument(skip(self, request), fields(service = "staff"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "staff", tenant_id = tracing::field::Empty)
+    )]
     async fn get_staff(
         &self,
         request: Request<GetStaffRequest>,
     ) -> Result<Response<GetStaffResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = GetStaffInput {
             id: req.id.map(StaffId::from_string),
@@ -71,32 +111,50 @@ impl StaffService for StaffServiceImpl {
             .staff_interactor
             .get(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?
-            .ok_or_else(|| Status::not_found("Staff not found"))?;
+            .map_err(to_status)?
+            .ok_or_else(|| to_status(errors::staff_not_found()))?;
+
+        tracing::Span::current().record("tenant_id", staff.tenant_id.as_str());
+        authorize(
+            actor.as_ref(),
+            ApiKeyScope::StaffsRead,
+            Some(&staff.tenant_id),
+        )
+        .map_err(to_status)?;
 
         Ok(Response::new(GetStaffResponse {
             staff: Some(to_proto_staff(staff)),
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "staff"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "staff", tenant_id = request.get_ref().tenant_id.as_deref())
+    )]
     async fn list_staffs(
         &self,
         request: Request<ListStaffsRequest>,
     ) -> Result<Response<ListStaffsResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = ListStaffInput {
             tenant_id: req.tenant_id.map(TenantId::from_string),
             limit: req.limit,
             offset: req.offset,
         };
+        authorize(
+            actor.as_ref(),
+            ApiKeyScope::StaffsRead,
+            input.tenant_id.as_ref(),
+        )
+        .map_err(to_status)?;
 
         let output = self
             .registry
             .staff_interactor
             .list(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(ListStaffsResponse {
             staffs: output.staff.into_iter().map(to_proto_staff).collect(),
@@ -104,11 +162,16 @@ impl StaffService for StaffServiceImpl {
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "staff"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "staff", tenant_id = %request.get_ref().tenant_id)
+    )]
     async fn create_staff(
         &self,
         request: Request<CreateStaffRequest>,
     ) -> Result<Response<CreateStaffResponse>, Status> {
+        let actor = actor(&request);
+        let idempotency_key = idempotency_key(&request).map_err(to_status)?;
         let req = request.into_inner();
         let input = CreateStaffInput {
             tenant_id: TenantId::from_string(req.tenant_id),
@@ -117,25 +180,36 @@ impl StaffService for StaffServiceImpl {
             display_name: req.display_name,
             image_path: req.image_path,
             email: req.email,
+            idempotency_key,
         };
+        authorize(
+            actor.as_ref(),
+            ApiKeyScope::StaffsWrite,
+            Some(&input.tenant_id),
+        )
+        .map_err(to_status)?;
 
         let staff = self
             .registry
             .staff_interactor
             .create(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(CreateStaffResponse {
             staff: Some(to_proto_staff(staff)),
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "staff"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "staff", tenant_id = tracing::field::Empty)
+    )]
     async fn update_staff(
         &self,
         request: Request<UpdateStaffRequest>,
     ) -> Result<Response<UpdateStaffResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = UpdateStaffInput {
             id: StaffId::from_string(req.id),
@@ -144,13 +218,17 @@ impl StaffService for StaffServiceImpl {
             image_path: req.image_path,
             email: req.email,
         };
+        self.authorize_staff(actor.as_ref(), ApiKeyScope::StaffsWrite, &input.id)
+            .await?;
 
         let staff = self
             .registry
             .staff_interactor
             .update(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
+
+        tracing::Span::current().record("tenant_id", staff.tenant_id.as_str());
 
         Ok(Response::new(UpdateStaffResponse {
             staff: Some(to_proto_staff(staff)),
@@ -162,16 +240,20 @@ impl StaffService for StaffServiceImpl {
         &self,
         request: Request<DeleteStaffRequest>,
     ) -> Result<Response<DeleteStaffResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = DeleteStaffInput {
             id: StaffId::from_string(req.id),
+            allow_missing: req.allow_missing,
         };
+        self.authorize_staff(actor.as_ref(), ApiKeyScope::StaffsWrite, &input.id)
+            .await?;
 
         self.registry
             .staff_interactor
             .delete(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(DeleteStaffResponse {}))
     }
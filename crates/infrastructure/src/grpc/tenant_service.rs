@@ -2,22 +2,30 @@ use std::sync::Arc;
 
 use tonic::{Request, Response, Status};
 
-use oxidize_domain::TenantId;
+use oxidize_domain::error::errors;
+use oxidize_domain::{ApiKeyScope, TenantId};
 use oxidize_usecase::{
     CreateTenantInput, DeleteTenantInput, GetTenantInput, ListTenantInput, UpdateTenantInput,
 };
 
+use super::auth::actor;
+use super::error::to_status;
+use super::idempotency::idempotency_key;
+use crate::auth::authorize;
 use crate::registry::Registry;
+use crate::retention::close_tenant;
 
 pub mod proto {
     tonic::include_proto!("tenant");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("tenant_descriptor");
 }
 
 use proto::tenant_service_server::TenantService;
 use proto::{
-    CreateTenantRequest, CreateTenantResponse, DeleteTenantRequest, DeleteTenantResponse,
-    GetTenantRequest, GetTenantResponse, ListTenantsRequest, ListTenantsResponse, Tenant,
-    UpdateTenantRequest, UpdateTenantResponse,
+    CloseTenantRequest, CloseTenantResponse, CreateTenantRequest, CreateTenantResponse,
+    DeleteTenantRequest, DeleteTenantResponse, GetTenantRequest, GetTenantResponse,
+    ListTenantsRequest, ListTenantsResponse, Tenant, UpdateTenantRequest, UpdateTenantResponse,
 };
 
 pub struct TenantServiceImpl {
@@ -36,28 +44,36 @@ fn to_proto_tenant(t: oxidize_domain::Tenant) -> Tenant {
         name: t.name,
         created_at: t.created_at.to_rfc3339(),
         updated_at: t.updated_at.to_rfc3339(),
+        closed_at: t.closed_at.map(|c| c.to_rfc3339()),
+        purge_after: t.purge_after.map(|p| p.to_rfc3339()),
+        archived_at: t.archive.map(|a| a.archived_at.to_rfc3339()),
     }
 }
 
 #[tonic::async_trait]
 impl TenantService for TenantServiceImpl {
-    #[tracing::instrument(skip(self, request), fields(service = "tenant"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "tenant", tenant_id = %request.get_ref().id)
+    )]
     async fn get_tenant(
         &self,
         request: Request<GetTenantRequest>,
     ) -> Result<Response<GetTenantResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = GetTenantInput {
             id: TenantId::from_string(req.id),
         };
+        authorize(actor.as_ref(), ApiKeyScope::TenantsRead, Some(&input.id)).map_err(to_status)?;
 
         let tenant = self
             .registry
             .tenant_interactor
             .get(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?
-            .ok_or_else(|| Status::not_found("Tenant not found"))?;
+            .map_err(to_status)?
+            .ok_or_else(|| to_status(errors::tenant_not_found()))?;
 
         Ok(Response::new(GetTenantResponse {
             tenant: Some(to_proto_tenant(tenant)),
@@ -69,6 +85,7 @@ impl TenantService for TenantServiceImpl {
         &self,
         request: Request<ListTenantsRequest>,
     ) -> Result<Response<ListTenantsResponse>, Status> {
+        authorize(actor(&request).as_ref(), ApiKeyScope::TenantsRead, None).map_err(to_status)?;
         let req = request.into_inner();
         let input = ListTenantInput {
             limit: req.limit,
@@ -80,7 +97,7 @@ impl TenantService for TenantServiceImpl {
             .tenant_interactor
             .list(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(ListTenantsResponse {
             tenants: output.tenants.into_iter().map(to_proto_tenant).collect(),
@@ -88,65 +105,111 @@ impl TenantService for TenantServiceImpl {
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "tenant"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "tenant", tenant_id = tracing::field::Empty)
+    )]
     async fn create_tenant(
         &self,
         request: Request<CreateTenantRequest>,
     ) -> Result<Response<CreateTenantResponse>, Status> {
+        authorize(actor(&request).as_ref(), ApiKeyScope::TenantsWrite, None).map_err(to_status)?;
+        let idempotency_key = idempotency_key(&request).map_err(to_status)?;
         let req = request.into_inner();
-        let input = CreateTenantInput { name: req.name };
+        let input = CreateTenantInput {
+            name: req.name,
+            tags: Vec::new(),
+            idempotency_key,
+        };
 
         let tenant = self
             .registry
             .tenant_interactor
             .create(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
+
+        tracing::Span::current().record("tenant_id", tenant.id.as_str());
 
         Ok(Response::new(CreateTenantResponse {
             tenant: Some(to_proto_tenant(tenant)),
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "tenant"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "tenant", tenant_id = %request.get_ref().id)
+    )]
     async fn update_tenant(
         &self,
         request: Request<UpdateTenantRequest>,
     ) -> Result<Response<UpdateTenantResponse>, Status> {
+        let actor = actor(&request);
         let req = request.into_inner();
         let input = UpdateTenantInput {
             id: TenantId::from_string(req.id),
             name: req.name,
         };
+        authorize(actor.as_ref(), ApiKeyScope::TenantsWrite, Some(&input.id)).map_err(to_status)?;
 
         let tenant = self
             .registry
             .tenant_interactor
             .update(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(UpdateTenantResponse {
             tenant: Some(to_proto_tenant(tenant)),
         }))
     }
 
-    #[tracing::instrument(skip(self, request), fields(service = "tenant"))]
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "tenant", tenant_id = %request.get_ref().id)
+    )]
     async fn delete_tenant(
         &self,
         request: Request<DeleteTenantRequest>,
     ) -> Result<Response<DeleteTenantResponse>, Status> {
+        // Ending a tenant is beyond any of its own API keys.
+        authorize(actor(&request).as_ref(), ApiKeyScope::TenantsWrite, None).map_err(to_status)?;
         let req = request.into_inner();
         let input = DeleteTenantInput {
             id: TenantId::from_string(req.id),
+            allow_missing: req.allow_missing,
+            force: req.force,
         };
 
         self.registry
             .tenant_interactor
             .delete(input)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(to_status)?;
 
         Ok(Response::new(DeleteTenantResponse {}))
     }
+
+    #[tracing::instrument(
+        skip(self, request),
+        fields(service = "tenant", tenant_id = %request.get_ref().id)
+    )]
+    async fn close_tenant(
+        &self,
+        request: Request<CloseTenantRequest>,
+    ) -> Result<Response<CloseTenantResponse>, Status> {
+        authorize(actor(&request).as_ref(), ApiKeyScope::TenantsWrite, None).map_err(to_status)?;
+        let req = request.into_inner();
+        let tenant = close_tenant(
+            &self.registry,
+            TenantId::from_string(req.id),
+            req.retention_days,
+        )
+        .await
+        .map_err(to_status)?;
+
+        Ok(Response::new(CloseTenantResponse {
+            tenant: Some(to_proto_tenant(tenant)),
+        }))
+    }
 }
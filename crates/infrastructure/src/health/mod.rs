@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::database::{migration_status, MigrationState, Pools};
+
+/// Upper bound for a single dependency check, so a hung database cannot hang
+/// the probe itself.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Unavailable,
+}
+
+/// The outcome of one check. Why a check failed is only logged, since the
+/// probe is unauthenticated and database errors name hosts and roles.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<&'static str, CheckResult>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.status == CheckStatus::Ok
+    }
+}
+
+async fn run_check<F>(name: &'static str, check: F) -> CheckResult
+where
+    F: Future<Output = anyhow::Result<()>>,
+{
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("timed out after {:?}", CHECK_TIMEOUT)),
+    };
+
+    let status = match result {
+        Ok(()) => CheckStatus::Ok,
+        Err(e) => {
+            tracing::warn!(check = name, error = %e, "Readiness check failed");
+            CheckStatus::Unavailable
+        }
+    };
+    CheckResult {
+        status,
+        latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+    }
+}
+
+async fn check_database(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
+
+async fn check_migrations(pool: &PgPool) -> anyhow::Result<()> {
+    let outstanding: Vec<String> = migration_status(pool)
+        .await?
+        .into_iter()
+        .filter(|s| s.state != MigrationState::Applied)
+        .map(|s| format!("{} {}", s.version, s.state.as_str()))
+        .collect();
+
+    if outstanding.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!("{}", outstanding.join(", "))
+    }
+}
+
+/// Checks every dependency the servers need to handle traffic.
+pub async fn check_readiness(pools: &Pools) -> Readiness {
+    let replica = async {
+        match pools.replica() {
+            Some(replica) => Some(run_check("database_replica", check_database(replica)).await),
+            None => None,
+        }
+    };
+    let (database, migrations, replica) = tokio::join!(
+        run_check("database", check_database(pools.primary())),
+        run_check("migrations", check_migrations(pools.primary())),
+        replica,
+    );
+
+    let mut checks = BTreeMap::from([("database", database), ("migrations", migrations)]);
+    if let Some(replica) = replica {
+        checks.insert("database_replica", replica);
+    }
+    let status = if checks.values().all(|c| c.status == CheckStatus::Ok) {
+        CheckStatus::Ok
+    } else {
+        CheckStatus::Unavailable
+    };
+
+    Readiness { status, checks }
+}
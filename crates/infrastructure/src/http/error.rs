@@ -0,0 +1,65 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use oxidize_domain::error::ErrorCategory;
+use oxidize_domain::DomainError;
+
+use crate::metrics::ErrorCode;
+
+/// Error body returned by every `/api/v1` endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Stable error code, e.g. `E200101`.
+    #[schema(example = "E200101")]
+    pub code: String,
+    /// Human-readable description.
+    #[schema(example = "Tenant not found")]
+    pub message: String,
+}
+
+/// A `DomainError` rendered as an HTTP response.
+#[derive(Debug)]
+pub struct ApiError(DomainError);
+
+impl From<DomainError> for ApiError {
+    fn from(err: DomainError) -> Self {
+        Self(err)
+    }
+}
+
+fn status_code(category: ErrorCategory) -> StatusCode {
+    match category {
+        ErrorCategory::BadRequest => StatusCode::BAD_REQUEST,
+        ErrorCategory::Unauthorized => StatusCode::UNAUTHORIZED,
+        ErrorCategory::Forbidden => StatusCode::FORBIDDEN,
+        ErrorCategory::NotFound => StatusCode::NOT_FOUND,
+        ErrorCategory::Conflict => StatusCode::CONFLICT,
+        ErrorCategory::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCategory::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = status_code(self.0.category);
+        // Internal messages may carry driver details; log them instead.
+        let message = if status.is_server_error() {
+            tracing::error!("{}: {}", self.0.code, self.0.message);
+            "Internal error".to_string()
+        } else {
+            self.0.message
+        };
+        let body = ErrorResponse {
+            code: self.0.code.to_string(),
+            message,
+        };
+        let mut response = (status, Json(body)).into_response();
+        response
+            .extensions_mut()
+            .insert(ErrorCode(self.0.code.to_string()));
+        response
+    }
+}
@@ -1,45 +1,114 @@
 use std::sync::Arc;
 
-use axum::{extract::State, http::StatusCode, Json};
-use serde::Serialize;
-
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Extension, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::{http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use oxidize_domain::{errors, ApiKeyScope, DomainError, TenantId};
 use oxidize_usecase::{ListStaffInput, ListStaffOutput, ListTenantInput, ListTenantOutput};
 
+use super::error::{ApiError, ErrorResponse};
+use crate::auth::{authorize, Actor};
+use crate::health::{check_readiness, Readiness};
 use crate::registry::Registry;
 
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Unwraps query parameters, reporting malformed ones as `E100002`.
+fn query<T>(query: Result<Query<T>, QueryRejection>) -> Result<T, ApiError> {
+    query.map(|Query(params)| params).map_err(|e| {
+        DomainError::bad_request(errors::invalid_argument().code, e.body_text()).into()
+    })
+}
+
 #[derive(Serialize)]
 pub struct HealthResponse {
     status: &'static str,
 }
 
+/// Liveness: the process is up and serving requests.
 #[tracing::instrument]
 pub async fn health() -> Json<HealthResponse> {
     Json(HealthResponse { status: "ok" })
 }
 
-#[derive(Serialize)]
+/// Readiness: every dependency is reachable; 503 otherwise.
+#[tracing::instrument(skip(state))]
+pub async fn ready(State(state): State<Arc<Registry>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = check_readiness(&state.pools).await;
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// Prometheus scrape endpoint.
+pub async fn metrics() -> Response {
+    match crate::metrics::render() {
+        Ok(body) => ([(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to render metrics: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTenantsParams {
+    /// Maximum number of tenants to return.
+    limit: Option<u64>,
+    /// Number of tenants to skip.
+    offset: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct ListTenantsResponse {
     tenants: Vec<TenantResponse>,
+    /// Number of tenants across all pages.
     total_count: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct TenantResponse {
     id: String,
     name: String,
 }
 
-#[tracing::instrument(skip(state))]
+/// List tenants, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/tenants",
+    tag = "tenants",
+    params(ListTenantsParams),
+    responses(
+        (status = 200, description = "A page of tenants", body = ListTenantsResponse),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Not allowed for this API key", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip(state, actor))]
 pub async fn list_tenants(
     State(state): State<Arc<Registry>>,
-) -> Result<Json<ListTenantsResponse>, StatusCode> {
-    let input = ListTenantInput::default();
+    actor: Option<Extension<Actor>>,
+    params: Result<Query<ListTenantsParams>, QueryRejection>,
+) -> Result<Json<ListTenantsResponse>, ApiError> {
+    authorize(actor.as_deref(), ApiKeyScope::TenantsRead, None)?;
+    let params = query(params)?;
+    let input = ListTenantInput {
+        limit: params.limit,
+        offset: params.offset,
+    };
 
-    let output: ListTenantOutput = state
-        .tenant_interactor
-        .list(input)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let output: ListTenantOutput = state.tenant_interactor.list(input).await?;
 
     let response = ListTenantsResponse {
         tenants: output
@@ -56,13 +125,25 @@ pub async fn list_tenants(
     Ok(Json(response))
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListStaffsParams {
+    /// Only return staff of this tenant.
+    tenant_id: Option<String>,
+    /// Maximum number of staff to return.
+    limit: Option<u64>,
+    /// Number of staff to skip.
+    offset: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct ListStaffsResponse {
     staffs: Vec<StaffResponse>,
+    /// Number of staff across all pages.
     total_count: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct StaffResponse {
     id: String,
     tenant_id: String,
@@ -70,17 +151,42 @@ pub struct StaffResponse {
     email: String,
 }
 
-#[tracing::instrument(skip(state))]
+/// List staff, optionally restricted to one tenant.
+#[utoipa::path(
+    get,
+    path = "/api/v1/staffs",
+    tag = "staffs",
+    params(ListStaffsParams),
+    responses(
+        (status = 200, description = "A page of staff", body = ListStaffsResponse),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Not allowed for this API key", body = ErrorResponse),
+        (status = 500, description = "Internal error", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(skip(state, actor), fields(tenant_id = tracing::field::Empty))]
 pub async fn list_staffs(
     State(state): State<Arc<Registry>>,
-) -> Result<Json<ListStaffsResponse>, StatusCode> {
-    let input = ListStaffInput::default();
-
-    let output: ListStaffOutput = state
-        .staff_interactor
-        .list(input)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    actor: Option<Extension<Actor>>,
+    params: Result<Query<ListStaffsParams>, QueryRejection>,
+) -> Result<Json<ListStaffsResponse>, ApiError> {
+    let params = query(params)?;
+    if let Some(tenant_id) = &params.tenant_id {
+        tracing::Span::current().record("tenant_id", tenant_id.as_str());
+    }
+    let input = ListStaffInput {
+        tenant_id: params.tenant_id.map(TenantId::from_string),
+        limit: params.limit,
+        offset: params.offset,
+    };
+    authorize(
+        actor.as_deref(),
+        ApiKeyScope::StaffsRead,
+        input.tenant_id.as_ref(),
+    )?;
+
+    let output: ListStaffOutput = state.staff_interactor.list(input).await?;
 
     let response = ListStaffsResponse {
         staffs: output
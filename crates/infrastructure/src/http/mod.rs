@@ -1,4 +1,8 @@
+mod error;
 mod handlers;
+mod openapi;
 mod router;
 
-pub use router::run_http_server;
+pub use error::ApiError;
+pub use openapi::openapi;
+pub use router::{router, run_http_server};
@@ -0,0 +1,53 @@
+use utoipa::OpenApi;
+
+use super::error::ErrorResponse;
+use super::handlers;
+
+/// OpenAPI 3.1 description of the hand-written `/api/v1` endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "oxidize", description = "Tenant and staff management API"),
+    paths(handlers::list_tenants, handlers::list_staffs),
+    components(schemas(ErrorResponse)),
+    tags(
+        (name = "tenants", description = "Tenants"),
+        (name = "staffs", description = "Staff belonging to a tenant"),
+    )
+)]
+pub struct ApiDoc;
+
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut document = ApiDoc::openapi();
+    // The crate declares no license, so utoipa would emit an empty one.
+    document.info.license = None;
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_document() {
+        let json = serde_json::to_value(openapi()).unwrap();
+        assert_eq!(json["openapi"], "3.1.0");
+
+        let list_tenants = &json["paths"]["/api/v1/tenants"]["get"];
+        let params: Vec<&str> = list_tenants["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(params, ["limit", "offset"]);
+        assert!(list_tenants["responses"]["400"].is_object());
+
+        for schema in ["ErrorResponse", "ListStaffsResponse", "TenantResponse"] {
+            assert!(
+                json["components"]["schemas"][schema].is_object(),
+                "{}",
+                schema
+            );
+        }
+    }
+}
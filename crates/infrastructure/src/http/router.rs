@@ -1,45 +1,75 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
-use axum::{extract::Request, routing::get, Router};
-use tower_http::trace::{DefaultOnResponse, MakeSpan, TraceLayer};
-use tracing::{Level, Span};
+use axum::{middleware, routing::get, Router};
+use utoipa_swagger_ui::SwaggerUi;
 
 use super::handlers;
+use super::openapi::openapi;
+use crate::auth::AuthLayer;
+use crate::cors::cors_layer;
+use crate::environment::{Environment, FeatureConfig};
+use crate::metrics::track_http;
+use crate::ratelimit::RateLimitLayer;
 use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+use crate::{gateway, grpc, otel};
 
-#[derive(Clone)]
-struct OtelMakeSpan;
-
-impl MakeSpan<axum::body::Body> for OtelMakeSpan {
-    fn make_span(&mut self, request: &Request<axum::body::Body>) -> Span {
-        tracing::info_span!(
-            "http_request",
-            http.method = %request.method(),
-            http.uri = %request.uri(),
-            http.version = ?request.version(),
-            otel.kind = "server",
-            otel.status_code = tracing::field::Empty,
-        )
-    }
-}
-
-pub async fn run_http_server(port: u16, registry: Arc<Registry>) -> anyhow::Result<()> {
-    let trace_layer = TraceLayer::new_for_http()
-        .make_span_with(OtelMakeSpan)
-        .on_response(DefaultOnResponse::new().level(Level::INFO));
-
-    let app = Router::new()
+/// The hand-written JSON API (described at `/openapi.json`, browsable at
+/// `/swagger-ui`) plus the gRPC gateway, which serves every RPC with a
+/// `google.api.http` annotation under its annotated path. Swagger UI and the
+/// gateway can be turned off in `features`.
+pub fn router(registry: Arc<Registry>, features: &FeatureConfig) -> anyhow::Result<Router> {
+    let rate_limit = RateLimitLayer::new(registry.rate_limiter.clone());
+    let per_ip = RateLimitLayer::per_ip(registry.rate_limiter.clone());
+    let auth = AuthLayer::new(registry.clone());
+    let mut router = Router::new()
         .route("/health", get(handlers::health))
+        .route("/health/live", get(handlers::health))
+        .route("/health/ready", get(handlers::ready))
+        .route("/metrics", get(handlers::metrics))
         .route("/api/v1/tenants", get(handlers::list_tenants))
         .route("/api/v1/staffs", get(handlers::list_staffs))
-        .layer(trace_layer)
-        .with_state(registry);
+        .with_state(registry.clone());
+    if features.swagger_ui {
+        router = router.merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", openapi()));
+    }
+    if features.http_gateway {
+        router = router.merge(gateway::router(
+            &grpc::FILE_DESCRIPTOR_SETS,
+            grpc::services(registry),
+        )?);
+    }
+
+    Ok(router
+        .layer(rate_limit)
+        .layer(auth)
+        .layer(per_ip)
+        .layer(middleware::from_fn(track_http))
+        .layer(otel::http_trace_layer()))
+}
+
+pub async fn run_http_server(
+    port: u16,
+    registry: Arc<Registry>,
+    shutdown: Shutdown,
+    env: &Environment,
+) -> anyhow::Result<()> {
+    let mut app = router(registry, &env.features)?;
+    if let Some(cors) = cors_layer(&env.server.cors)? {
+        app = app.layer(cors);
+    }
 
     let addr = format!("0.0.0.0:{}", port);
     tracing::info!("Starting HTTP server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    let signal = shutdown.clone();
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server =
+        axum::serve(listener, app).with_graceful_shutdown(async move { signal.requested().await });
+    shutdown.drain(async { Ok(server.await?) }).await?;
 
+    tracing::info!("HTTP server stopped");
     Ok(())
 }
@@ -1,14 +1,34 @@
+pub mod archive;
+pub mod auth;
+pub mod cache;
 pub mod cmd;
+pub mod cors;
 pub mod database;
 pub mod environment;
+pub mod gateway;
 pub mod grpc;
+pub mod health;
 pub mod http;
+pub mod metrics;
 pub mod otel;
+pub mod ratelimit;
 pub mod registry;
+pub mod retention;
+pub mod serve;
+pub mod shutdown;
 
-pub use cmd::{Cli, Commands};
+pub use cmd::{
+    run_api_key, run_config, run_export, run_import, run_migrate, run_openapi, run_seed, run_staff,
+    run_tenant, Cli, Commands,
+};
 pub use database::*;
-pub use environment::Environment;
+pub use environment::{
+    AuthConfig, ConfigError, CorsConfig, DatabaseConfig, Environment, FeatureConfig, LogFormat,
+    OtlpProtocol, RateLimitConfig, SamplerConfig, Secret, ServerConfig, TelemetryConfig,
+    TenantConfig, TraceSampler,
+};
 pub use grpc::run_grpc_server;
 pub use http::run_http_server;
 pub use registry::Registry;
+pub use serve::{run_multiplexed_server, run_server};
+pub use shutdown::Shutdown;
@@ -0,0 +1,85 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::http::{HeaderMap, Request, Response};
+use opentelemetry::KeyValue;
+use tower::{Layer, Service};
+
+use super::metrics;
+use crate::grpc::ERROR_CODE_METADATA;
+use crate::otel::rpc_name;
+
+/// Records the call count and latency of every gRPC method, labeled by
+/// service, method, status code and error code.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GrpcMetricsLayer;
+
+impl<S> Layer<S> for GrpcMetricsLayer {
+    type Service = GrpcMetrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcMetrics { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GrpcMetrics<S> {
+    inner: S,
+}
+
+fn record(path: &str, headers: &HeaderMap, start: Instant) {
+    let (service, method) = rpc_name(path);
+    // Only trailers-only responses, i.e. failed unary calls, carry the status
+    // in the headers; anything else completed normally.
+    let status = headers
+        .get("grpc-status")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    let mut attributes = vec![
+        KeyValue::new("rpc.system", "grpc"),
+        KeyValue::new("rpc.service", service.to_string()),
+        KeyValue::new("rpc.method", method.to_string()),
+        KeyValue::new("rpc.grpc.status_code", status),
+    ];
+    if let Some(code) = headers
+        .get(ERROR_CODE_METADATA)
+        .and_then(|v| v.to_str().ok())
+    {
+        attributes.push(KeyValue::new("error.code", code.to_string()));
+    }
+
+    let metrics = metrics();
+    metrics.rpc_requests.add(1, &attributes);
+    metrics
+        .rpc_duration
+        .record(start.elapsed().as_secs_f64(), &attributes);
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for GrpcMetrics<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let start = Instant::now();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let response = future.await?;
+            record(&path, response.headers(), start);
+            Ok(response)
+        })
+    }
+}
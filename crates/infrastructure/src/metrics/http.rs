@@ -0,0 +1,57 @@
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use opentelemetry::KeyValue;
+
+use super::metrics;
+
+/// Response extension naming the route of a request axum did not route
+/// itself, such as a gateway path template.
+#[derive(Clone, Debug)]
+pub struct RouteLabel(pub String);
+
+/// Response extension carrying the `DomainError` code of a failed request.
+#[derive(Clone, Debug)]
+pub struct ErrorCode(pub String);
+
+/// Records the request count and latency of every HTTP request, labeled by
+/// method, route template, status and error code.
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let matched = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let route = matched
+        .or_else(|| {
+            response
+                .extensions()
+                .get::<RouteLabel>()
+                .map(|r| r.0.clone())
+        })
+        .unwrap_or_else(|| "unmatched".to_string());
+    let mut attributes = vec![
+        KeyValue::new("http.request.method", method),
+        KeyValue::new("http.route", route),
+        KeyValue::new(
+            "http.response.status_code",
+            i64::from(response.status().as_u16()),
+        ),
+    ];
+    if let Some(ErrorCode(code)) = response.extensions().get() {
+        attributes.push(KeyValue::new("error.code", code.clone()));
+    }
+
+    let metrics = metrics();
+    metrics.http_requests.add(1, &attributes);
+    metrics
+        .http_duration
+        .record(start.elapsed().as_secs_f64(), &attributes);
+    response
+}
@@ -0,0 +1,146 @@
+//! Request, connection pool and business metrics.
+//!
+//! Instruments are recorded through the global OpenTelemetry meter set up by
+//! `otel::init`, which exports them in Prometheus text format at `/metrics`
+//! and, when `OTLP_ENDPOINT` is set, pushes them over OTLP as well.
+
+mod grpc;
+mod http;
+
+use std::sync::OnceLock;
+
+use opentelemetry::metrics::{Counter, Histogram, Meter};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_prometheus::PrometheusExporter;
+use prometheus::TextEncoder;
+use sqlx::PgPool;
+
+pub use grpc::GrpcMetricsLayer;
+pub use http::{track_http, ErrorCode, RouteLabel};
+
+/// Bucket boundaries, in seconds, for every latency histogram.
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0,
+];
+
+static PROMETHEUS: OnceLock<prometheus::Registry> = OnceLock::new();
+
+fn prometheus_registry() -> &'static prometheus::Registry {
+    PROMETHEUS.get_or_init(prometheus::Registry::new)
+}
+
+/// A reader collecting into the registry rendered by [`render`].
+pub fn prometheus_reader() -> anyhow::Result<PrometheusExporter> {
+    Ok(opentelemetry_prometheus::exporter()
+        .with_registry(prometheus_registry().clone())
+        .build()?)
+}
+
+/// Current metrics in the Prometheus text exposition format.
+pub fn render() -> anyhow::Result<String> {
+    Ok(TextEncoder::new().encode_to_string(&prometheus_registry().gather())?)
+}
+
+pub struct Metrics {
+    pub http_requests: Counter<u64>,
+    pub http_duration: Histogram<f64>,
+    pub rpc_requests: Counter<u64>,
+    pub rpc_duration: Histogram<f64>,
+    pub rate_limited: Counter<u64>,
+    pub tenants_created: Counter<u64>,
+    pub staff_created: Counter<u64>,
+    pub cache_requests: Counter<u64>,
+}
+
+fn duration_histogram(
+    meter: &Meter,
+    name: &'static str,
+    description: &'static str,
+) -> Histogram<f64> {
+    meter
+        .f64_histogram(name)
+        .with_description(description)
+        .with_unit("s")
+        .with_boundaries(LATENCY_BUCKETS.to_vec())
+        .build()
+}
+
+impl Metrics {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            http_requests: meter
+                .u64_counter("http.server.requests")
+                .with_description("HTTP requests handled")
+                .build(),
+            http_duration: duration_histogram(
+                meter,
+                "http.server.request.duration",
+                "HTTP request latency",
+            ),
+            rpc_requests: meter
+                .u64_counter("rpc.server.requests")
+                .with_description("gRPC calls handled")
+                .build(),
+            rpc_duration: duration_histogram(meter, "rpc.server.duration", "gRPC call latency"),
+            rate_limited: meter
+                .u64_counter("oxidize.rate_limit.rejected")
+                .with_description("Requests refused by the rate limiter")
+                .build(),
+            tenants_created: meter
+                .u64_counter("oxidize.tenants.created")
+                .with_description("Tenants created")
+                .build(),
+            staff_created: meter
+                .u64_counter("oxidize.staff.created")
+                .with_description("Staff created")
+                .build(),
+            cache_requests: meter
+                .u64_counter("oxidize.cache.requests")
+                .with_description("Repository cache lookups, by hit or miss")
+                .build(),
+        }
+    }
+}
+
+/// The process-wide instruments. Must not be called before `otel::init`,
+/// or they are bound to the no-op meter.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::new(&global::meter("oxidize")))
+}
+
+/// Reports the connection counts of `pool`. `name` tells the primary and
+/// replica pools apart.
+pub fn observe_pool(pool: &PgPool, name: &'static str) {
+    let meter = global::meter("oxidize");
+    let attributes = [KeyValue::new("pool.name", name)];
+
+    let used = pool.clone();
+    let used_attributes = attributes.clone();
+    meter
+        .u64_observable_gauge("db.client.connections.used")
+        .with_description("Connections currently checked out")
+        .with_callback(move |observer| {
+            let idle = used.num_idle() as u64;
+            observer.observe(
+                u64::from(used.size()).saturating_sub(idle),
+                &used_attributes,
+            );
+        })
+        .build();
+
+    let idle = pool.clone();
+    let idle_attributes = attributes.clone();
+    meter
+        .u64_observable_gauge("db.client.connections.idle")
+        .with_description("Open connections waiting in the pool")
+        .with_callback(move |observer| observer.observe(idle.num_idle() as u64, &idle_attributes))
+        .build();
+
+    let max = pool.options().get_max_connections();
+    meter
+        .u64_observable_gauge("db.client.connections.max")
+        .with_description("Maximum open connections")
+        .with_callback(move |observer| observer.observe(u64::from(max), &attributes))
+        .build();
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use otlp_tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+
+use crate::environment::TelemetryConfig;
+
+/// Export settings shared by the span, metric and log exporters.
+pub struct OtlpSettings<'a> {
+    pub endpoint: &'a str,
+    pub config: &'a TelemetryConfig,
+}
+
+impl OtlpSettings<'_> {
+    /// OTLP/HTTP posts each signal to its own path under the base endpoint.
+    pub fn http_endpoint(&self, signal_path: &str) -> String {
+        format!("{}{}", self.endpoint.trim_end_matches('/'), signal_path)
+    }
+
+    pub fn http_headers(&self) -> HashMap<String, String> {
+        self.config
+            .otlp_headers
+            .iter()
+            .map(|(key, value)| (key.clone(), value.expose().to_string()))
+            .collect()
+    }
+
+    pub fn grpc_metadata(&self) -> anyhow::Result<MetadataMap> {
+        let mut metadata = MetadataMap::new();
+        for (key, value) in &self.config.otlp_headers {
+            metadata.insert(
+                MetadataKey::from_bytes(key.to_lowercase().as_bytes())?,
+                MetadataValue::try_from(value.expose())?,
+            );
+        }
+        Ok(metadata)
+    }
+}
+
+/// Builds an OTLP exporter of type `$exporter` over the configured protocol.
+macro_rules! otlp_exporter {
+    ($exporter:ty, $settings:expr, $signal_path:literal) => {{
+        use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
+
+        let settings = &$settings;
+        match settings.config.otlp_protocol {
+            $crate::environment::OtlpProtocol::Grpc => <$exporter>::builder()
+                .with_tonic()
+                .with_endpoint(settings.endpoint)
+                .with_timeout(settings.config.otlp_timeout)
+                .with_metadata(settings.grpc_metadata()?)
+                .build()?,
+            $crate::environment::OtlpProtocol::HttpProtobuf => <$exporter>::builder()
+                .with_http()
+                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                .with_endpoint(settings.http_endpoint($signal_path))
+                .with_timeout(settings.config.otlp_timeout)
+                .with_headers(settings.http_headers())
+                .build()?,
+        }
+    }};
+}
+
+pub(super) use otlp_exporter;
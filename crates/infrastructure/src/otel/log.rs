@@ -0,0 +1,198 @@
+use std::fmt;
+
+use opentelemetry::trace::{SpanId, TraceContextExt, TraceId};
+use tracing::{Event, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::environment::LogFormat;
+
+/// Targets the OTLP exporters log through; bridging them back into OTLP
+/// would feed every export into the next one. Each also covers its
+/// submodules, but not other crates sharing its prefix, e.g. `tower_http`.
+const EXPORTER_TARGETS: [&str; 9] = [
+    "h2",
+    "hyper",
+    "hyper_util",
+    "opentelemetry",
+    "opentelemetry_sdk",
+    "opentelemetry_otlp",
+    "reqwest",
+    "tonic",
+    "tower::buffer",
+];
+
+/// Whether events of `target` may come from exporting over OTLP.
+pub fn is_exporter_target(target: &str) -> bool {
+    EXPORTER_TARGETS.iter().any(|exporter| {
+        target
+            .strip_prefix(exporter)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+/// Adds the OpenTelemetry trace and span ids of the current span to each
+/// event, so log lines can be joined with their trace.
+struct WithTraceIds<F> {
+    inner: F,
+    json: bool,
+}
+
+impl<F> WithTraceIds<F> {
+    fn text(inner: F) -> Self {
+        Self { inner, json: false }
+    }
+}
+
+fn trace_ids<S, N>(ctx: &FmtContext<'_, S, N>) -> Option<(TraceId, SpanId)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    let span = ctx.lookup_current()?;
+    let extensions = span.extensions();
+    let data = extensions.get::<OtelData>()?;
+    // Only root spans get a trace id of their own.
+    let trace_id = if data.parent_cx.has_active_span() {
+        data.parent_cx.span().span_context().trace_id()
+    } else {
+        data.builder.trace_id?
+    };
+    Some((trace_id, data.builder.span_id?))
+}
+
+impl<S, N, F> FormatEvent<S, N> for WithTraceIds<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+    F: FormatEvent<S, N>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let Some((trace_id, span_id)) = trace_ids(ctx) else {
+            return self.inner.format_event(ctx, writer, event);
+        };
+
+        if !self.json {
+            write!(writer, "trace_id={} span_id={} ", trace_id, span_id)?;
+            return self.inner.format_event(ctx, writer, event);
+        }
+
+        let mut line = String::new();
+        self.inner
+            .format_event(ctx, Writer::new(&mut line), event)?;
+        match line.strip_prefix('{') {
+            Some(rest) => write!(
+                writer,
+                r#"{{"trace_id":"{}","span_id":"{}",{}"#,
+                trace_id, span_id, rest
+            ),
+            None => writer.write_str(&line),
+        }
+    }
+}
+
+/// The log layer in the configured format.
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Full => layer.map_event_format(WithTraceIds::text).boxed(),
+        LogFormat::Pretty => layer.pretty().map_event_format(WithTraceIds::text).boxed(),
+        LogFormat::Compact => layer.compact().map_event_format(WithTraceIds::text).boxed(),
+        LogFormat::Json => layer
+            .json()
+            .map_event_format(|inner| WithTraceIds { inner, json: true })
+            .boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_exporter_targets_match_whole_path_segments() {
+        assert!(is_exporter_target("tonic"));
+        assert!(is_exporter_target("tonic::transport::channel"));
+        assert!(is_exporter_target("tower::buffer::worker"));
+        assert!(is_exporter_target("opentelemetry_sdk::logs"));
+
+        assert!(!is_exporter_target("tower_http::trace::on_response"));
+        assert!(!is_exporter_target("tower::limit"));
+        assert!(!is_exporter_target("tonic_web"));
+        assert!(!is_exporter_target("oxidize_infrastructure::otel"));
+    }
+
+    fn log_line(format: LogFormat) -> String {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let tracer = TracerProvider::default().tracer("test");
+        let subscriber = tracing_subscriber::registry()
+            .with(fmt_layer(format, move || writer.clone()))
+            .with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside");
+            let root = tracing::info_span!("root");
+            let _root = root.enter();
+            tracing::info_span!("child").in_scope(|| tracing::info!("inside"));
+        });
+
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_json_lines_carry_trace_ids() {
+        let output = log_line(LogFormat::Json);
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert!(lines[0].get("trace_id").is_none());
+        assert_eq!(lines[1]["fields"]["message"], "inside");
+        assert_eq!(lines[1]["trace_id"].as_str().unwrap().len(), 32);
+        assert_eq!(lines[1]["span_id"].as_str().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn test_text_lines_carry_trace_ids() {
+        let output = log_line(LogFormat::Compact);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(!lines[0].contains("trace_id="));
+        assert!(lines[1].starts_with("trace_id="));
+        assert!(lines[1].contains(" span_id="));
+    }
+}
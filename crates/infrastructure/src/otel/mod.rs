@@ -1,54 +1,186 @@
+mod exporter;
+mod log;
+mod propagation;
+mod sampler;
+mod span;
+
+use opentelemetry::propagation::TextMapCompositePropagator;
 use opentelemetry::trace::TracerProvider as _;
 use opentelemetry::{global, KeyValue};
-use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use opentelemetry_sdk::logs::LoggerProvider;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
 use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
-use crate::environment::Environment;
+use exporter::{otlp_exporter, OtlpSettings};
 
-pub fn init(env: &Environment) -> anyhow::Result<Option<TracerProvider>> {
-    let env_filter = EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into());
+use crate::environment::{redact_endpoint, Environment, TelemetryConfig};
+use crate::metrics;
 
-    if let Some(ref otlp_endpoint) = env.otlp_endpoint {
-        let exporter = opentelemetry_otlp::SpanExporter::builder()
-            .with_tonic()
-            .with_endpoint(otlp_endpoint)
-            .build()?;
+pub use propagation::inject;
+pub use span::{grpc_trace_layer, http_trace_layer, record_actor, rpc_name};
 
-        let resource = Resource::new(vec![KeyValue::new("service.name", "oxidize")]);
+const SERVICE_NAME: &str = "service.name";
+const SERVICE_VERSION: &str = "service.version";
+const DEPLOYMENT_ENVIRONMENT: &str = "deployment.environment";
 
-        let provider = TracerProvider::builder()
-            .with_batch_exporter(exporter, runtime::Tokio)
+/// Providers that must be flushed on exit.
+pub struct Telemetry {
+    tracer_provider: TracerProvider,
+    meter_provider: SdkMeterProvider,
+    logger_provider: Option<LoggerProvider>,
+}
+
+/// `service.name` and `service.version` defaults, overridden by
+/// `OTEL_RESOURCE_ATTRIBUTES`, overridden in turn by the dedicated settings.
+fn resource(config: &TelemetryConfig) -> Resource {
+    let mut attributes = vec![
+        KeyValue::new(SERVICE_NAME, "oxidize"),
+        KeyValue::new(SERVICE_VERSION, env!("CARGO_PKG_VERSION")),
+    ];
+    attributes.extend(
+        config
+            .resource_attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+    );
+    let overrides = [
+        (SERVICE_NAME, &config.service_name),
+        (SERVICE_VERSION, &config.service_version),
+        (DEPLOYMENT_ENVIRONMENT, &config.deployment_environment),
+    ];
+    for (key, value) in overrides {
+        if let Some(value) = value {
+            attributes.push(KeyValue::new(key, value.clone()));
+        }
+    }
+    // Later duplicates replace earlier ones.
+    Resource::new(attributes)
+}
+
+/// Metrics are always collected for `/metrics`, and also pushed over OTLP
+/// when an endpoint is configured.
+fn init_meter_provider(
+    otlp: Option<&OtlpSettings>,
+    resource: Resource,
+) -> anyhow::Result<SdkMeterProvider> {
+    let mut builder = SdkMeterProvider::builder()
+        .with_resource(resource)
+        .with_reader(metrics::prometheus_reader()?);
+
+    if let Some(otlp) = otlp {
+        let exporter = otlp_exporter!(opentelemetry_otlp::MetricExporter, otlp, "/v1/metrics");
+        builder = builder.with_reader(PeriodicReader::builder(exporter, runtime::Tokio).build());
+    }
+
+    let provider = builder.build();
+    global::set_meter_provider(provider.clone());
+    Ok(provider)
+}
+
+/// W3C `traceparent`/`tracestate` plus `baggage`.
+fn propagator() -> TextMapCompositePropagator {
+    TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ])
+}
+
+/// Spans are always recorded so logs carry trace ids and context is passed
+/// on to downstream calls; they are only exported when an endpoint is set.
+fn init_tracer_provider(
+    config: &TelemetryConfig,
+    otlp: Option<&OtlpSettings>,
+    resource: Resource,
+) -> anyhow::Result<TracerProvider> {
+    let mut builder = TracerProvider::builder()
+        .with_resource(resource)
+        .with_sampler(sampler::sampler(&config.sampler));
+
+    if let Some(otlp) = otlp {
+        let exporter = otlp_exporter!(opentelemetry_otlp::SpanExporter, otlp, "/v1/traces");
+        builder = builder.with_batch_exporter(exporter, runtime::Tokio);
+    }
+
+    let provider = builder.build();
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Logs are only exported when `OTLP_LOGS` is set next to an endpoint.
+fn init_logger_provider(
+    config: &TelemetryConfig,
+    otlp: Option<&OtlpSettings>,
+    resource: Resource,
+) -> anyhow::Result<Option<LoggerProvider>> {
+    let Some(otlp) = otlp.filter(|_| config.otlp_logs) else {
+        return Ok(None);
+    };
+
+    let exporter = otlp_exporter!(opentelemetry_otlp::LogExporter, otlp, "/v1/logs");
+    Ok(Some(
+        LoggerProvider::builder()
             .with_resource(resource)
-            .build();
-
-        global::set_tracer_provider(provider.clone());
-        let tracer = provider.tracer("oxidize");
-        let telemetry_layer = tracing_opentelemetry::layer().with_tracer(tracer);
-
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(tracing_subscriber::fmt::layer())
-            .with(telemetry_layer)
-            .init();
-
-        tracing::info!("OpenTelemetry initialized with endpoint: {}", otlp_endpoint);
-        Ok(Some(provider))
-    } else {
-        tracing_subscriber::registry()
-            .with(env_filter)
-            .with(tracing_subscriber::fmt::layer())
-            .init();
-
-        tracing::info!("OpenTelemetry disabled (OTLP_ENDPOINT not set)");
-        Ok(None)
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .build(),
+    ))
+}
+
+pub fn init(env: &Environment) -> anyhow::Result<Telemetry> {
+    let config = &env.telemetry;
+    global::set_text_map_propagator(propagator());
+    let env_filter = EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into());
+    let resource = resource(config);
+    let otlp = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| OtlpSettings { endpoint, config });
+    let meter_provider = init_meter_provider(otlp.as_ref(), resource.clone())?;
+    let tracer_provider = init_tracer_provider(config, otlp.as_ref(), resource.clone())?;
+    let logger_provider = init_logger_provider(config, otlp.as_ref(), resource)?;
+
+    let log_bridge = logger_provider.as_ref().map(|provider| {
+        OpenTelemetryTracingBridge::new(provider).with_filter(filter_fn(|metadata| {
+            !log::is_exporter_target(metadata.target())
+        }))
+    });
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(log::fmt_layer(config.log_format, std::io::stderr))
+        .with(tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("oxidize")))
+        .with(log_bridge)
+        .init();
+
+    match config.otlp_endpoint {
+        Some(ref otlp_endpoint) => tracing::info!(
+            "OpenTelemetry initialized with endpoint: {} ({:?})",
+            redact_endpoint(otlp_endpoint),
+            config.otlp_protocol
+        ),
+        None => tracing::info!("OTLP export disabled (OTLP_ENDPOINT not set)"),
     }
+
+    Ok(Telemetry {
+        tracer_provider,
+        meter_provider,
+        logger_provider,
+    })
 }
 
-pub fn shutdown(provider: Option<TracerProvider>) {
-    if let Some(provider) = provider {
+pub fn shutdown(telemetry: Telemetry) {
+    if let Err(e) = telemetry.tracer_provider.shutdown() {
+        tracing::error!("Failed to shutdown OpenTelemetry: {:?}", e);
+    }
+    if let Err(e) = telemetry.meter_provider.shutdown() {
+        tracing::error!("Failed to shutdown OpenTelemetry metrics: {:?}", e);
+    }
+    if let Some(provider) = telemetry.logger_provider {
         if let Err(e) = provider.shutdown() {
-            tracing::error!("Failed to shutdown OpenTelemetry: {:?}", e);
+            tracing::error!("Failed to shutdown OpenTelemetry logs: {:?}", e);
         }
     }
 }
@@ -0,0 +1,81 @@
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::global;
+use opentelemetry::propagation::{Extractor, Injector};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Reads propagation fields from HTTP headers or gRPC metadata, which tonic
+/// carries as HTTP/2 headers.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// Continues the trace and baggage of the caller (`traceparent`,
+/// `tracestate`, `baggage`) in `span`.
+pub fn set_parent(span: &Span, headers: &HeaderMap) {
+    let cx = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(headers)));
+    span.set_parent(cx);
+}
+
+/// Writes the current span's context into the headers of an outgoing call.
+pub fn inject(headers: &mut HeaderMap) {
+    let cx = Span::current().context();
+    global::get_text_map_propagator(|p| p.inject_context(&cx, &mut HeaderInjector(headers)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::baggage::BaggageExt;
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry::KeyValue;
+
+    use super::super::propagator;
+
+    #[test]
+    fn test_extract_and_inject_round_trip() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let mut incoming = HeaderMap::new();
+        incoming.insert("traceparent", HeaderValue::from_static(traceparent));
+        incoming.insert("baggage", HeaderValue::from_static("tenant_id=t1"));
+
+        let propagator = propagator();
+        let cx = propagator.extract(&HeaderExtractor(&incoming));
+        assert_eq!(
+            cx.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+        assert_eq!(
+            cx.baggage().get("tenant_id").map(|v| v.to_string()),
+            Some("t1".to_string())
+        );
+
+        let cx = cx.with_baggage(vec![KeyValue::new("actor", "svc")]);
+        let mut outgoing = HeaderMap::new();
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut outgoing));
+        assert_eq!(outgoing["traceparent"], traceparent);
+        assert!(outgoing["baggage"].to_str().unwrap().contains("actor=svc"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanKind, TraceContextExt, TraceId,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::trace::{Sampler, ShouldSample};
+
+use crate::environment::{SamplerConfig, TraceSampler};
+
+/// Samples at most `per_second` traces per second, with a burst of as many
+/// but at least one, so rates below 1/s still sample.
+#[derive(Clone, Debug)]
+pub struct RateLimitedSampler {
+    per_second: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimitedSampler {
+    pub fn new(per_second: f64) -> Self {
+        let burst = if per_second > 0.0 {
+            per_second.max(1.0)
+        } else {
+            0.0
+        };
+        Self {
+            per_second,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    fn acquire(&self, now: Instant) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.per_second).min(self.burst);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl ShouldSample for RateLimitedSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        _trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        let decision = if self.acquire(Instant::now()) {
+            SamplingDecision::RecordAndSample
+        } else {
+            SamplingDecision::Drop
+        };
+        SamplingResult {
+            decision,
+            attributes: Vec::new(),
+            trace_state: parent_context
+                .map(|cx| cx.span().span_context().trace_state().clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Either one of the SDK samplers or the rate limiter, so both can be
+/// wrapped in `Sampler::ParentBased` or used on their own.
+#[derive(Clone, Debug)]
+pub enum ConfiguredSampler {
+    Sdk(Sampler),
+    RateLimited(RateLimitedSampler),
+}
+
+impl ShouldSample for ConfiguredSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        name: &str,
+        span_kind: &SpanKind,
+        attributes: &[KeyValue],
+        links: &[Link],
+    ) -> SamplingResult {
+        match self {
+            Self::Sdk(sampler) => {
+                sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+            }
+            Self::RateLimited(sampler) => {
+                sampler.should_sample(parent_context, trace_id, name, span_kind, attributes, links)
+            }
+        }
+    }
+}
+
+pub fn sampler(config: &SamplerConfig) -> ConfiguredSampler {
+    let root = match config.sampler {
+        TraceSampler::AlwaysOn => ConfiguredSampler::Sdk(Sampler::AlwaysOn),
+        TraceSampler::AlwaysOff => ConfiguredSampler::Sdk(Sampler::AlwaysOff),
+        TraceSampler::Ratio(ratio) => ConfiguredSampler::Sdk(Sampler::TraceIdRatioBased(ratio)),
+        TraceSampler::RateLimited(per_second) => {
+            ConfiguredSampler::RateLimited(RateLimitedSampler::new(per_second))
+        }
+    };
+    if config.parent_based {
+        ConfiguredSampler::Sdk(Sampler::ParentBased(Box::new(root)))
+    } else {
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limited_sampler_refills() {
+        let sampler = RateLimitedSampler::new(2.0);
+        let start = Instant::now();
+
+        assert!(sampler.acquire(start));
+        assert!(sampler.acquire(start));
+        assert!(!sampler.acquire(start));
+        assert!(sampler.acquire(start + Duration::from_millis(500)));
+        assert!(!sampler.acquire(start + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_rate_limited_sampler_below_one_per_second() {
+        let sampler = RateLimitedSampler::new(0.5);
+        let start = Instant::now();
+
+        assert!(sampler.acquire(start));
+        assert!(!sampler.acquire(start + Duration::from_secs(1)));
+        assert!(sampler.acquire(start + Duration::from_secs(2)));
+        assert!(!sampler.acquire(start + Duration::from_secs(2)));
+
+        let sampler = RateLimitedSampler::new(0.0);
+        assert!(!sampler.acquire(start + Duration::from_secs(60)));
+    }
+}
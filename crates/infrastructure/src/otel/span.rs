@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::{HeaderMap, Response};
+use tower_http::classify::{
+    GrpcErrorsAsFailures, GrpcFailureClass, ServerErrorsAsFailures, SharedClassifier,
+};
+use tower_http::trace::{
+    DefaultOnBodyChunk, DefaultOnEos, DefaultOnFailure, DefaultOnRequest, DefaultOnResponse,
+    MakeSpan, OnFailure, OnResponse, TraceLayer,
+};
+use tracing::{Level, Span};
+
+use super::propagation::set_parent;
+use crate::grpc::ERROR_CODE_METADATA;
+use crate::metrics::ErrorCode;
+
+pub type HttpTraceLayer = TraceLayer<
+    SharedClassifier<ServerErrorsAsFailures>,
+    HttpMakeSpan,
+    DefaultOnRequest,
+    HttpOnResponse,
+>;
+
+pub type GrpcTraceLayer = TraceLayer<
+    SharedClassifier<GrpcErrorsAsFailures>,
+    GrpcMakeSpan,
+    DefaultOnRequest,
+    GrpcOnResponse,
+    DefaultOnBodyChunk,
+    DefaultOnEos,
+    GrpcOnFailure,
+>;
+
+/// Server spans for HTTP requests, continuing the caller's trace.
+pub fn http_trace_layer() -> HttpTraceLayer {
+    TraceLayer::new_for_http()
+        .make_span_with(HttpMakeSpan)
+        .on_response(HttpOnResponse)
+}
+
+/// Server spans for gRPC calls, continuing the caller's trace.
+pub fn grpc_trace_layer() -> GrpcTraceLayer {
+    TraceLayer::new_for_grpc()
+        .make_span_with(GrpcMakeSpan)
+        .on_response(GrpcOnResponse)
+        .on_failure(GrpcOnFailure)
+}
+
+/// Records who is calling on the request span. Must be called from within
+/// the request span, before any handler span is entered.
+pub fn record_actor(actor: &str) {
+    Span::current().record("actor", actor);
+}
+
+/// Splits `/package.Service/Method` into its service and method.
+pub fn rpc_name(path: &str) -> (&str, &str) {
+    path.trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or(("unknown", "unknown"))
+}
+
+fn error_code(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(ERROR_CODE_METADATA)
+        .and_then(|v| v.to_str().ok())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct HttpMakeSpan;
+
+impl<B> MakeSpan<B> for HttpMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str);
+        let span = tracing::info_span!(
+            "http_request",
+            http.method = %request.method(),
+            http.uri = %request.uri(),
+            http.version = ?request.version(),
+            http.route = route,
+            http.response.status_code = tracing::field::Empty,
+            error.code = tracing::field::Empty,
+            actor = tracing::field::Empty,
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+        );
+        set_parent(&span, request.headers());
+        span
+    }
+}
+
+/// Records the response status, marking 5xx responses as errors.
+#[derive(Clone, Copy, Debug)]
+pub struct HttpOnResponse;
+
+impl<B> OnResponse<B> for HttpOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        let status = response.status();
+        span.record("http.response.status_code", status.as_u16());
+        if let Some(ErrorCode(code)) = response.extensions().get() {
+            span.record("error.code", code.as_str());
+        }
+        if status.is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        DefaultOnResponse::new()
+            .level(Level::INFO)
+            .on_response(response, latency, span);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GrpcMakeSpan;
+
+impl<B> MakeSpan<B> for GrpcMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let (service, method) = rpc_name(request.uri().path());
+        let span = tracing::info_span!(
+            "grpc_request",
+            otel.name = %format_args!("{}/{}", service, method),
+            rpc.system = "grpc",
+            rpc.service = service,
+            rpc.method = method,
+            rpc.grpc.status_code = tracing::field::Empty,
+            error.code = tracing::field::Empty,
+            actor = tracing::field::Empty,
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+        );
+        set_parent(&span, request.headers());
+        span
+    }
+}
+
+/// Records the error code of failed unary calls, which are trailers-only and
+/// so carry their metadata in the headers.
+#[derive(Clone, Copy, Debug)]
+pub struct GrpcOnResponse;
+
+impl<B> OnResponse<B> for GrpcOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if let Some(code) = error_code(response.headers()) {
+            span.record("error.code", code);
+        }
+        if response.headers().get("grpc-status").is_none() {
+            span.record("rpc.grpc.status_code", 0);
+        }
+        DefaultOnResponse::new().on_response(response, latency, span);
+    }
+}
+
+/// gRPC codes that mean the server failed rather than the caller.
+fn is_server_error(code: i32) -> bool {
+    matches!(code, 2 | 4 | 12 | 13 | 14 | 15)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GrpcOnFailure;
+
+impl OnFailure<GrpcFailureClass> for GrpcOnFailure {
+    fn on_failure(&mut self, failure: GrpcFailureClass, latency: Duration, span: &Span) {
+        let server_error = match &failure {
+            GrpcFailureClass::Code(code) => {
+                span.record("rpc.grpc.status_code", code.get());
+                is_server_error(code.get())
+            }
+            GrpcFailureClass::Error(_) => true,
+        };
+        // Client errors leave the span status unset, as for HTTP 4xx.
+        if server_error {
+            span.record("otel.status_code", "ERROR");
+            DefaultOnFailure::new().on_failure(failure, latency, span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_name() {
+        assert_eq!(
+            rpc_name("/tenant.TenantService/GetTenant"),
+            ("tenant.TenantService", "GetTenant")
+        );
+        assert_eq!(rpc_name("/"), ("unknown", "unknown"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderValue};
+
+use crate::environment::LimitConfig;
+
+/// A token bucket is kept as the time until it is full again, `full_in`:
+/// taking a token adds one refill interval, and is refused if that would put
+/// `full_in` beyond `per`. An empty (never used) bucket is full.
+fn interval(limit: LimitConfig) -> Duration {
+    limit.per / limit.requests
+}
+
+/// The outcome of taking a token, as reported in the `RateLimit-*` headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: LimitConfig,
+    pub remaining: u32,
+    /// Until the bucket is full again.
+    pub reset: Duration,
+    /// Until a token is available again; zero when allowed.
+    pub retry_after: Duration,
+}
+
+impl Decision {
+    /// A token was taken, leaving the bucket full in `full_in`.
+    pub fn allowed(limit: LimitConfig, full_in: Duration) -> Self {
+        let left = limit.per.saturating_sub(full_in);
+        Self {
+            allowed: true,
+            limit,
+            remaining: (left.as_nanos() / interval(limit).as_nanos()) as u32,
+            reset: full_in,
+            retry_after: Duration::ZERO,
+        }
+    }
+
+    /// No token was left in a bucket full in `full_in`.
+    pub fn denied(limit: LimitConfig, full_in: Duration) -> Self {
+        Self {
+            allowed: false,
+            limit,
+            remaining: 0,
+            reset: full_in,
+            retry_after: (full_in + interval(limit)).saturating_sub(limit.per),
+        }
+    }
+
+    /// Sets `RateLimit-Limit`, `-Remaining`, `-Reset` and `-Policy` as in the
+    /// IETF RateLimit header fields draft, plus `Retry-After` when denied.
+    pub fn write_headers(&self, headers: &mut HeaderMap) {
+        headers.insert("ratelimit-limit", self.limit.requests.into());
+        headers.insert("ratelimit-remaining", self.remaining.into());
+        headers.insert("ratelimit-reset", ceil_secs(self.reset).into());
+        let policy = format!("{};w={}", self.limit.requests, self.limit.per.as_secs());
+        if let Ok(policy) = HeaderValue::from_str(&policy) {
+            headers.insert("ratelimit-policy", policy);
+        }
+        if !self.allowed {
+            headers.insert(RETRY_AFTER, ceil_secs(self.retry_after).into());
+        }
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Takes a token from a bucket that is full in `full_in`, returning the
+/// decision and, if a token was taken, the bucket's new `full_in`.
+pub fn take(limit: LimitConfig, full_in: Duration) -> (Decision, Option<Duration>) {
+    let after = full_in + interval(limit);
+    if after <= limit.per {
+        (Decision::allowed(limit, after), Some(after))
+    } else {
+        (Decision::denied(limit, full_in), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: LimitConfig = LimitConfig {
+        requests: 2,
+        per: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn test_take_until_empty() {
+        let (first, full_in) = take(LIMIT, Duration::ZERO);
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        assert_eq!(first.reset, Duration::from_secs(30));
+
+        let (second, full_in) = take(LIMIT, full_in.unwrap());
+        assert!(second.allowed);
+        assert_eq!(second.remaining, 0);
+
+        let (third, unchanged) = take(LIMIT, full_in.unwrap());
+        assert!(!third.allowed);
+        assert_eq!(unchanged, None);
+        assert_eq!(third.retry_after, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_refills_over_time() {
+        // Full in 45s: 15s have passed since the bucket was emptied.
+        let (decision, _) = take(LIMIT, Duration::from_secs(45));
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_secs(15));
+
+        let (decision, _) = take(LIMIT, Duration::from_secs(30));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+    }
+
+    #[test]
+    fn test_write_headers() {
+        let mut headers = HeaderMap::new();
+        Decision::denied(LIMIT, Duration::from_millis(59_500)).write_headers(&mut headers);
+        assert_eq!(headers["ratelimit-limit"], "2");
+        assert_eq!(headers["ratelimit-remaining"], "0");
+        assert_eq!(headers["ratelimit-reset"], "60");
+        assert_eq!(headers["ratelimit-policy"], "2;w=60");
+        assert_eq!(headers["retry-after"], "30");
+    }
+}
@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::{Request, Response};
+use axum::BoxError;
+use tower::{Layer, Service};
+
+use oxidize_domain::errors;
+
+use super::{Decision, RateLimiter};
+use crate::serve::refuse;
+
+/// Applies a `RateLimiter`, or passes everything through without one.
+/// Responses carry the `RateLimit-*` headers of the bucket they drew from.
+#[derive(Clone, Default)]
+pub struct RateLimitLayer {
+    limiter: Option<Arc<RateLimiter>>,
+    per_ip: bool,
+}
+
+impl RateLimitLayer {
+    /// Limits per client and route; goes inside `AuthLayer`.
+    pub fn new(limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self {
+            limiter,
+            per_ip: false,
+        }
+    }
+
+    /// Limits per IP address; goes outside `AuthLayer`. Only refusals carry
+    /// its headers, leaving the others to the per-client limit.
+    pub fn per_ip(limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self {
+            limiter,
+            per_ip: true,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
+            per_ip: self.per_ip,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Option<Arc<RateLimiter>>,
+    per_ip: bool,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimit<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // The clone may not be ready; call the one that is.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let per_ip = self.per_ip;
+        let plan = self.limiter.as_ref().and_then(|limiter| {
+            let plan = if per_ip {
+                limiter.plan_per_ip(&request)
+            } else {
+                limiter.plan(&request)
+            };
+            Some((limiter.clone(), plan?))
+        });
+
+        Box::pin(async move {
+            let decision: Option<Decision> = match plan {
+                Some((limiter, plan)) => limiter.take(plan).await,
+                None => None,
+            };
+            let mut response = match decision {
+                Some(decision) if !decision.allowed => refuse(&request, errors::rate_limited()),
+                _ => inner.call(request).await?.map(Body::new),
+            };
+            if let Some(decision) = decision.filter(|d| !per_ip || !d.allowed) {
+                decision.write_headers(response.headers_mut());
+            }
+            Ok(response)
+        })
+    }
+}
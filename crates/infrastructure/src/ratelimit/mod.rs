@@ -0,0 +1,469 @@
+//! Token-bucket rate limiting for both servers, per client and route.
+//!
+//! See `RateLimitConfig` for how clients are identified and which limit
+//! applies. Runs inside `AuthLayer`, so only credentials that authenticated
+//! pick a bucket; a second, per-IP limit runs outside it, so that failed
+//! authentications are limited too. Health checks, metrics and reflection
+//! are never limited.
+
+mod bucket;
+mod layer;
+mod store;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::ConnectInfo;
+use axum::http::{Method, Request};
+use opentelemetry::KeyValue;
+use sqlx::PgPool;
+use tonic::transport::server::TcpConnectInfo;
+
+use crate::auth::Actor;
+use crate::environment::{
+    LimitConfig, RateLimitConfig, RateLimitStore, RouteLimitConfig, TenantLimitConfig,
+};
+use crate::metrics::metrics;
+
+pub use bucket::Decision;
+pub use layer::{RateLimit, RateLimitLayer};
+pub use store::{BucketStore, MemoryStore, PostgresStore};
+
+/// Paths that must stay reachable however busy a client is.
+const EXEMPT_PREFIXES: [&str; 4] = [
+    "/health",
+    "/metrics",
+    "/grpc.health.v1.Health/",
+    "/grpc.reflection.",
+];
+
+/// Who a bucket belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Client {
+    /// API keys, all of a tenant's sharing its limit.
+    Tenant(String),
+    /// By the `sub` of their token.
+    User(String),
+    /// Anonymous calls only.
+    Ip(Option<IpAddr>),
+}
+
+impl Client {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Tenant(_) => "tenant",
+            Self::User(_) => "user",
+            Self::Ip(_) => "ip",
+        }
+    }
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tenant(id) => write!(f, "tenant:{}", id),
+            Self::User(subject) => write!(f, "user:{}", subject),
+            Self::Ip(Some(ip)) => write!(f, "ip:{}", ip),
+            Self::Ip(None) => f.write_str("ip:unknown"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Route {
+    pattern: String,
+    method: Option<Method>,
+    path: String,
+    prefix: bool,
+    limit: LimitConfig,
+}
+
+impl Route {
+    fn new(config: &RouteLimitConfig) -> Self {
+        let (method, path) = match config.route.split_once(' ') {
+            Some((method, path)) => (method.parse().ok(), path),
+            None => (None, config.route.as_str()),
+        };
+        let (path, prefix) = match path.strip_suffix('*') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        Self {
+            pattern: config.route.clone(),
+            method,
+            path: path.to_string(),
+            prefix,
+            limit: LimitConfig {
+                requests: config.requests,
+                per: config.per,
+            },
+        }
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method)
+            && if self.prefix {
+                path.starts_with(&self.path)
+            } else {
+                path == self.path
+            }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+    routes: Vec<Route>,
+    default: Option<LimitConfig>,
+}
+
+impl Rules {
+    fn new(routes: &[RouteLimitConfig], default: Option<LimitConfig>) -> Self {
+        Self {
+            routes: routes.iter().map(Route::new).collect(),
+            default,
+        }
+    }
+
+    fn route(&self, method: &Method, path: &str) -> Option<(&str, LimitConfig)> {
+        self.routes
+            .iter()
+            .find(|r| r.matches(method, path))
+            .map(|r| (r.pattern.as_str(), r.limit))
+    }
+}
+
+/// The bucket a request draws from.
+#[derive(Debug)]
+pub struct Plan {
+    key: String,
+    client: &'static str,
+    rule: String,
+    limit: LimitConfig,
+}
+
+pub struct RateLimiter {
+    per_ip: LimitConfig,
+    global: Rules,
+    tenants: HashMap<String, Rules>,
+    store: Box<dyn BucketStore>,
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig, store: Box<dyn BucketStore>) -> Self {
+        let tenants = config
+            .tenants
+            .iter()
+            .map(|(id, TenantLimitConfig { default, routes })| {
+                (id.clone(), Rules::new(routes, *default))
+            })
+            .collect();
+        Self {
+            per_ip: config.per_ip,
+            global: Rules::new(&config.routes, Some(config.default)),
+            tenants,
+            store,
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+
+    /// The limiter `config` asks for, if enabled; `pool` backs the
+    /// Postgres store.
+    pub fn from_config(config: &RateLimitConfig, pool: &PgPool) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+        let store: Box<dyn BucketStore> = match config.store {
+            RateLimitStore::Memory => Box::<MemoryStore>::default(),
+            RateLimitStore::Postgres => Box::new(PostgresStore::new(pool.clone())),
+        };
+        Some(Arc::new(Self::new(config, store)))
+    }
+
+    fn client<B>(&self, request: &Request<B>) -> Client {
+        match request.extensions().get::<Actor>() {
+            Some(Actor::Service { tenant_id, .. }) => {
+                return Client::Tenant(tenant_id.as_str().to_string())
+            }
+            Some(Actor::User { subject }) => return Client::User(subject.clone()),
+            None => {}
+        }
+        Client::Ip(self.ip(request))
+    }
+
+    fn ip<B>(&self, request: &Request<B>) -> Option<IpAddr> {
+        let headers = request.headers();
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for")?.to_str().ok())
+            .flatten()
+            .and_then(|v| v.split(',').next()?.trim().parse().ok());
+        let peer = || {
+            let extensions = request.extensions();
+            extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|info| info.0)
+                .or_else(|| extensions.get::<TcpConnectInfo>()?.remote_addr())
+                .map(|addr| addr.ip())
+        };
+        forwarded.or_else(peer)
+    }
+
+    fn exempt<B>(request: &Request<B>) -> bool {
+        let path = request.uri().path();
+        EXEMPT_PREFIXES.iter().any(|p| path.starts_with(p))
+    }
+
+    /// The per-IP bucket `request` draws from before it is authenticated,
+    /// or `None` if it is not limited.
+    pub fn plan_per_ip<B>(&self, request: &Request<B>) -> Option<Plan> {
+        if Self::exempt(request) || self.per_ip.requests == 0 {
+            return None;
+        }
+        let client = Client::Ip(self.ip(request));
+        Some(Plan {
+            key: format!("{}|per_ip", client),
+            client: client.kind(),
+            rule: "per_ip".to_string(),
+            limit: self.per_ip,
+        })
+    }
+
+    /// The bucket `request` draws from, or `None` if it is not limited.
+    pub fn plan<B>(&self, request: &Request<B>) -> Option<Plan> {
+        if Self::exempt(request) {
+            return None;
+        }
+
+        let path = request.uri().path();
+        let method = request.method();
+        let tenant = match request.extensions().get::<Actor>() {
+            Some(Actor::Service { tenant_id, .. }) => self.tenants.get(tenant_id.as_str()),
+            _ => None,
+        };
+        let (rule, limit) = tenant
+            .and_then(|t| t.route(method, path))
+            .or_else(|| self.global.route(method, path))
+            .or_else(|| tenant.and_then(|t| t.default).map(|l| ("default", l)))
+            .or_else(|| self.global.default.map(|l| ("default", l)))?;
+        if limit.requests == 0 {
+            return None;
+        }
+
+        let client = self.client(request);
+        Some(Plan {
+            key: format!("{}|{}", client, rule),
+            client: client.kind(),
+            rule: rule.to_string(),
+            limit,
+        })
+    }
+
+    /// Takes a token for `plan`. Lets the request through, without a
+    /// decision, if the store is unavailable.
+    pub async fn take(&self, plan: Plan) -> Option<Decision> {
+        match self.store.take(&plan.key, plan.limit).await {
+            Ok(decision) => {
+                if !decision.allowed {
+                    let attributes = [
+                        KeyValue::new("rate_limit.client", plan.client),
+                        KeyValue::new("rate_limit.rule", plan.rule),
+                    ];
+                    metrics().rate_limited.add(1, &attributes);
+                }
+                Some(decision)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Rate limit store unavailable; not limiting");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use oxidize_domain::{ApiKeyId, TenantId};
+
+    use super::*;
+
+    fn limit(requests: u32) -> LimitConfig {
+        LimitConfig {
+            requests,
+            per: Duration::from_secs(60),
+        }
+    }
+
+    fn route(route: &str, requests: u32) -> RouteLimitConfig {
+        RouteLimitConfig {
+            route: route.to_string(),
+            requests,
+            per: Duration::from_secs(60),
+        }
+    }
+
+    fn limiter() -> RateLimiter {
+        let config = RateLimitConfig {
+            enabled: true,
+            default: limit(100),
+            routes: vec![
+                route("POST /v1/tenants", 10),
+                route("/tenant.TenantService/*", 20),
+            ],
+            tenants: BTreeMap::from([(
+                "big".to_string(),
+                TenantLimitConfig {
+                    default: Some(limit(1000)),
+                    routes: vec![route("POST /v1/tenants", 0)],
+                },
+            )]),
+            ..Default::default()
+        };
+        RateLimiter::new(&config, Box::<MemoryStore>::default())
+    }
+
+    /// A request from an API key of `tenant`, if given.
+    fn request(method: &str, path: &str, tenant: Option<&str>) -> Request<()> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(())
+            .unwrap();
+        if let Some(tenant) = tenant {
+            request.extensions_mut().insert(Actor::Service {
+                key_id: ApiKeyId::from_string(format!("{}-key", tenant)),
+                tenant_id: TenantId::from_string(tenant.to_string()),
+                scopes: Vec::new(),
+            });
+        }
+        request
+    }
+
+    #[test]
+    fn test_plan_picks_the_first_matching_rule() {
+        let limiter = limiter();
+
+        let plan = limiter.plan(&request("POST", "/v1/tenants", None)).unwrap();
+        assert_eq!(
+            (plan.rule.as_str(), plan.limit.requests),
+            ("POST /v1/tenants", 10)
+        );
+        assert_eq!(plan.key, "ip:unknown|POST /v1/tenants");
+
+        let plan = limiter
+            .plan(&request("POST", "/tenant.TenantService/GetTenant", None))
+            .unwrap();
+        assert_eq!(plan.limit.requests, 20);
+
+        let plan = limiter.plan(&request("GET", "/v1/tenants", None)).unwrap();
+        assert_eq!((plan.rule.as_str(), plan.limit.requests), ("default", 100));
+
+        // The tenant's own routes, then the global ones, then its default.
+        assert!(limiter
+            .plan(&request("POST", "/v1/tenants", Some("big")))
+            .is_none());
+        let plan = limiter
+            .plan(&request(
+                "POST",
+                "/tenant.TenantService/GetTenant",
+                Some("big"),
+            ))
+            .unwrap();
+        assert_eq!(plan.limit.requests, 20);
+        let plan = limiter
+            .plan(&request("GET", "/v1/staffs", Some("big")))
+            .unwrap();
+        assert_eq!(plan.limit.requests, 1000);
+        assert_eq!(plan.key, "tenant:big|default");
+
+        assert!(limiter
+            .plan(&request("GET", "/health/ready", None))
+            .is_none());
+    }
+
+    #[test]
+    fn test_clients_are_told_apart_by_their_actor() {
+        let limiter = limiter();
+
+        let plan = limiter
+            .plan(&request("GET", "/v1/tenants", Some("t1")))
+            .unwrap();
+        assert_eq!(
+            (plan.client, plan.key.as_str()),
+            ("tenant", "tenant:t1|default")
+        );
+
+        // Every key of a tenant draws from the same bucket.
+        let mut other_key = request("GET", "/v1/tenants", None);
+        other_key.extensions_mut().insert(Actor::Service {
+            key_id: ApiKeyId::from_string("t1-other-key".to_string()),
+            tenant_id: TenantId::from_string("t1".to_string()),
+            scopes: Vec::new(),
+        });
+        assert_eq!(limiter.plan(&other_key).unwrap().key, plan.key);
+
+        let mut user = request("GET", "/v1/tenants", None);
+        user.extensions_mut().insert(Actor::User {
+            subject: "alice".to_string(),
+        });
+        let plan = limiter.plan(&user).unwrap();
+        assert_eq!(
+            (plan.client, plan.key.as_str()),
+            ("user", "user:alice|default")
+        );
+
+        // Unverified credentials and headers pick neither bucket nor limits.
+        let forged = Request::builder()
+            .uri("/v1/tenants")
+            .header("authorization", "ApiKey forged")
+            .header("x-tenant-id", "big")
+            .body(())
+            .unwrap();
+        let plan = limiter.plan(&forged).unwrap();
+        assert_eq!((plan.client, plan.limit.requests), ("ip", 100));
+        assert_eq!(plan.key, "ip:unknown|default");
+    }
+
+    #[test]
+    fn test_per_ip_plan_ignores_the_actor() {
+        let limiter = limiter();
+
+        let plan = limiter
+            .plan_per_ip(&request("GET", "/v1/tenants", Some("big")))
+            .unwrap();
+        assert_eq!(
+            (plan.client, plan.key.as_str(), plan.limit.requests),
+            ("ip", "ip:unknown|per_ip", 1000)
+        );
+        assert!(limiter
+            .plan_per_ip(&request("GET", "/health/ready", None))
+            .is_none());
+
+        let config = RateLimitConfig {
+            enabled: true,
+            per_ip: limit(0),
+            ..Default::default()
+        };
+        let limiter = RateLimiter::new(&config, Box::<MemoryStore>::default());
+        assert!(limiter
+            .plan_per_ip(&request("GET", "/v1/tenants", None))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_take_refuses_when_empty() {
+        let limiter = limiter();
+        let request = request("POST", "/v1/tenants", None);
+        for _ in 0..10 {
+            let decision = limiter.take(limiter.plan(&request).unwrap()).await;
+            assert!(decision.unwrap().allowed);
+        }
+        let decision = limiter.take(limiter.plan(&request).unwrap()).await;
+        assert!(!decision.unwrap().allowed);
+    }
+}
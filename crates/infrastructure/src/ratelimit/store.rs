@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::bucket::{self, Decision};
+use crate::environment::LimitConfig;
+
+/// Buckets that have refilled are dropped every this many takes.
+const PRUNE_EVERY: u64 = 1024;
+
+/// Where buckets are kept.
+#[async_trait]
+pub trait BucketStore: Send + Sync {
+    /// Takes a token from the bucket `key`.
+    async fn take(&self, key: &str, limit: LimitConfig) -> anyhow::Result<Decision>;
+}
+
+/// Buckets in this process, by the instant each is full again.
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Instant>>,
+    takes: AtomicU64,
+}
+
+#[async_trait]
+impl BucketStore for MemoryStore {
+    async fn take(&self, key: &str, limit: LimitConfig) -> anyhow::Result<Decision> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if self
+            .takes
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(PRUNE_EVERY)
+        {
+            buckets.retain(|_, full_at| *full_at > now);
+        }
+
+        let full_in = buckets
+            .get(key)
+            .map(|full_at| full_at.saturating_duration_since(now))
+            .unwrap_or_default();
+        let (decision, full_in) = bucket::take(limit, full_in);
+        if let Some(full_in) = full_in {
+            buckets.insert(key.to_string(), now + full_in);
+        }
+        Ok(decision)
+    }
+}
+
+/// Buckets in `rate_limit_buckets`, shared by every replica. Each take is a
+/// single upsert that only moves `full_at` if a token is left, timed by the
+/// database clock.
+pub struct PostgresStore {
+    pool: PgPool,
+    takes: AtomicU64,
+}
+
+impl PostgresStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            takes: AtomicU64::new(0),
+        }
+    }
+
+    fn prune(&self) {
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            if let Err(e) = sqlx::query("DELETE FROM rate_limit_buckets WHERE full_at < now()")
+                .execute(&pool)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to prune rate limit buckets");
+            }
+        });
+    }
+}
+
+fn until(full_at: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (full_at - now).to_std().unwrap_or_default()
+}
+
+#[async_trait]
+impl BucketStore for PostgresStore {
+    async fn take(&self, key: &str, limit: LimitConfig) -> anyhow::Result<Decision> {
+        if self
+            .takes
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(PRUNE_EVERY)
+        {
+            self.prune();
+        }
+
+        let interval = (limit.per / limit.requests).as_secs_f64();
+        let taken: Option<(DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            INSERT INTO rate_limit_buckets AS b (key, full_at)
+            VALUES ($1, now() + make_interval(secs => $2))
+            ON CONFLICT (key) DO UPDATE
+            SET full_at = GREATEST(b.full_at, now()) + make_interval(secs => $2)
+            WHERE GREATEST(b.full_at, now()) + make_interval(secs => $2)
+                <= now() + make_interval(secs => $3)
+            RETURNING full_at, now()
+            "#,
+        )
+        .bind(key)
+        .bind(interval)
+        .bind(limit.per.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some((full_at, now)) = taken {
+            return Ok(Decision::allowed(limit, until(full_at, now)));
+        }
+
+        let (full_at, now): (DateTime<Utc>, DateTime<Utc>) =
+            sqlx::query_as("SELECT full_at, now() FROM rate_limit_buckets WHERE key = $1")
+                .bind(key)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(Decision::denied(limit, until(full_at, now)))
+    }
+}
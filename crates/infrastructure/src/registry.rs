@@ -1,24 +1,77 @@
 use std::sync::Arc;
 
-use oxidize_usecase::{StaffInteractor, TenantInteractor};
+use oxidize_usecase::{
+    ApiKeyInteractor, ArchiveInteractor, Idempotency, StaffInteractor, TenantInteractor,
+};
 
-use crate::database::{create_pool, StaffRepositoryImpl, TenantRepositoryImpl};
+use crate::auth::Authenticator;
+use crate::cache::{CachedStaffRepository, CachedTenantRepository};
+use crate::database::{
+    ApiKeyRepositoryImpl, IdempotencyRepositoryImpl, Pools, StaffRepositoryImpl,
+    TenantRepositoryImpl,
+};
+use crate::environment::{Environment, TenantConfig};
+use crate::metrics;
+use crate::ratelimit::RateLimiter;
+
+/// The repositories behind the interactors; they cache when `cache.enabled`.
+type TenantRepo = CachedTenantRepository<TenantRepositoryImpl>;
+type StaffRepo = CachedStaffRepository<StaffRepositoryImpl>;
 
 pub struct Registry {
-    pub tenant_interactor: TenantInteractor<TenantRepositoryImpl>,
-    pub staff_interactor: StaffInteractor<StaffRepositoryImpl>,
+    pub tenant_interactor: TenantInteractor<TenantRepo, IdempotencyRepositoryImpl>,
+    pub staff_interactor: StaffInteractor<StaffRepo, TenantRepo, IdempotencyRepositoryImpl>,
+    pub archive_interactor: ArchiveInteractor<TenantRepo, StaffRepo>,
+    pub api_key_interactor: ApiKeyInteractor<ApiKeyRepositoryImpl, TenantRepo>,
+    pub authenticator: Authenticator,
+    pub tenant_config: TenantConfig,
+    /// `None` unless `rate_limit.enabled`.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub pools: Pools,
 }
 
 impl Registry {
-    pub async fn new(database_url: &str) -> anyhow::Result<Arc<Self>> {
-        let pool = create_pool(database_url).await?;
+    pub async fn new(env: &Environment) -> anyhow::Result<Arc<Self>> {
+        let pools = Pools::connect(&env.database).await?;
+        metrics::observe_pool(pools.primary(), "primary");
+        if let Some(replica) = pools.replica() {
+            metrics::observe_pool(replica, "replica");
+        }
 
-        let tenant_repo = Arc::new(TenantRepositoryImpl::new(pool.clone()));
-        let staff_repo = Arc::new(StaffRepositoryImpl::new(pool));
+        let staff_repo = Arc::new(CachedStaffRepository::new(
+            StaffRepositoryImpl::new(pools.clone()),
+            &env.cache,
+        ));
+        let tenant_repo = Arc::new(CachedTenantRepository::new(
+            TenantRepositoryImpl::new(pools.clone()),
+            &env.cache,
+            staff_repo.caches(),
+        ));
+        let api_key_repo = Arc::new(ApiKeyRepositoryImpl::new(pools.clone()));
+        let idempotency = Arc::new(Idempotency::new(
+            Arc::new(IdempotencyRepositoryImpl::new(pools.clone())),
+            chrono::Duration::from_std(env.idempotency.ttl)?,
+            chrono::Duration::from_std(env.idempotency.lease)?,
+        ));
 
         Ok(Arc::new(Self {
-            tenant_interactor: TenantInteractor::new(tenant_repo),
-            staff_interactor: StaffInteractor::new(staff_repo),
+            tenant_interactor: TenantInteractor::new(tenant_repo.clone(), idempotency.clone()),
+            staff_interactor: StaffInteractor::new(
+                staff_repo.clone(),
+                tenant_repo.clone(),
+                idempotency,
+            ),
+            archive_interactor: ArchiveInteractor::new(tenant_repo.clone(), staff_repo),
+            api_key_interactor: ApiKeyInteractor::new(api_key_repo, tenant_repo),
+            authenticator: Authenticator::new(&env.auth),
+            tenant_config: env.tenants.clone(),
+            rate_limiter: RateLimiter::from_config(&env.rate_limit, pools.primary()),
+            pools,
         }))
     }
+
+    /// Closes the connection pools, waiting for checked-out connections to return.
+    pub async fn close(&self) {
+        self.pools.close().await;
+    }
 }
@@ -0,0 +1,224 @@
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::ensure;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use oxidize_domain::{Result, Tenant, TenantArchiveRef, TenantId};
+use oxidize_usecase::{CloseTenantInput, ExportTenantInput};
+
+use crate::archive::{ArchiveFormat, TenantArchive};
+use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+
+/// Closes a tenant, refusing access to its staff at once. It is archived in
+/// the background (see [`spawn_archiver`]) and purged after
+/// `retention_days`, or `tenants.retention_days` if unset.
+pub async fn close_tenant(
+    registry: &Registry,
+    id: TenantId,
+    retention_days: Option<u32>,
+) -> Result<Tenant> {
+    let days = retention_days.unwrap_or(registry.tenant_config.retention_days);
+    let input = CloseTenantInput {
+        id,
+        retention: chrono::Duration::days(days.into()),
+    };
+    let tenant = registry.tenant_interactor.close(input).await?;
+    tracing::info!(
+        tenant_id = tenant.id.as_str(),
+        "Closed tenant; purging after {:?}",
+        tenant.purge_after
+    );
+    Ok(tenant)
+}
+
+/// Writes the archive of the closed tenant `id` to `tenants.archive_dir` and
+/// records its location and checksum on the tenant. Returns `None` if the
+/// tenant was reopened, or archived by another replica, meanwhile.
+pub async fn archive_tenant(
+    registry: &Registry,
+    id: &TenantId,
+) -> anyhow::Result<Option<TenantArchiveRef>> {
+    let input = ExportTenantInput { id: id.clone() };
+    let exported = registry.archive_interactor.export_tenant(input).await?;
+    let mut tenant = exported.tenant.clone();
+    if !tenant.is_closed() || tenant.archive.is_some() {
+        return Ok(None);
+    }
+
+    let archive = TenantArchive::new(exported.tenant, exported.staff);
+    let dir = registry.tenant_config.archive_dir.clone();
+    // Unique, so replicas archiving the same tenant never write one file.
+    let name = format!(
+        "{}-{}-{}.json",
+        id.as_str(),
+        archive.exported_at.format("%Y%m%dT%H%M%SZ"),
+        &Uuid::new_v4().simple().to_string()[..8]
+    );
+    let (path, checksum) =
+        tokio::task::spawn_blocking(move || write_file(&dir, &name, &archive)).await??;
+
+    let archive = TenantArchiveRef {
+        location: path.display().to_string(),
+        checksum,
+        archived_at: Utc::now(),
+    };
+    tenant.archive = Some(archive.clone());
+    if !registry.tenant_interactor.record_archive(&tenant).await? {
+        fs::remove_file(&path)?;
+        return Ok(None);
+    }
+    Ok(Some(archive))
+}
+
+/// Writes under a temporary name first, so a crash never leaves a
+/// truncated archive under the final one. Returns the absolute path and the
+/// SHA-256 of the archive.
+fn write_file(
+    dir: &Path,
+    name: &str,
+    archive: &TenantArchive,
+) -> anyhow::Result<(PathBuf, String)> {
+    let mut bytes = Vec::new();
+    archive.write(ArchiveFormat::Json, &mut bytes)?;
+    let checksum = hex::encode(Sha256::digest(&bytes));
+
+    fs::create_dir_all(dir)?;
+    let partial = dir.join(format!("{}.partial", name));
+    let mut file = File::create(&partial)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+
+    let path = std::path::absolute(dir.join(name))?;
+    fs::rename(&partial, &path)?;
+    Ok((path, checksum))
+}
+
+/// Checks that `archive` can still be read back as it was written.
+fn verify_archive(archive: &TenantArchiveRef) -> anyhow::Result<()> {
+    let bytes = fs::read(&archive.location)?;
+    let checksum = hex::encode(Sha256::digest(&bytes));
+    ensure!(checksum == archive.checksum, "checksum mismatch");
+    Ok(())
+}
+
+/// Archives the closed tenants that have no archive yet, returning how many
+/// were. A tenant that fails is retried on the next run.
+pub async fn archive_tenants(registry: &Registry) -> Result<usize> {
+    let mut archived = 0;
+    for tenant in registry.tenant_interactor.unarchived().await? {
+        match archive_tenant(registry, &tenant.id).await {
+            Ok(Some(archive)) => {
+                tracing::info!(
+                    tenant_id = tenant.id.as_str(),
+                    archive = archive.location,
+                    "Archived closed tenant"
+                );
+                archived += 1;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                tenant_id = tenant.id.as_str(),
+                error = %e,
+                "Failed to archive closed tenant"
+            ),
+        }
+    }
+    Ok(archived)
+}
+
+/// Deletes the closed tenants whose retention period has passed, each only
+/// once its archive reads back intact from this node.
+pub async fn purge_tenants(registry: &Registry) -> Result<Vec<TenantId>> {
+    let now = Utc::now();
+    let mut purged = Vec::new();
+    for tenant in registry.tenant_interactor.purgeable(now).await? {
+        let Some(archive) = tenant.archive.clone() else {
+            continue;
+        };
+        let verified = tokio::task::spawn_blocking(move || verify_archive(&archive))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+        if let Err(e) = verified {
+            tracing::warn!(
+                tenant_id = tenant.id.as_str(),
+                error = %e,
+                "Archive of closed tenant is unreadable; not purging it"
+            );
+            continue;
+        }
+
+        if registry.tenant_interactor.purge(&tenant, now).await? {
+            tracing::info!(tenant_id = tenant.id.as_str(), "Purged closed tenant");
+            purged.push(tenant.id);
+        }
+    }
+    Ok(purged)
+}
+
+/// Runs [`archive_tenants`] every `tenants.archive_interval` until shutdown,
+/// or not at all if it is 0. Safe to run in every replica: only the first
+/// archive of a tenant is recorded, and the others are removed.
+pub fn spawn_archiver(registry: Arc<Registry>, shutdown: Shutdown) {
+    if registry.tenant_config.archive_dir.is_relative() {
+        tracing::warn!(
+            archive_dir = %registry.tenant_config.archive_dir.display(),
+            "tenants.archive_dir is relative; replicas only purge tenants whose archive they can read"
+        );
+    }
+    let interval = registry.tenant_config.archive_interval;
+    spawn_every(interval, shutdown, move || {
+        let registry = registry.clone();
+        async move {
+            if let Err(e) = archive_tenants(&registry).await {
+                tracing::warn!(error = %e, "Archiving closed tenants failed");
+            }
+        }
+    });
+}
+
+/// Runs [`purge_tenants`] every `tenants.purge_interval` until shutdown, or
+/// not at all if it is 0. Safe to run in every replica: each tenant is
+/// deleted by exactly one `DELETE`.
+pub fn spawn_purger(registry: Arc<Registry>, shutdown: Shutdown) {
+    let interval = registry.tenant_config.purge_interval;
+    spawn_every(interval, shutdown, move || {
+        let registry = registry.clone();
+        async move {
+            if let Err(e) = purge_tenants(&registry).await {
+                tracing::warn!(error = %e, "Purging closed tenants failed");
+            }
+        }
+    });
+}
+
+fn spawn_every<F, Fut>(interval: Duration, shutdown: Shutdown, mut job: F)
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    if interval.is_zero() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.requested() => return,
+            }
+            job().await;
+        }
+    });
+}
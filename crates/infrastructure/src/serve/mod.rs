@@ -0,0 +1,129 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::routing::future::RouteFuture;
+use axum::Router;
+use tonic_web::GrpcWebLayer;
+use tower::Service;
+
+use oxidize_domain::DomainError;
+
+use crate::auth::AuthLayer;
+use crate::cors::cors_layer;
+use crate::environment::Environment;
+use crate::metrics::GrpcMetricsLayer;
+use crate::ratelimit::RateLimitLayer;
+use crate::registry::Registry;
+use crate::shutdown::Shutdown;
+use crate::{gateway, grpc, http, otel};
+
+/// Runs the HTTP and gRPC servers side by side on their own ports, sharing
+/// one `Registry` (and therefore one connection pool).
+pub async fn run_server(
+    http_port: u16,
+    grpc_port: u16,
+    registry: Arc<Registry>,
+    shutdown: Shutdown,
+    env: &Environment,
+) -> anyhow::Result<()> {
+    tokio::try_join!(
+        http::run_http_server(http_port, registry.clone(), shutdown.clone(), env),
+        grpc::run_grpc_server(grpc_port, registry, shutdown, env),
+    )?;
+
+    Ok(())
+}
+
+/// Serves HTTP/1.1 JSON, gRPC and gRPC-Web on a single port, routing each
+/// request by its `content-type`.
+pub async fn run_multiplexed_server(
+    port: u16,
+    registry: Arc<Registry>,
+    shutdown: Shutdown,
+    env: &Environment,
+) -> anyhow::Result<()> {
+    let rate_limit = RateLimitLayer::new(registry.rate_limiter.clone());
+    let per_ip = RateLimitLayer::per_ip(registry.rate_limiter.clone());
+    let auth = AuthLayer::new(registry.clone());
+    let multiplex = Multiplex {
+        http: http::router(registry.clone(), &env.features)?,
+        grpc: grpc::routes(registry, shutdown.clone(), env.features.grpc_reflection)?
+            .into_axum_router()
+            .layer(rate_limit)
+            .layer(auth)
+            .layer(per_ip)
+            .layer(GrpcMetricsLayer)
+            .layer(GrpcWebLayer::new())
+            .layer(otel::grpc_trace_layer()),
+    };
+    let mut app = Router::new().fallback_service(multiplex);
+    if let Some(cors) = cors_layer(&env.server.cors)? {
+        app = app.layer(cors);
+    }
+
+    let addr = format!("0.0.0.0:{}", port);
+    tracing::info!("Starting HTTP and gRPC server on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let signal = shutdown.clone();
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server =
+        axum::serve(listener, app).with_graceful_shutdown(async move { signal.requested().await });
+    shutdown.drain(async { Ok(server.await?) }).await?;
+
+    tracing::info!("HTTP and gRPC server stopped");
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Multiplex {
+    http: Router,
+    grpc: Router,
+}
+
+/// Whether `request` is gRPC (or gRPC-Web) rather than plain HTTP.
+pub(crate) fn is_grpc<B>(request: &axum::http::Request<B>) -> bool {
+    request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/grpc"))
+}
+
+/// `err` as a response in the caller's protocol: a gRPC status, or the
+/// `/api/v1` or gateway error body for HTTP. For middleware that refuses a
+/// request before it is routed.
+pub(crate) fn refuse<B>(request: &axum::http::Request<B>, err: DomainError) -> Response {
+    if is_grpc(request) {
+        grpc::to_status(err).into_http()
+    } else if request.uri().path().starts_with("/api/") {
+        http::ApiError::from(err).into_response()
+    } else {
+        gateway::Error::from(grpc::to_status(err)).into_response()
+    }
+}
+
+impl Service<Request<Body>> for Multiplex {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = RouteFuture<Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Routers are always ready.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        if is_grpc(&request) {
+            self.grpc.call(request)
+        } else {
+            self.http.call(request)
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Shared shutdown signal for the servers in this process.
+///
+/// Servers stop accepting new connections once `requested` resolves and are
+/// given `drain_timeout` to finish in-flight requests before being dropped.
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+    drain_timeout: Duration,
+}
+
+impl Shutdown {
+    pub fn new(drain_timeout: Duration) -> Self {
+        let (tx, _) = watch::channel(false);
+        Self {
+            tx: Arc::new(tx),
+            drain_timeout,
+        }
+    }
+
+    /// Creates a handle that is triggered by SIGINT or SIGTERM.
+    pub fn install(drain_timeout: Duration) -> Self {
+        let shutdown = Self::new(drain_timeout);
+        let trigger = shutdown.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            tracing::info!("Shutdown signal received");
+            trigger.trigger();
+        });
+        shutdown
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub async fn requested(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives as long as `self`, so this only returns once triggered.
+        let _ = rx.wait_for(|requested| *requested).await;
+    }
+
+    /// Runs a server future that shuts down gracefully on `requested`, and
+    /// abandons it if draining takes longer than the configured timeout.
+    pub async fn drain<F>(&self, server: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = anyhow::Result<()>>,
+    {
+        tokio::pin!(server);
+        tokio::select! {
+            result = &mut server => return result,
+            _ = self.requested() => {}
+        }
+
+        tracing::info!(
+            "Draining in-flight requests for up to {:?}",
+            self.drain_timeout
+        );
+        match tokio::time::timeout(self.drain_timeout, server).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::warn!("Drain timeout elapsed; closing remaining connections");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!("Failed to install SIGTERM handler: {:?}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_returns_when_server_finishes() {
+        let shutdown = Shutdown::new(Duration::from_secs(5));
+        let server = {
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown.requested().await;
+                Ok(())
+            }
+        };
+
+        shutdown.trigger();
+        assert!(shutdown.drain(server).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_after_timeout() {
+        let shutdown = Shutdown::new(Duration::from_millis(20));
+        shutdown.trigger();
+
+        let stuck = std::future::pending::<anyhow::Result<()>>();
+        let result = tokio::time::timeout(Duration::from_secs(5), shutdown.drain(stuck)).await;
+        assert!(matches!(result, Ok(Ok(()))));
+    }
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use oxidize_domain::{ApiKeyId, ApiKeyScope, TenantId};
+
+#[derive(Debug)]
+pub struct CreateApiKeyInput {
+    pub tenant_id: TenantId,
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Never expires if unset.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct ListApiKeyInput {
+    pub tenant_id: TenantId,
+}
+
+#[derive(Debug)]
+pub struct RevokeApiKeyInput {
+    pub tenant_id: TenantId,
+    pub id: ApiKeyId,
+}
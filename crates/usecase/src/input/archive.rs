@@ -0,0 +1,22 @@
+use oxidize_domain::{Staff, Tenant, TenantId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportIdMode {
+    /// Keep the archived IDs; fails if the tenant already exists.
+    #[default]
+    Keep,
+    /// Assign fresh IDs to the tenant, its tags and its staff.
+    Remap,
+}
+
+#[derive(Debug)]
+pub struct ExportTenantInput {
+    pub id: TenantId,
+}
+
+#[derive(Debug)]
+pub struct ImportTenantInput {
+    pub tenant: Tenant,
+    pub staff: Vec<Staff>,
+    pub id_mode: ImportIdMode,
+}
@@ -0,0 +1,7 @@
+/// A key the caller sent to make a create safe to retry.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    pub key: String,
+    /// Who sent it, e.g. the authenticated actor; empty for anonymous callers.
+    pub caller: String,
+}
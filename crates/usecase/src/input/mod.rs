@@ -1,5 +1,11 @@
+mod api_key;
+mod archive;
+mod idempotency;
 mod staff;
 mod tenant;
 
+pub use api_key::*;
+pub use archive::*;
+pub use idempotency::*;
 pub use staff::*;
 pub use tenant::*;
@@ -1,5 +1,7 @@
 use oxidize_domain::{StaffId, StaffRole, TenantId};
 
+use super::IdempotencyKey;
+
 #[derive(Debug)]
 pub struct CreateStaffInput {
     pub tenant_id: TenantId,
@@ -8,6 +10,7 @@ pub struct CreateStaffInput {
     pub display_name: String,
     pub image_path: String,
     pub email: String,
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
 #[derive(Debug)]
@@ -36,4 +39,6 @@ pub struct ListStaffInput {
 #[derive(Debug)]
 pub struct DeleteStaffInput {
     pub id: StaffId,
+    /// Succeeds when the staff is already gone, for idempotent callers.
+    pub allow_missing: bool,
 }
@@ -1,8 +1,13 @@
-use oxidize_domain::TenantId;
+use chrono::Duration;
+use oxidize_domain::{TenantId, TenantTagType};
+
+use super::IdempotencyKey;
 
 #[derive(Debug)]
 pub struct CreateTenantInput {
     pub name: String,
+    pub tags: Vec<TenantTagType>,
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
 #[derive(Debug)]
@@ -25,4 +30,20 @@ pub struct ListTenantInput {
 #[derive(Debug)]
 pub struct DeleteTenantInput {
     pub id: TenantId,
+    /// Succeeds when the tenant is already gone, for idempotent callers.
+    pub allow_missing: bool,
+    /// Deletes the tenant even though it still has staff, and them with it.
+    pub force: bool,
+}
+
+#[derive(Debug)]
+pub struct CloseTenantInput {
+    pub id: TenantId,
+    /// How long the closed tenant is kept before it is purged.
+    pub retention: Duration,
+}
+
+#[derive(Debug)]
+pub struct ReopenTenantInput {
+    pub id: TenantId,
 }
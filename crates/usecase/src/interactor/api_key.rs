@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use oxidize_domain::{
+    errors, ApiKey, ApiKeyRepository, GetTenantQuery, Result, TenantId, TenantRepository,
+};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::input::{CreateApiKeyInput, ListApiKeyInput, RevokeApiKeyInput};
+use crate::output::CreateApiKeyOutput;
+
+/// Starts every key, so they are easy to recognize, e.g. by secret scanners.
+const KEY_PREFIX: &str = "oxk_";
+
+/// Characters of a key kept in `ApiKey::prefix`.
+const DISPLAY_PREFIX_LEN: usize = KEY_PREFIX.len() + 8;
+
+/// `last_used_at` is only written again once it is this old, so a busy key
+/// does not cost a write per request.
+const TOUCH_INTERVAL: Duration = Duration::minutes(1);
+
+/// A new key: the prefix followed by 256 random bits.
+fn generate_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", KEY_PREFIX, hex::encode(bytes))
+}
+
+/// Keys are random enough that a fast hash cannot be brute-forced.
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub struct ApiKeyInteractor<R: ApiKeyRepository, T: TenantRepository> {
+    repository: Arc<R>,
+    tenant_repository: Arc<T>,
+}
+
+impl<R: ApiKeyRepository, T: TenantRepository> ApiKeyInteractor<R, T> {
+    pub fn new(repository: Arc<R>, tenant_repository: Arc<T>) -> Self {
+        Self {
+            repository,
+            tenant_repository,
+        }
+    }
+
+    /// Closed tenants cannot be given new keys.
+    async fn ensure_tenant_open(&self, tenant_id: &TenantId) -> Result<()> {
+        let query = GetTenantQuery {
+            id: Some(tenant_id.clone()),
+            ..Default::default()
+        };
+        match self.tenant_repository.get(query).await? {
+            Some(tenant) if tenant.is_closed() => Err(errors::tenant_closed()),
+            Some(_) => Ok(()),
+            None => Err(errors::tenant_not_found()),
+        }
+    }
+
+    pub async fn create(&self, input: CreateApiKeyInput) -> Result<CreateApiKeyOutput> {
+        let now = Utc::now();
+        if input.name.trim().is_empty()
+            || input.scopes.is_empty()
+            || input.expires_at.is_some_and(|e| e <= now)
+        {
+            return Err(errors::invalid_argument());
+        }
+        self.ensure_tenant_open(&input.tenant_id).await?;
+
+        let mut scopes = input.scopes;
+        scopes.sort_by_key(|s| s.as_str());
+        scopes.dedup();
+
+        let key = generate_key();
+        let api_key = ApiKey::new(
+            input.tenant_id,
+            input.name,
+            key[..DISPLAY_PREFIX_LEN].to_string(),
+            hash_key(&key),
+            scopes,
+            input.expires_at,
+            now,
+        );
+        self.repository.create(&api_key).await?;
+        Ok(CreateApiKeyOutput { api_key, key })
+    }
+
+    pub async fn list(&self, input: ListApiKeyInput) -> Result<Vec<ApiKey>> {
+        self.repository.list(&input.tenant_id).await
+    }
+
+    /// Revoking a revoked key succeeds and leaves `revoked_at` as it was.
+    pub async fn revoke(&self, input: RevokeApiKeyInput) -> Result<ApiKey> {
+        self.repository
+            .revoke(&input.tenant_id, &input.id, Utc::now())
+            .await?
+            .ok_or_else(errors::api_key_not_found)
+    }
+
+    /// The active key `key` stands for; unknown, revoked and expired keys,
+    /// and the keys of closed tenants, are all refused alike.
+    pub async fn authenticate(&self, key: &str) -> Result<ApiKey> {
+        if !key.starts_with(KEY_PREFIX) {
+            return Err(errors::unauthenticated());
+        }
+
+        let now = Utc::now();
+        let mut api_key = self
+            .repository
+            .find_by_hash(&hash_key(key))
+            .await?
+            .filter(|k| k.is_active(now))
+            .ok_or_else(errors::unauthenticated)?;
+
+        if api_key
+            .last_used_at
+            .is_none_or(|t| now - t >= TOUCH_INTERVAL)
+        {
+            self.repository.touch(&api_key.id, now).await?;
+            api_key.last_used_at = Some(now);
+        }
+        Ok(api_key)
+    }
+}
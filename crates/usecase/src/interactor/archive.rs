@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use oxidize_domain::{
+    errors, GetTenantQuery, ListStaffQuery, Result, StaffId, StaffRepository, TenantId,
+    TenantRepository, TenantTagId,
+};
+
+use crate::input::{ExportTenantInput, ImportIdMode, ImportTenantInput};
+use crate::output::{ExportTenantOutput, ImportTenantOutput};
+
+pub struct ArchiveInteractor<T: TenantRepository, S: StaffRepository> {
+    tenant_repository: Arc<T>,
+    staff_repository: Arc<S>,
+}
+
+impl<T: TenantRepository, S: StaffRepository> ArchiveInteractor<T, S> {
+    pub fn new(tenant_repository: Arc<T>, staff_repository: Arc<S>) -> Self {
+        Self {
+            tenant_repository,
+            staff_repository,
+        }
+    }
+
+    /// Reads from the primary, so a tenant exported right after it was
+    /// closed is captured as it was at closing.
+    pub async fn export_tenant(&self, input: ExportTenantInput) -> Result<ExportTenantOutput> {
+        let query = GetTenantQuery {
+            id: Some(input.id.clone()),
+            consistent: true,
+        };
+        let tenant = self
+            .tenant_repository
+            .get(query)
+            .await?
+            .ok_or_else(errors::tenant_not_found)?;
+
+        let query = ListStaffQuery {
+            tenant_id: Some(input.id),
+            consistent: true,
+            ..Default::default()
+        };
+        let staff = self.staff_repository.list(query).await?;
+
+        Ok(ExportTenantOutput { tenant, staff })
+    }
+
+    pub async fn import_tenant(&self, input: ImportTenantInput) -> Result<ImportTenantOutput> {
+        let ImportTenantInput {
+            mut tenant,
+            mut staff,
+            id_mode,
+        } = input;
+
+        if staff.iter().any(|s| s.tenant_id != tenant.id) {
+            return Err(errors::invalid_argument());
+        }
+        // Archives written on closing record the tenant as closed; restoring
+        // one brings the tenant back.
+        if tenant.is_closed() {
+            tenant.reopen(Utc::now());
+        }
+
+        match id_mode {
+            ImportIdMode::Keep => {
+                let query = GetTenantQuery {
+                    id: Some(tenant.id.clone()),
+                    consistent: true,
+                };
+                if self.tenant_repository.get(query).await?.is_some() {
+                    return Err(errors::tenant_already_exists());
+                }
+            }
+            ImportIdMode::Remap => {
+                tenant.id = TenantId::new();
+                for tag in &mut tenant.tags {
+                    tag.id = TenantTagId::new();
+                }
+                for s in &mut staff {
+                    s.id = StaffId::new();
+                    s.tenant_id = tenant.id.clone();
+                }
+            }
+        }
+
+        self.tenant_repository.import(&tenant, &staff).await?;
+
+        Ok(ImportTenantOutput { tenant, staff })
+    }
+}
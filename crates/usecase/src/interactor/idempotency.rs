@@ -0,0 +1,100 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use oxidize_domain::{errors, IdempotencyRecord, IdempotencyRepository, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::input::IdempotencyKey;
+
+/// Longest idempotency key accepted, in bytes.
+const MAX_KEY_LEN: usize = 255;
+
+/// A hash of the fields of a request, each length-prefixed so that moving
+/// characters between fields changes it.
+pub(crate) fn fingerprint(fields: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for field in fields {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Makes creates safe to retry: the result of the first request with an
+/// idempotency key is stored for `ttl` and replayed to its retries. The
+/// first request holds the key for `lease`; should it be abandoned, a retry
+/// takes over once that lapses.
+pub struct Idempotency<R: IdempotencyRepository> {
+    repository: Arc<R>,
+    ttl: Duration,
+    lease: Duration,
+}
+
+impl<R: IdempotencyRepository> Idempotency<R> {
+    pub fn new(repository: Arc<R>, ttl: Duration, lease: Duration) -> Self {
+        Self {
+            repository,
+            ttl,
+            lease,
+        }
+    }
+
+    /// Runs `create` unless a request with the same `key` already did, in
+    /// which case its result is returned instead. A key reused for a request
+    /// with another `fingerprint` is refused, as is a retry racing the first
+    /// request. Failed requests are not stored, so they can be retried.
+    pub(crate) async fn run<T, F>(
+        &self,
+        operation: &str,
+        key: Option<IdempotencyKey>,
+        fingerprint: String,
+        create: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T>>,
+    {
+        let Some(key) = key else {
+            return create.await;
+        };
+        if key.key.is_empty() || key.key.len() > MAX_KEY_LEN {
+            return Err(errors::invalid_argument());
+        }
+
+        let record = IdempotencyRecord::new(
+            operation.to_string(),
+            key.caller,
+            key.key,
+            fingerprint,
+            self.ttl,
+            self.lease,
+            Utc::now(),
+        );
+        if let Some(existing) = self.repository.claim(&record).await? {
+            if existing.fingerprint != record.fingerprint {
+                return Err(errors::idempotency_key_reused());
+            }
+            let response = existing
+                .response
+                .ok_or_else(errors::idempotency_key_in_use)?;
+            return serde_json::from_str(&response).map_err(|_| errors::internal());
+        }
+
+        match create.await {
+            Ok(value) => {
+                let response = serde_json::to_string(&value).map_err(|_| errors::internal())?;
+                // The create went through either way; should this fail,
+                // retries are refused as in progress until the lease lapses.
+                let _ = self.repository.complete(&record, &response).await;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self.repository.release(&record).await;
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,20 +1,48 @@
 use std::sync::Arc;
 
 use chrono::Utc;
-use oxidize_domain::{errors, GetStaffQuery, ListStaffQuery, Result, Staff, StaffRepository};
+use oxidize_domain::{
+    errors, GetStaffQuery, GetTenantQuery, IdempotencyRepository, ListStaffQuery, Result, Staff,
+    StaffRepository, TenantId, TenantRepository,
+};
 
 use crate::input::{
     CreateStaffInput, DeleteStaffInput, GetStaffInput, ListStaffInput, UpdateStaffInput,
 };
 use crate::output::ListStaffOutput;
 
-pub struct StaffInteractor<R: StaffRepository> {
+use super::idempotency::{fingerprint, Idempotency};
+
+pub struct StaffInteractor<R: StaffRepository, T: TenantRepository, I: IdempotencyRepository> {
     repository: Arc<R>,
+    tenant_repository: Arc<T>,
+    idempotency: Arc<Idempotency<I>>,
 }
 
-impl<R: StaffRepository> StaffInteractor<R> {
-    pub fn new(repository: Arc<R>) -> Self {
-        Self { repository }
+impl<R: StaffRepository, T: TenantRepository, I: IdempotencyRepository> StaffInteractor<R, T, I> {
+    pub fn new(
+        repository: Arc<R>,
+        tenant_repository: Arc<T>,
+        idempotency: Arc<Idempotency<I>>,
+    ) -> Self {
+        Self {
+            repository,
+            tenant_repository,
+            idempotency,
+        }
+    }
+
+    /// Staff of a closed tenant can no longer be read, listed or changed.
+    async fn ensure_tenant_open(&self, tenant_id: &TenantId) -> Result<()> {
+        let query = GetTenantQuery {
+            id: Some(tenant_id.clone()),
+            ..Default::default()
+        };
+        match self.tenant_repository.get(query).await? {
+            Some(tenant) if tenant.is_closed() => Err(errors::tenant_closed()),
+            Some(_) => Ok(()),
+            None => Err(errors::tenant_not_found()),
+        }
     }
 
     pub async fn get(&self, input: GetStaffInput) -> Result<Option<Staff>> {
@@ -22,22 +50,57 @@ impl<R: StaffRepository> StaffInteractor<R> {
             id: input.id,
             auth_uid: input.auth_uid,
             with_tenant: input.with_tenant,
+            ..Default::default()
         };
-        self.repository.get(query).await
+        let staff = self.repository.get(query).await?;
+        if let Some(staff) = &staff {
+            match &staff.tenant {
+                Some(tenant) if tenant.is_closed() => return Err(errors::tenant_closed()),
+                Some(_) => {}
+                None => self.ensure_tenant_open(&staff.tenant_id).await?,
+            }
+        }
+        Ok(staff)
     }
 
     pub async fn list(&self, input: ListStaffInput) -> Result<ListStaffOutput> {
+        if let Some(tenant_id) = &input.tenant_id {
+            self.ensure_tenant_open(tenant_id).await?;
+        }
+
+        // Listing across tenants leaves out the closed ones instead.
         let query = ListStaffQuery {
+            open_tenants_only: input.tenant_id.is_none(),
             tenant_id: input.tenant_id,
             limit: input.limit,
             offset: input.offset,
+            ..Default::default()
         };
         let staff = self.repository.list(query.clone()).await?;
         let total_count = self.repository.count(query).await?;
         Ok(ListStaffOutput { staff, total_count })
     }
 
-    pub async fn create(&self, input: CreateStaffInput) -> Result<Staff> {
+    /// Retries with the same idempotency key get the staff created first.
+    pub async fn create(&self, mut input: CreateStaffInput) -> Result<Staff> {
+        let fingerprint = fingerprint(&[
+            input.tenant_id.as_str(),
+            input.role.as_str(),
+            &input.auth_uid,
+            &input.display_name,
+            &input.image_path,
+            &input.email,
+        ]);
+        let key = input.idempotency_key.take();
+
+        self.idempotency
+            .run("CreateStaff", key, fingerprint, self.insert(input))
+            .await
+    }
+
+    async fn insert(&self, input: CreateStaffInput) -> Result<Staff> {
+        self.ensure_tenant_open(&input.tenant_id).await?;
+
         let now = Utc::now();
         let staff = Staff::new(
             input.tenant_id,
@@ -55,6 +118,7 @@ impl<R: StaffRepository> StaffInteractor<R> {
     pub async fn update(&self, input: UpdateStaffInput) -> Result<Staff> {
         let query = GetStaffQuery {
             id: Some(input.id.clone()),
+            consistent: true,
             ..Default::default()
         };
         let mut staff = self
@@ -62,6 +126,7 @@ impl<R: StaffRepository> StaffInteractor<R> {
             .get(query)
             .await?
             .ok_or_else(errors::staff_not_found)?;
+        self.ensure_tenant_open(&staff.tenant_id).await?;
 
         if let Some(role) = input.role {
             staff.role = role;
@@ -77,11 +142,27 @@ impl<R: StaffRepository> StaffInteractor<R> {
         }
         staff.updated_at = Utc::now();
 
-        self.repository.update(&staff).await?;
+        if !self.repository.update(&staff).await? {
+            // Deleted since it was read.
+            return Err(errors::staff_not_found());
+        }
         Ok(staff)
     }
 
     pub async fn delete(&self, input: DeleteStaffInput) -> Result<()> {
-        self.repository.delete(&input.id).await
+        let query = GetStaffQuery {
+            id: Some(input.id.clone()),
+            consistent: true,
+            ..Default::default()
+        };
+        if let Some(staff) = self.repository.get(query).await? {
+            self.ensure_tenant_open(&staff.tenant_id).await?;
+        }
+
+        let deleted = self.repository.delete(&input.id).await?;
+        if !deleted && !input.allow_missing {
+            return Err(errors::staff_not_found());
+        }
+        Ok(())
     }
 }
@@ -1,24 +1,56 @@
 use std::sync::Arc;
 
-use chrono::Utc;
-use oxidize_domain::{errors, GetTenantQuery, ListTenantQuery, Result, Tenant, TenantRepository};
+use chrono::{DateTime, Utc};
+use oxidize_domain::{
+    errors, GetTenantQuery, IdempotencyRepository, ListTenantQuery, Result, Tenant, TenantId,
+    TenantRepository, TenantTag, TenantTagType,
+};
 
 use crate::input::{
-    CreateTenantInput, DeleteTenantInput, GetTenantInput, ListTenantInput, UpdateTenantInput,
+    CloseTenantInput, CreateTenantInput, DeleteTenantInput, GetTenantInput, ListTenantInput,
+    ReopenTenantInput, UpdateTenantInput,
 };
 use crate::output::ListTenantOutput;
 
-pub struct TenantInteractor<R: TenantRepository> {
+use super::idempotency::{fingerprint, Idempotency};
+
+pub struct TenantInteractor<R: TenantRepository, I: IdempotencyRepository> {
     repository: Arc<R>,
+    idempotency: Arc<Idempotency<I>>,
 }
 
-impl<R: TenantRepository> TenantInteractor<R> {
-    pub fn new(repository: Arc<R>) -> Self {
-        Self { repository }
+impl<R: TenantRepository, I: IdempotencyRepository> TenantInteractor<R, I> {
+    pub fn new(repository: Arc<R>, idempotency: Arc<Idempotency<I>>) -> Self {
+        Self {
+            repository,
+            idempotency,
+        }
+    }
+
+    async fn get_for_update(&self, id: TenantId) -> Result<Tenant> {
+        let query = GetTenantQuery {
+            id: Some(id),
+            consistent: true,
+        };
+        self.repository
+            .get(query)
+            .await?
+            .ok_or_else(errors::tenant_not_found)
+    }
+
+    async fn save(&self, tenant: &Tenant) -> Result<()> {
+        if !self.repository.update(tenant).await? {
+            // Deleted since it was read.
+            return Err(errors::tenant_not_found());
+        }
+        Ok(())
     }
 
     pub async fn get(&self, input: GetTenantInput) -> Result<Option<Tenant>> {
-        let query = GetTenantQuery { id: Some(input.id) };
+        let query = GetTenantQuery {
+            id: Some(input.id),
+            ..Default::default()
+        };
         self.repository.get(query).await
     }
 
@@ -35,31 +67,281 @@ impl<R: TenantRepository> TenantInteractor<R> {
         })
     }
 
+    /// Retries with the same idempotency key get the tenant created first.
     pub async fn create(&self, input: CreateTenantInput) -> Result<Tenant> {
+        let mut fields = vec![input.name.as_str()];
+        fields.extend(input.tags.iter().map(|t| t.as_str()));
+        let fingerprint = fingerprint(&fields);
+
+        self.idempotency
+            .run(
+                "CreateTenant",
+                input.idempotency_key,
+                fingerprint,
+                self.insert(input.name, input.tags),
+            )
+            .await
+    }
+
+    async fn insert(&self, name: String, tags: Vec<TenantTagType>) -> Result<Tenant> {
+        if tags.iter().any(|t| !t.is_valid()) {
+            return Err(errors::invalid_argument());
+        }
+
         let now = Utc::now();
-        let tenant = Tenant::new(input.name, now);
+        let mut tenant = Tenant::new(name, now);
+        for tag_type in tags {
+            tenant.add_tag(TenantTag::new(tag_type, now));
+        }
         self.repository.create(&tenant).await?;
         Ok(tenant)
     }
 
     pub async fn update(&self, input: UpdateTenantInput) -> Result<Tenant> {
-        let query = GetTenantQuery {
-            id: Some(input.id.clone()),
-        };
-        let mut tenant = self
-            .repository
-            .get(query)
-            .await?
-            .ok_or_else(errors::tenant_not_found)?;
+        let mut tenant = self.get_for_update(input.id).await?;
+        if tenant.is_closed() {
+            return Err(errors::tenant_closed());
+        }
 
         let now = Utc::now();
         tenant.update(input.name, now);
 
-        self.repository.update(&tenant).await?;
+        self.save(&tenant).await?;
         Ok(tenant)
     }
 
+    /// Refuses while the tenant has staff, unless `force` is set, rather
+    /// than letting the foreign key delete them silently.
     pub async fn delete(&self, input: DeleteTenantInput) -> Result<()> {
-        self.repository.delete(&input.id).await
+        let deleted = self.repository.delete(&input.id, input.force).await?;
+        if !deleted && !input.allow_missing {
+            return Err(errors::tenant_not_found());
+        }
+        Ok(())
+    }
+
+    /// Cuts off access to the tenant's staff and schedules the tenant for
+    /// archiving, then purging once `retention` has passed.
+    pub async fn close(&self, input: CloseTenantInput) -> Result<Tenant> {
+        let mut tenant = self.get_for_update(input.id).await?;
+        if tenant.is_closed() {
+            return Err(errors::tenant_closed());
+        }
+
+        tenant.close(input.retention, Utc::now());
+        self.save(&tenant).await?;
+        Ok(tenant)
+    }
+
+    /// Cancels a pending purge; a no-op for a tenant that is not closed.
+    pub async fn reopen(&self, input: ReopenTenantInput) -> Result<Tenant> {
+        let mut tenant = self.get_for_update(input.id).await?;
+        if !tenant.is_closed() {
+            return Ok(tenant);
+        }
+
+        tenant.reopen(Utc::now());
+        self.save(&tenant).await?;
+        Ok(tenant)
+    }
+
+    /// Closed tenants whose archive is yet to be written.
+    pub async fn unarchived(&self) -> Result<Vec<Tenant>> {
+        self.repository.list_unarchived().await
+    }
+
+    /// Records where the archive of `tenant`, as read when it was exported,
+    /// was written; `false` if it was reopened or archived meanwhile.
+    pub async fn record_archive(&self, tenant: &Tenant) -> Result<bool> {
+        self.repository.record_archive(tenant).await
+    }
+
+    /// Archived closed tenants due for purging by `now`.
+    pub async fn purgeable(&self, now: DateTime<Utc>) -> Result<Vec<Tenant>> {
+        self.repository.list_purgeable(now).await
+    }
+
+    /// Deletes, with its staff, a tenant from `purgeable` once its archive
+    /// was verified; `false` if it was reopened or purged meanwhile.
+    pub async fn purge(&self, tenant: &Tenant, now: DateTime<Utc>) -> Result<bool> {
+        self.repository.purge(tenant, now).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::Duration;
+    use oxidize_domain::{IdempotencyRecord, Staff};
+
+    use super::*;
+
+    /// Tenants in memory. With `deleted_after_read` set, reading a tenant
+    /// also deletes it, as another request could right after the read.
+    #[derive(Default)]
+    struct FakeTenants {
+        tenants: Mutex<Vec<Tenant>>,
+        deleted_after_read: AtomicBool,
+    }
+
+    #[async_trait]
+    impl TenantRepository for FakeTenants {
+        async fn get(&self, query: GetTenantQuery) -> Result<Option<Tenant>> {
+            let mut tenants = self.tenants.lock().unwrap();
+            let found = tenants.iter().find(|t| query.id.as_ref() == Some(&t.id));
+            let found = found.cloned();
+            if self.deleted_after_read.load(Ordering::SeqCst) {
+                tenants.retain(|t| query.id.as_ref() != Some(&t.id));
+            }
+            Ok(found)
+        }
+
+        async fn list(&self, _query: ListTenantQuery) -> Result<Vec<Tenant>> {
+            Ok(self.tenants.lock().unwrap().clone())
+        }
+
+        async fn count(&self, _query: ListTenantQuery) -> Result<u64> {
+            Ok(self.tenants.lock().unwrap().len() as u64)
+        }
+
+        async fn create(&self, tenant: &Tenant) -> Result<()> {
+            self.tenants.lock().unwrap().push(tenant.clone());
+            Ok(())
+        }
+
+        async fn import(&self, tenant: &Tenant, _staff: &[Staff]) -> Result<()> {
+            self.create(tenant).await
+        }
+
+        async fn update(&self, tenant: &Tenant) -> Result<bool> {
+            let mut tenants = self.tenants.lock().unwrap();
+            let found = tenants.iter_mut().find(|t| t.id == tenant.id);
+            Ok(found.map(|t| *t = tenant.clone()).is_some())
+        }
+
+        async fn delete(&self, id: &TenantId, _force: bool) -> Result<bool> {
+            let mut tenants = self.tenants.lock().unwrap();
+            let before = tenants.len();
+            tenants.retain(|t| t.id != *id);
+            Ok(tenants.len() < before)
+        }
+
+        async fn list_unarchived(&self) -> Result<Vec<Tenant>> {
+            Ok(Vec::new())
+        }
+
+        async fn record_archive(&self, tenant: &Tenant) -> Result<bool> {
+            self.update(tenant).await
+        }
+
+        async fn list_purgeable(&self, _now: DateTime<Utc>) -> Result<Vec<Tenant>> {
+            Ok(Vec::new())
+        }
+
+        async fn purge(&self, tenant: &Tenant, _now: DateTime<Utc>) -> Result<bool> {
+            self.delete(&tenant.id, true).await
+        }
+    }
+
+    /// Never called: the tests create without idempotency keys.
+    struct NoIdempotency;
+
+    #[async_trait]
+    impl IdempotencyRepository for NoIdempotency {
+        async fn claim(&self, _record: &IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+            Ok(None)
+        }
+
+        async fn complete(&self, _record: &IdempotencyRecord, _response: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn release(&self, _record: &IdempotencyRecord) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn interactor() -> (
+        Arc<FakeTenants>,
+        TenantInteractor<FakeTenants, NoIdempotency>,
+    ) {
+        let repository = Arc::new(FakeTenants::default());
+        let idempotency = Idempotency::new(
+            Arc::new(NoIdempotency),
+            Duration::hours(24),
+            Duration::seconds(60),
+        );
+        let interactor = TenantInteractor::new(repository.clone(), Arc::new(idempotency));
+        (repository, interactor)
+    }
+
+    async fn create(interactor: &TenantInteractor<FakeTenants, NoIdempotency>) -> Tenant {
+        let input = CreateTenantInput {
+            name: "Acme".to_string(),
+            tags: Vec::new(),
+            idempotency_key: None,
+        };
+        interactor.create(input).await.unwrap()
+    }
+
+    fn delete_input(id: &TenantId, allow_missing: bool) -> DeleteTenantInput {
+        DeleteTenantInput {
+            id: id.clone(),
+            allow_missing,
+            force: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_of_a_missing_tenant_is_not_found_unless_allowed() {
+        let (repository, interactor) = interactor();
+        let tenant = create(&interactor).await;
+
+        interactor
+            .delete(delete_input(&tenant.id, false))
+            .await
+            .unwrap();
+        assert!(repository.tenants.lock().unwrap().is_empty());
+
+        let err = interactor
+            .delete(delete_input(&tenant.id, false))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, errors::tenant_not_found().code);
+        interactor
+            .delete(delete_input(&tenant.id, true))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_of_a_tenant_deleted_meanwhile_is_not_found() {
+        let (repository, interactor) = interactor();
+        let tenant = create(&interactor).await;
+        repository.deleted_after_read.store(true, Ordering::SeqCst);
+
+        let input = UpdateTenantInput {
+            id: tenant.id.clone(),
+            name: Some("Renamed".to_string()),
+        };
+        let err = interactor.update(input).await.unwrap_err();
+        assert_eq!(err.code, errors::tenant_not_found().code);
+    }
+
+    #[tokio::test]
+    async fn test_close_of_a_tenant_deleted_meanwhile_is_not_found() {
+        let (repository, interactor) = interactor();
+        let tenant = create(&interactor).await;
+        repository.deleted_after_read.store(true, Ordering::SeqCst);
+
+        let input = CloseTenantInput {
+            id: tenant.id,
+            retention: Duration::days(30),
+        };
+        let err = interactor.close(input).await.unwrap_err();
+        assert_eq!(err.code, errors::tenant_not_found().code);
     }
 }
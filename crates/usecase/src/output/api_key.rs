@@ -0,0 +1,8 @@
+use oxidize_domain::ApiKey;
+
+#[derive(Debug)]
+pub struct CreateApiKeyOutput {
+    pub api_key: ApiKey,
+    /// The key itself, which cannot be retrieved again.
+    pub key: String,
+}
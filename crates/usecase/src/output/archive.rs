@@ -0,0 +1,13 @@
+use oxidize_domain::{Staff, Tenant};
+
+#[derive(Debug)]
+pub struct ExportTenantOutput {
+    pub tenant: Tenant,
+    pub staff: Vec<Staff>,
+}
+
+#[derive(Debug)]
+pub struct ImportTenantOutput {
+    pub tenant: Tenant,
+    pub staff: Vec<Staff>,
+}
@@ -1,5 +1,9 @@
+mod api_key;
+mod archive;
 mod staff;
 mod tenant;
 
+pub use api_key::*;
+pub use archive::*;
 pub use staff::*;
 pub use tenant::*;
@@ -0,0 +1,2 @@
+-- Drop tenants table
+DROP TABLE IF EXISTS tenants;
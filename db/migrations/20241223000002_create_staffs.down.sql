@@ -0,0 +1,2 @@
+-- Drop staffs table
+DROP TABLE IF EXISTS staffs;
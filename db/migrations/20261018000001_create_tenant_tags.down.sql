@@ -0,0 +1,2 @@
+-- Drop tenant_tags table
+DROP TABLE IF EXISTS tenant_tags;
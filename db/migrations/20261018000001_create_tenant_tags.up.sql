@@ -0,0 +1,10 @@
+-- Create tenant_tags table
+CREATE TABLE IF NOT EXISTS tenant_tags (
+    id VARCHAR(36) PRIMARY KEY,
+    tenant_id VARCHAR(36) NOT NULL REFERENCES tenants(id) ON DELETE CASCADE,
+    tag_type VARCHAR(50) NOT NULL DEFAULT 'unknown',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tenant_tags_tenant_id ON tenant_tags(tenant_id);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_tenants_purge_after;
+
+ALTER TABLE tenants
+    DROP COLUMN IF EXISTS purge_after,
+    DROP COLUMN IF EXISTS closed_at;
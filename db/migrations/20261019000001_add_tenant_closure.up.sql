@@ -0,0 +1,6 @@
+-- Closed tenants keep their data until purge_after, then are deleted
+ALTER TABLE tenants
+    ADD COLUMN closed_at TIMESTAMPTZ,
+    ADD COLUMN purge_after TIMESTAMPTZ;
+
+CREATE INDEX idx_tenants_purge_after ON tenants(purge_after) WHERE purge_after IS NOT NULL;
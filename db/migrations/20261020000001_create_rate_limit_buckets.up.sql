@@ -0,0 +1,8 @@
+-- Token buckets shared by all replicas when rate_limit.store = "postgres".
+-- A bucket is full again at full_at; rows past it carry no state.
+CREATE UNLOGGED TABLE IF NOT EXISTS rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    full_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_rate_limit_buckets_full_at ON rate_limit_buckets(full_at);
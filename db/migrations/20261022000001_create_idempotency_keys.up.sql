@@ -0,0 +1,13 @@
+-- Create idempotency_keys table; the results of creates, replayed to retries
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    operation VARCHAR(64) NOT NULL,
+    caller TEXT NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    fingerprint CHAR(64) NOT NULL,
+    response TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (operation, caller, key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
@@ -0,0 +1,12 @@
+-- Drop row-level security; the oxidize_tenant role is shared by the
+-- cluster's databases and is left in place
+DROP POLICY IF EXISTS tenant_isolation ON api_keys;
+DROP POLICY IF EXISTS tenant_isolation ON tenant_tags;
+DROP POLICY IF EXISTS tenant_isolation ON staffs;
+ALTER TABLE api_keys DISABLE ROW LEVEL SECURITY;
+ALTER TABLE tenant_tags DISABLE ROW LEVEL SECURITY;
+ALTER TABLE staffs DISABLE ROW LEVEL SECURITY;
+
+REVOKE ALL ON api_keys, tenant_tags, staffs, tenants FROM oxidize_tenant;
+REVOKE USAGE ON SCHEMA public FROM oxidize_tenant;
+DROP FUNCTION IF EXISTS enable_tenant_isolation(regclass);
@@ -0,0 +1,37 @@
+-- Row-level security on tenant-owned tables: roles other than their owner
+-- (the one running migrations) only see the rows of the tenant in the
+-- app.tenant_id setting, and none without it. Requests of API keys run as
+-- oxidize_tenant with it set for each transaction.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'oxidize_tenant') THEN
+        CREATE ROLE oxidize_tenant NOLOGIN;
+    END IF;
+END
+$$;
+
+-- Lets the servers, connected as this role, switch to it.
+GRANT oxidize_tenant TO CURRENT_USER;
+GRANT USAGE ON SCHEMA public TO oxidize_tenant;
+GRANT SELECT, UPDATE ON tenants TO oxidize_tenant;
+
+-- Confines a table with a tenant_id column to the current tenant. New
+-- tenant-owned tables call it in their migration.
+CREATE OR REPLACE FUNCTION enable_tenant_isolation(tbl regclass) RETURNS void
+LANGUAGE plpgsql AS $$
+BEGIN
+    EXECUTE format('ALTER TABLE %s ENABLE ROW LEVEL SECURITY', tbl);
+    EXECUTE format('DROP POLICY IF EXISTS tenant_isolation ON %s', tbl);
+    EXECUTE format(
+        'CREATE POLICY tenant_isolation ON %s '
+        'USING (tenant_id = current_setting(''app.tenant_id'', true)) '
+        'WITH CHECK (tenant_id = current_setting(''app.tenant_id'', true))',
+        tbl
+    );
+    EXECUTE format('GRANT SELECT, INSERT, UPDATE, DELETE ON %s TO oxidize_tenant', tbl);
+END
+$$;
+
+SELECT enable_tenant_isolation('staffs');
+SELECT enable_tenant_isolation('tenant_tags');
+SELECT enable_tenant_isolation('api_keys');
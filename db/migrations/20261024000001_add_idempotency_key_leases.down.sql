@@ -0,0 +1,2 @@
+-- Drop the lease of in-flight idempotency keys
+ALTER TABLE idempotency_keys DROP COLUMN IF EXISTS locked_until;
@@ -0,0 +1,3 @@
+-- Add a lease to in-flight idempotency keys, after which a retry may take
+-- over a key whose first request never completed
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ;
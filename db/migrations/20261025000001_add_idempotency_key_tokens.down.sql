@@ -0,0 +1,2 @@
+-- Drop the token of the request holding an idempotency key
+ALTER TABLE idempotency_keys DROP COLUMN IF EXISTS token;
@@ -0,0 +1,4 @@
+-- Identify the request holding an idempotency key, so one whose lease was
+-- taken over can no longer complete or release it
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS token VARCHAR(36) NOT NULL DEFAULT '';
+ALTER TABLE idempotency_keys ALTER COLUMN token DROP DEFAULT;
@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS idx_tenants_unarchived;
+
+ALTER TABLE tenants
+    DROP COLUMN IF EXISTS archived_at,
+    DROP COLUMN IF EXISTS archive_checksum,
+    DROP COLUMN IF EXISTS archive_location;
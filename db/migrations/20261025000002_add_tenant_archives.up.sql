@@ -0,0 +1,9 @@
+-- Closed tenants are archived in the background; a tenant is only purged
+-- once its archive is recorded here and verified
+ALTER TABLE tenants
+    ADD COLUMN archive_location TEXT,
+    ADD COLUMN archive_checksum CHAR(64),
+    ADD COLUMN archived_at TIMESTAMPTZ;
+
+CREATE INDEX idx_tenants_unarchived ON tenants(closed_at)
+    WHERE closed_at IS NOT NULL AND archived_at IS NULL;